@@ -5,7 +5,7 @@ use std::fs; // Keep fs for potential top-level directory creations if needed
 mod build_logic;
 
 // Use items from the new modules
-use build_logic::{paths, vendor_copier, comfyui_installer};
+use build_logic::{paths, vendor_copier, comfyui_installer, frontend_installer};
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=build.rs");
@@ -16,6 +16,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:warning=BUILD_RS: Rerun if archive_utils.rs changed.");
     println!("cargo:rerun-if-changed=build_logic/vendor_copier.rs");
     println!("cargo:warning=BUILD_RS: Rerun if vendor_copier.rs changed.");
+    println!("cargo:rerun-if-changed=build_logic/frontend_installer.rs");
+    // The runtime checks the bundled frontend against this version.
+    println!("cargo:rustc-env=COMFYUI_FRONTEND_VERSION={}", frontend_installer::COMFYUI_FRONTEND_VERSION);
 
     // Assume ComfyUI source is already in ../vendor/comfyui (relative to src-tauri)
     // This path will be derived more robustly using the paths module.
@@ -44,6 +47,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map_err(|e| format!("ComfyUI base installation failed: {}", e))?;
     eprintln!("cargo:warning=BUILD_RS_MAIN: ComfyUI base installation in source vendor directory ensured.");
 
+    // --- 1.6. Ensure the pinned ComfyUI frontend is in Source Vendor ---
+    eprintln!("cargo:warning=BUILD_RS_MAIN: Stage 1.6: Ensuring the ComfyUI frontend is bundled in source vendor directory.");
+    frontend_installer::ensure_comfyui_frontend_installed(&metamorphosis_app_dir)
+        .map_err(|e| format!("ComfyUI frontend installation failed: {}", e))?;
+    eprintln!("cargo:warning=BUILD_RS_MAIN: ComfyUI frontend bundled in source vendor directory.");
+
 
 
 
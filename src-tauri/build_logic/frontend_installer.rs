@@ -0,0 +1,67 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io,
+    path::Path,
+};
+use zip::ZipArchive;
+use super::paths::get_source_vendor_dir;
+
+// Pinned ComfyUI frontend release. ComfyUI serves it from `--front-end-root`, so launches don't
+// depend on GitHub being reachable. Bump together with the ComfyUI version in comfyui_installer.
+pub const COMFYUI_FRONTEND_VERSION: &str = "1.18.2";
+const COMFYUI_FRONTEND_RELEASES_URL: &str = "https://github.com/Comfy-Org/ComfyUI_frontend/releases/download";
+pub const FRONTEND_DIR_NAME: &str = "comfyui_frontend"; // Directory inside VENDOR_DIR_NAME, next to comfyui
+// Written after a successful extraction; holds the version that is in the directory.
+pub const FRONTEND_VERSION_MARKER: &str = ".frontend_version";
+
+/// Ensures the pinned frontend release is extracted into `vendor/comfyui_frontend`.
+/// Re-downloads when the directory holds a different version.
+pub fn ensure_comfyui_frontend_installed(metamorphosis_app_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let frontend_dir = get_source_vendor_dir(metamorphosis_app_dir).join(FRONTEND_DIR_NAME);
+    let marker_path = frontend_dir.join(FRONTEND_VERSION_MARKER);
+
+    let installed_version = fs::read_to_string(&marker_path).ok();
+    if installed_version.as_deref().map(str::trim) == Some(COMFYUI_FRONTEND_VERSION) && frontend_dir.join("index.html").exists() {
+        println!("cargo:warning=FRONTEND_INSTALLER: ComfyUI frontend v{} already present at {:?}. Skipping download.", COMFYUI_FRONTEND_VERSION, frontend_dir);
+        return Ok(());
+    }
+
+    let url = format!("{}/v{}/dist.zip", COMFYUI_FRONTEND_RELEASES_URL, COMFYUI_FRONTEND_VERSION);
+    println!("cargo:warning=FRONTEND_INSTALLER: Downloading ComfyUI frontend v{} from {}", COMFYUI_FRONTEND_VERSION, url);
+    let response = reqwest::blocking::get(&url)?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download ComfyUI frontend from {}: HTTP {}", url, response.status()).into());
+    }
+    let bytes = response.bytes()?;
+
+    // Extract into a fresh directory so files from an older release don't linger.
+    if frontend_dir.exists() {
+        fs::remove_dir_all(&frontend_dir)?;
+    }
+    fs::create_dir_all(&frontend_dir)?;
+    let mut archive = ZipArchive::new(io::Cursor::new(bytes))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(relative_path) = entry.enclosed_name() else {
+            println!("cargo:warning=FRONTEND_INSTALLER: Skipping entry with invalid path in zip: {}", entry.name());
+            continue;
+        };
+        let out_path = frontend_dir.join(relative_path);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&out_path)?)?;
+    }
+
+    if !frontend_dir.join("index.html").exists() {
+        return Err(format!("ComfyUI frontend archive from {} has no index.html at its root.", url).into());
+    }
+    fs::write(&marker_path, COMFYUI_FRONTEND_VERSION)?;
+    println!("cargo:warning=FRONTEND_INSTALLER: Extracted ComfyUI frontend v{} to {:?}", COMFYUI_FRONTEND_VERSION, frontend_dir);
+    Ok(())
+}
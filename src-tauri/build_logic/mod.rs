@@ -2,4 +2,5 @@
 pub mod archive_utils;
 pub mod paths;
 pub mod vendor_copier;
-pub mod comfyui_installer;
\ No newline at end of file
+pub mod comfyui_installer;
+pub mod frontend_installer;
\ No newline at end of file
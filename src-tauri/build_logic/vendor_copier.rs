@@ -5,6 +5,7 @@ use std::{
     error::Error,
 };
 use fs_extra::dir::{copy as fs_extra_copy, CopyOptions as FsExtraCopyOptions};
+use super::frontend_installer::{FRONTEND_DIR_NAME, FRONTEND_VERSION_MARKER};
 
 // Helper function for manual recursive copy using std::fs
 fn copy_recursively_std(source: &Path, destination: &Path) -> io::Result<()> {
@@ -204,6 +205,28 @@ pub fn copy_vendor_directories(
     eprintln!("cargo:warning=VENDOR_COPIER: [ComfyUI] Section finished.");
     println!("cargo:warning=VENDOR_COPIER: After attempting to copy comfyui directory.");
 
+    // --- Copy the bundled ComfyUI frontend ---
+    let source_frontend_path = source_vendor_dir.join(FRONTEND_DIR_NAME);
+    let target_frontend_path = dest_vendor_dir.join(FRONTEND_DIR_NAME);
+    eprintln!("cargo:warning=VENDOR_COPIER: [Frontend] Source: {:?}, Target: {:?}", &source_frontend_path, &target_frontend_path);
+    if !source_frontend_path.join("index.html").exists() {
+        let err_msg = format!("[Frontend] Bundled ComfyUI frontend not found at {:?}.", source_frontend_path);
+        eprintln!("cargo:warning=VENDOR_COPIER_ERROR: {}", err_msg);
+        return Err(err_msg.into());
+    }
+    let source_version = fs::read_to_string(source_frontend_path.join(FRONTEND_VERSION_MARKER)).ok();
+    let target_version = fs::read_to_string(target_frontend_path.join(FRONTEND_VERSION_MARKER)).ok();
+    if source_version.is_some() && source_version == target_version {
+        eprintln!("cargo:warning=VENDOR_COPIER_VERIFY: [Frontend] Same version already in target. Skipping copy.");
+    } else {
+        // A different release may be in the target; replace it rather than merging files.
+        if target_frontend_path.exists() {
+            fs::remove_dir_all(&target_frontend_path)?;
+        }
+        copy_directory_contents(&source_frontend_path, &target_frontend_path, "Frontend", force_std_fs_copy)?;
+        eprintln!("cargo:warning=VENDOR_COPIER: [Frontend] Content copy process completed.");
+    }
+
     println!("cargo:warning=VENDOR_COPIER: After attempting to copy python directory.");
 
     eprintln!("cargo:warning=VENDOR_COPIER: Vendor directory copying finished successfully.");
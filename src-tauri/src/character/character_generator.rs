@@ -3,6 +3,9 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use super::character_creation_types::{CharacterGenerationState, GenerationMode};
+use crate::setup_manager::custom_node_manager::workflow_requirements::ensure_workflow_runnable;
+use crate::sidecar_manager::comfyui_url;
+use crate::sidecar_manager::external_backend::is_external_mode;
 use tauri::AppHandle;
 use tauri::Manager;
 use uuid::Uuid;
@@ -10,6 +13,8 @@ use base64::{Engine as _, engine::general_purpose};
 use chrono;
 
 
+pub const UNIFIED_WORKFLOW_JSON: &str = include_str!("../../../resources/workflows/Metamorphosis Workflow.json");
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GenerationResponse {
     prompt_id: String,
@@ -19,11 +24,15 @@ pub struct GenerationResponse {
 
 #[tauri::command]
 pub async fn generate_character(
+    app: AppHandle,
     state: CharacterGenerationState,
 ) -> Result<GenerationResponse, String> {
     let mut workflow: Value = serde_json::from_str(&state.workflow_json).map_err(|e| e.to_string())?;
 
-    update_workflow_json(&mut workflow, &state)?;
+    // Fail with the missing node pack instead of letting ComfyUI reject the prompt.
+    ensure_workflow_runnable(&app, &workflow).await?;
+
+    update_workflow_json(&app, &mut workflow, &state)?;
 
     let client_id = uuid::Uuid::new_v4().to_string();
     let payload = json!({
@@ -32,7 +41,7 @@ pub async fn generate_character(
     });
 
     let client = reqwest::Client::new();
-    let res = client.post("http://127.0.0.1:8188/prompt")
+    let res = client.post(comfyui_url(&app, "/prompt"))
         .json(&payload)
         .send()
         .await
@@ -96,7 +105,7 @@ fn replace_placeholders_recursive(value: &mut Value, state: &CharacterGeneration
     }
 }
 
-fn update_workflow_json(workflow: &mut Value, state: &CharacterGenerationState) -> Result<(), String> {
+fn update_workflow_json(app: &AppHandle, workflow: &mut Value, state: &CharacterGenerationState) -> Result<(), String> {
     // First, replace template placeholders in the workflow JSON
     replace_template_placeholders(workflow, state)?;
     
@@ -168,8 +177,11 @@ fn update_workflow_json(workflow: &mut Value, state: &CharacterGenerationState)
             // Get character ID or use "unknown"
             let character_id = state.character_id.as_deref().unwrap_or("unknown");
             
-            // Ensure output directory structure exists
-            ensure_character_output_directories(character_id, image_type)?;
+            // Ensure output directory structure exists. An external ComfyUI writes to its own
+            // output directory and creates the subfolders itself.
+            if !is_external_mode(app) {
+                ensure_character_output_directories(character_id, image_type)?;
+            }
             
             // Create subfolder path with filename: characters/{characterId}/{imageType}/{characterId}_{imageType}_{seed}_{timestamp}
             let filename_with_path = format!("characters/{}/{}/{}_{}_{}_{}", 
@@ -322,8 +334,35 @@ pub fn prepare_image_for_edit(app: AppHandle, permanent_path: String) -> Result<
     Ok(temp_filename)
 }
 
+/// Downloads an output image through `/view`, for an external ComfyUI whose output directory
+/// isn't on this machine.
+async fn fetch_output_image(app: &AppHandle, filename: &str, subfolder: &str) -> Result<(Vec<u8>, String), String> {
+    let response = reqwest::Client::new()
+        .get(comfyui_url(app, "/view"))
+        .query(&[("filename", filename), ("subfolder", subfolder), ("type", "output")])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch image {}: {}", filename, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch image {}: HTTP {}", filename, response.status()));
+    }
+    let mime_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("image/png")
+        .to_string();
+    let bytes = response.bytes().await.map_err(|e| format!("Failed to read image {}: {}", filename, e))?;
+    Ok((bytes.to_vec(), mime_type))
+}
+
 #[tauri::command]
-pub fn get_image_as_data_url(app: AppHandle, filename: String, subfolder: String) -> Result<String, String> {
+pub async fn get_image_as_data_url(app: AppHandle, filename: String, subfolder: String) -> Result<String, String> {
+    if is_external_mode(&app) {
+        let (image_data, mime_type) = fetch_output_image(&app, &filename, &subfolder).await?;
+        return Ok(format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(&image_data)));
+    }
+
     // Use the same path resolution logic as get_asset_url
     let comfyui_dir = if cfg!(debug_assertions) {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -376,7 +415,7 @@ pub fn get_image_as_data_url(app: AppHandle, filename: String, subfolder: String
     
 #[tauri::command]
 pub fn get_unified_workflow() -> Result<String, String> {
-        Ok(include_str!("../../../resources/workflows/Metamorphosis Workflow.json").to_string())
+        Ok(UNIFIED_WORKFLOW_JSON.to_string())
     }
         
         #[tauri::command]
@@ -0,0 +1,307 @@
+// metamorphosis-app/src-tauri/src/diagnostics.rs
+//
+// Collects everything we usually ask for by hand when someone reports "setup failed" and
+// writes it into a single zip. Each section is gathered independently: if one fails (no conda
+// env yet, no git checkout, ...) the error text is written into that section's file instead of
+// aborting the whole export.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Wry};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::gpu_detection::{get_gpu_info, GpuInfo};
+use crate::setup_manager::custom_node_manager::archive_install::read_archive_source;
+use crate::setup_manager::install_manifest::get_master_marker_path;
+use crate::setup_manager::orchestration::{get_app_root_path, MINICONDA_INSTALLED_MARKER};
+use crate::setup_manager::env_backend::pip_freeze;
+use crate::setup_manager::python_utils::{execute_command_to_string, get_comfyui_directory_path, get_conda_executable_path};
+
+const CONDA_ENV_NAME: &str = "comfyui_env";
+const CONDA_DEPS_MARKER: &str = ".conda_env_deps_installed.marker";
+// How many sidecar stderr lines from app.log to include.
+const SIDECAR_STDERR_TAIL_LINES: usize = 500;
+const SIDECAR_STDERR_PREFIX: &str = "[comfyui_sidecar_stderr]";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    os: String,
+    os_family: String,
+    arch: String,
+    app_version: String,
+    gpu: GpuInfo,
+    generated_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CustomNodeInfo {
+    name: String,
+    is_dir: bool,
+    git_head: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelFileInfo {
+    path: String,
+    bytes: u64,
+}
+
+/// Replaces the user's home directory (and user name) so paths in shared files don't identify them.
+fn redact(text: &str, home_dir: Option<&Path>) -> String {
+    let mut redacted = text.to_string();
+    if let Some(home) = home_dir {
+        let home_str = home.to_string_lossy();
+        if !home_str.is_empty() {
+            redacted = redacted.replace(home_str.as_ref(), "<HOME>");
+            // log4rs.yaml is written with forward slashes even on Windows.
+            redacted = redacted.replace(&home_str.replace('\\', "/"), "<HOME>");
+            // JSON sections (settings, setup journal) escape Windows backslashes.
+            redacted = redacted.replace(&home_str.replace('\\', "\\\\"), "<HOME>");
+        }
+        if let Some(user_name) = home.file_name().map(|n| n.to_string_lossy().to_string()) {
+            if user_name.len() > 2 {
+                redacted = redacted.replace(&user_name, "<USER>");
+            }
+        }
+    }
+    redacted
+}
+
+/// Resolves HEAD of a git checkout to a commit hash without requiring git on PATH.
+fn read_git_head(repo_dir: &Path) -> Result<String, String> {
+    let git_dir = repo_dir.join(".git");
+    let head = fs::read_to_string(git_dir.join("HEAD"))
+        .map_err(|e| format!("No readable .git/HEAD: {}", e))?;
+    let head = head.trim();
+    let ref_name = match head.strip_prefix("ref: ") {
+        Some(r) => r.trim(),
+        None => return Ok(head.to_string()), // Detached HEAD
+    };
+    if let Ok(hash) = fs::read_to_string(git_dir.join(ref_name)) {
+        return Ok(format!("{} ({})", hash.trim(), ref_name));
+    }
+    // Refs may have been packed by `git gc`.
+    let packed = fs::read_to_string(git_dir.join("packed-refs")).unwrap_or_default();
+    packed
+        .lines()
+        .filter(|l| !l.starts_with('#') && !l.starts_with('^'))
+        .find_map(|l| {
+            let mut parts = l.split_whitespace();
+            let hash = parts.next()?;
+            (parts.next()? == ref_name).then(|| format!("{} ({})", hash, ref_name))
+        })
+        .ok_or_else(|| format!("Could not resolve {}", ref_name))
+}
+
+fn collect_custom_nodes(comfyui_dir: &Path) -> Result<Vec<CustomNodeInfo>, String> {
+    let custom_nodes_dir = comfyui_dir.join("custom_nodes");
+    let entries = fs::read_dir(&custom_nodes_dir)
+        .map_err(|e| format!("Failed to read {}: {}", custom_nodes_dir.display(), e))?;
+    let mut nodes: Vec<CustomNodeInfo> = entries
+        .filter_map(|e| e.ok())
+        .map(|entry| {
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            // Archive installs (no git available) record their source revision instead of a .git dir.
+            let git_head = if is_dir {
+                read_git_head(&path).ok().or_else(|| read_archive_source(&path).map(|src| format!("{} (archive)", src.commit.or(src.revision).unwrap_or_else(|| "HEAD".to_string()))))
+            } else {
+                None
+            };
+            CustomNodeInfo { name: entry.file_name().to_string_lossy().to_string(), is_dir, git_head }
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(nodes)
+}
+
+fn collect_model_files(dir: &Path, base: &Path, files: &mut Vec<ModelFileInfo>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let metadata = match fs::metadata(&path) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            collect_model_files(&path, base, files);
+        } else {
+            let relative = path.strip_prefix(base).unwrap_or(&path);
+            files.push(ModelFileInfo { path: relative.to_string_lossy().replace('\\', "/"), bytes: metadata.len() });
+        }
+    }
+}
+
+/// Returns the last `max_lines` sidecar stderr lines from app.log.
+fn tail_sidecar_stderr(app_log: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = app_log.lines().filter(|l| l.contains(SIDECAR_STDERR_PREFIX)).collect();
+    let start = lines.len().saturating_sub(max_lines);
+    lines[start..].join("\n")
+}
+
+fn to_pretty_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!("Failed to serialize: {}", e))
+}
+
+fn read_or_error(path: &Path) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| format!("Could not read {}: {}", path.display(), e))
+}
+
+/// Turns the user's destination into a zip file path. A directory gets a timestamped file name.
+fn resolve_destination(destination_path: &str) -> Result<PathBuf, String> {
+    if destination_path.trim().is_empty() {
+        return Err("No destination path given for the diagnostics bundle.".to_string());
+    }
+    let destination = PathBuf::from(destination_path);
+    if destination.is_dir() {
+        let file_name = format!("metamorphosis-diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        return Ok(destination.join(file_name));
+    }
+    if destination.extension().map(|e| e.eq_ignore_ascii_case("zip")).unwrap_or(false) {
+        Ok(destination)
+    } else {
+        Ok(destination.with_extension("zip"))
+    }
+}
+
+/// Gathers all sections as (file name inside the zip, contents), each one redacted.
+async fn collect_sections(app_handle: &AppHandle<Wry>) -> Vec<(String, String)> {
+    let home_dir = app_handle.path().home_dir().ok();
+    // Every section goes through `redact`: settings, the setup journal and pip freeze output
+    // (`name @ file:///...`) all contain paths under the user's home directory.
+    collect_unredacted_sections(app_handle)
+        .await
+        .into_iter()
+        .map(|(name, contents)| (name, redact(&contents, home_dir.as_deref())))
+        .collect()
+}
+
+async fn collect_unredacted_sections(app_handle: &AppHandle<Wry>) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+
+    // System and GPU
+    let system_info = SystemInfo {
+        os: std::env::consts::OS.to_string(),
+        os_family: std::env::consts::FAMILY.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_version: app_handle.package_info().version.to_string(),
+        gpu: get_gpu_info(),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    sections.push(("system.json".to_string(), to_pretty_json(&system_info)));
+
+    // Logs and logging config
+    match app_handle.path().app_data_dir() {
+        Ok(app_data_dir) => {
+            let app_log = read_or_error(&app_data_dir.join("logs").join("app.log"));
+            sections.push(("sidecar_stderr.log".to_string(), tail_sidecar_stderr(&app_log, SIDECAR_STDERR_TAIL_LINES)));
+            sections.push(("app.log".to_string(), app_log));
+            sections.push(("setup_journal.json".to_string(), read_or_error(&app_data_dir.join("setup_journal.json"))));
+        }
+        Err(e) => sections.push(("app.log".to_string(), format!("Failed to get app data dir: {}", e))),
+    }
+    match app_handle.path().app_config_dir() {
+        Ok(config_dir) => {
+            sections.push(("log4rs.yaml".to_string(), read_or_error(&config_dir.join("log4rs.yaml"))));
+            sections.push(("settings.json".to_string(), read_or_error(&config_dir.join("settings.json"))));
+        }
+        Err(e) => sections.push(("log4rs.yaml".to_string(), format!("Failed to get app config dir: {}", e))),
+    }
+
+    // Markers
+    let mut markers = String::new();
+    match get_master_marker_path(app_handle) {
+        Ok(path) => markers.push_str(&format!("== {} ==\n{}\n\n", path.display(), read_or_error(&path))),
+        Err(e) => markers.push_str(&format!("== master marker ==\n{}\n\n", e)),
+    }
+    if let Ok(app_root) = get_app_root_path() {
+        let miniconda_marker = app_root.join(MINICONDA_INSTALLED_MARKER);
+        markers.push_str(&format!("== {} ==\n{}\n\n", miniconda_marker.display(), read_or_error(&miniconda_marker)));
+    }
+
+    // ComfyUI install: deps marker, custom nodes and models
+    match get_comfyui_directory_path(app_handle) {
+        Ok(comfyui_dir) => {
+            let deps_marker = comfyui_dir.join(CONDA_DEPS_MARKER);
+            markers.push_str(&format!("== {} ==\n{}\n", deps_marker.display(), read_or_error(&deps_marker)));
+
+            let custom_nodes = collect_custom_nodes(&comfyui_dir)
+                .map(|nodes| to_pretty_json(&nodes))
+                .unwrap_or_else(|e| e);
+            sections.push(("custom_nodes.json".to_string(), custom_nodes));
+
+            let models_dir = comfyui_dir.join("models");
+            let mut model_files = Vec::new();
+            collect_model_files(&models_dir, &models_dir, &mut model_files);
+            model_files.sort_by(|a, b| a.path.cmp(&b.path));
+            sections.push(("models.json".to_string(), to_pretty_json(&model_files)));
+        }
+        Err(e) => {
+            sections.push(("custom_nodes.json".to_string(), e.clone()));
+            sections.push(("models.json".to_string(), e));
+        }
+    }
+    sections.push(("markers.txt".to_string(), markers));
+
+    // Python environment
+    let pip_freeze = pip_freeze(app_handle).await.unwrap_or_else(|e| format!("pip freeze failed: {}", e));
+    sections.push(("pip_freeze.txt".to_string(), pip_freeze));
+
+    let conda_list = match get_conda_executable_path(app_handle).await {
+        Ok(conda) => execute_command_to_string(&conda, &["list", "-n", CONDA_ENV_NAME], None)
+            .await
+            .unwrap_or_else(|e| format!("conda list failed: {}", e)),
+        Err(e) => format!("Conda executable not found: {}", e),
+    };
+    sections.push(("conda_list.txt".to_string(), conda_list));
+
+    sections
+}
+
+fn write_zip(zip_path: &Path, sections: &[(String, String)]) -> Result<(), String> {
+    if let Some(parent) = zip_path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+    }
+    let file = fs::File::create(zip_path).map_err(|e| format!("Failed to create {}: {}", zip_path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in sections {
+        zip.start_file(name.as_str(), options).map_err(|e| format!("Failed to add {} to zip: {}", name, e))?;
+        zip.write_all(contents.as_bytes()).map_err(|e| format!("Failed to write {} to zip: {}", name, e))?;
+    }
+    zip.finish().map_err(|e| format!("Failed to finalize {}: {}", zip_path.display(), e))?;
+    Ok(())
+}
+
+/// Writes a diagnostics zip to `destination_path` (a file path or a directory) and returns the
+/// path of the written zip.
+#[tauri::command]
+pub async fn export_diagnostics_bundle(app_handle: AppHandle<Wry>, destination_path: String) -> Result<String, String> {
+    let zip_path = resolve_destination(&destination_path)?;
+    info!("[DIAGNOSTICS] Exporting diagnostics bundle to {}", zip_path.display());
+
+    let sections = collect_sections(&app_handle).await;
+    let zip_path_clone = zip_path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_zip(&zip_path_clone, &sections))
+        .await
+        .map_err(|e| format!("Diagnostics export task failed: {}", e))?
+        .map_err(|e| {
+            warn!("[DIAGNOSTICS] {}", e);
+            e
+        })?;
+
+    info!("[DIAGNOSTICS] Diagnostics bundle written to {}", zip_path.display());
+    Ok(zip_path.display().to_string())
+}
@@ -1,7 +1,8 @@
 use log::{info, error};
+use serde::Serialize;
 
 // Enum to represent the detected GPU type
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum GpuType {
     Nvidia,
     Amd,
@@ -10,7 +11,8 @@ pub enum GpuType {
 }
 
 // Struct to hold detailed GPU information
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpuInfo {
     pub gpu_type: GpuType,
     pub cuda_version: Option<String>,
@@ -270,4 +272,19 @@ pub fn get_gpu_info() -> GpuInfo {
         gpu_type: GpuType::Unknown, // Default to Unknown if detection is not conclusive
         cuda_version: None,
     }
-}
\ No newline at end of file
+}
+// Total memory of the first NVIDIA GPU in MiB, via nvidia-smi. None for other vendors or when
+// nvidia-smi isn't available.
+pub fn detect_vram_mb() -> Option<u64> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=memory.total", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        info!("nvidia-smi memory query failed with status: {:?}", output.status);
+        return None;
+    }
+    let vram_mb = String::from_utf8_lossy(&output.stdout).lines().next()?.trim().parse().ok();
+    info!("Detected VRAM: {:?} MiB", vram_mb);
+    vram_mb
+}
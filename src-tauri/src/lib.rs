@@ -12,6 +12,10 @@ pub mod sidecar_manager;   // Declare the new top-level module
 pub mod setup_manager;     // Declare the new top-level module
 pub mod character;
 pub mod process_manager;   // Declare the new process manager module
+pub mod process_tree;      // Staged shutdown of managed process trees
+pub mod process_records;   // PID records for finding processes orphaned by a crash
+pub mod settings;
+pub mod diagnostics;
 
 // Define a state to track the shutdown process
 pub struct ShutdownState(pub Arc<Mutex<bool>>);
@@ -67,6 +71,8 @@ pub fn run() {
     .plugin(tauri_plugin_opener::init()) // Initialize the Opener plugin
     .manage(process_manager::ProcessManager::new()) // Add the process manager to the state
     .manage(ShutdownState(Arc::new(Mutex::new(false)))) // Add shutdown state
+    .manage(setup_manager::SetupTaskState::new()) // Tracks the running setup so it can be cancelled
+    .manage(sidecar_manager::node_load_report::NodeLoadReportState::default()) // Custom node load status from the sidecar's startup output
     .setup(move |app| {
         match init_logging(app) {
             Ok(handle) => {
@@ -181,6 +187,23 @@ pub fn run() {
       // Sidecar process will be started explicitly by the frontend later
       // log::info!("[STARTUP] Starting ComfyUI sidecar process");
       // comfyui_sidecar::start_comfyui_sidecar(app.handle().clone());
+
+      // LAN access is opt-in; the proxy only starts if the user enabled it in settings.
+      let app_handle_for_lan_proxy = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+          sidecar_manager::lan_proxy::start_lan_proxy_if_enabled(&app_handle_for_lan_proxy).await;
+      });
+
+      // In external backend mode, let the frontend's HTTP client reach the configured ComfyUI.
+      if let Err(e) = sidecar_manager::external_backend::allow_external_backend_url(app.handle()) {
+          log::error!("[STARTUP] {}", e);
+      }
+
+      // Look for processes a crashed earlier run left behind; the frontend is told via an event.
+      let app_handle_for_orphans = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+          process_records::pending_orphans(&app_handle_for_orphans).await;
+      });
       log::info!("[STARTUP] Setup complete - elapsed: {:?}", app_start_time.elapsed());
 
       Ok(())
@@ -192,8 +215,46 @@ pub fn run() {
       setup_manager::orchestration::start_application_setup,
       setup_manager::orchestration::retry_application_setup,
       setup_manager::orchestration::get_setup_status_and_initialize,
+      setup_manager::orchestration::cancel_application_setup,
+      setup_manager::setup_journal::get_setup_journal,
+      setup_manager::deep_verification::run_deep_verification,
+      setup_manager::component_reset::reset_miniconda,
+      setup_manager::component_reset::reset_conda_env,
+      setup_manager::component_reset::reset_custom_node,
+      setup_manager::component_reset::reset_model,
+      setup_manager::component_reset::reset_master_marker,
+      setup_manager::component_reset::uninstall_all_components,
+      setup_manager::dependency_manager::lockfile::regenerate_python_lockfile,
+      setup_manager::dependency_manager::lockfile::check_python_lock_drift,
+      setup_manager::torch_probe::run_torch_device_probe,
+      setup_manager::torch_probe::reinstall_torch_variant,
+      setup_manager::env_snapshot::list_env_snapshots,
+      setup_manager::env_snapshot::rollback_env_snapshot,
+      setup_manager::custom_node_manager::updates::check_custom_node_updates,
+      setup_manager::custom_node_manager::updates::update_custom_node,
+      setup_manager::custom_node_manager::updates::update_all_custom_nodes,
+      setup_manager::custom_node_manager::updates::rollback_custom_node,
+      setup_manager::custom_node_manager::updates::get_custom_node_history,
+      setup_manager::custom_node_manager::workflow_requirements::check_workflow_requirements,
+      setup_manager::custom_node_manager::user_registry::list_available_custom_node_packs,
+      setup_manager::custom_node_manager::user_registry::list_user_custom_nodes,
+      setup_manager::custom_node_manager::user_registry::install_user_custom_node,
+      setup_manager::custom_node_manager::user_registry::uninstall_user_custom_node,
+      setup_manager::dependency_manager::requirement_conflicts::check_requirement_conflicts,
+      settings::get_app_settings,
+      settings::update_app_settings,
+      diagnostics::export_diagnostics_bundle,
+      process_records::get_orphaned_processes,
+      process_records::resolve_orphaned_process,
       // Register the new backend readiness command
       sidecar_manager::orchestration::ensure_backend_ready,
+      sidecar_manager::node_load_report::get_custom_node_load_report,
+      sidecar_manager::endpoint::get_comfyui_endpoint,
+      sidecar_manager::lan_proxy::get_lan_access_info,
+      sidecar_manager::lan_proxy::set_lan_access_enabled,
+      sidecar_manager::external_backend::check_external_comfyui,
+      sidecar_manager::launch_profile::get_launch_profiles,
+      sidecar_manager::launch_profile::set_launch_profile,
       sidecar_manager::orchestration::ensure_comfyui_running_and_healthy,
       character::character_generator::generate_character,
       character::character_generator::get_asset_url,
@@ -1,18 +1,59 @@
 use log::{error, info, warn};
 use tauri_plugin_shell::process::{Command, CommandEvent};
-use tauri_plugin_shell::ShellExt;
 use tauri::{AppHandle, Manager, Wry};
 use tauri_plugin_shell::process::CommandChild;
+use tauri_plugin_http::reqwest;
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Mutex;
 use std::time::Duration;
 use uuid::Uuid;
+use crate::process_records::{self, kill_recorded_process, ProcessRecord};
+use crate::process_tree::{terminate_process_tree, ProcessStopReport, StopOutcome, GRACEFUL_SHUTDOWN_TIMEOUT};
+use crate::sidecar_manager::endpoint::comfyui_url;
+
+tokio::task_local! {
+    // Group name applied to every process spawned from within `ProcessManager::run_in_group`.
+    static PROCESS_GROUP: String;
+}
 
 /// Manages all active child processes spawned by the application.
 /// This struct is managed by Tauri's state system, ensuring that it is accessible
 /// from anywhere in the application and that its lifecycle is tied to the app itself.
 pub struct ProcessManager {
     pub active_processes: Mutex<HashMap<String, CommandChild>>,
+    // Process name -> group name, for processes spawned inside a group scope (e.g. "setup").
+    process_groups: Mutex<HashMap<String, String>>,
+    // Processes left over from an earlier run that the user chose to keep (see `process_records`).
+    // We have no handle to them, only the record to find and verify them by.
+    adopted_processes: Mutex<HashMap<String, AdoptedProcess>>,
+}
+
+struct AdoptedProcess {
+    record: ProcessRecord,
+    app_handle: AppHandle<Wry>, // For removing the record once it is stopped
+}
+
+// A process taken out of tracking to be stopped.
+enum StoppableProcess {
+    Child(CommandChild),
+    Adopted(AdoptedProcess),
+}
+
+impl StoppableProcess {
+    fn pid(&self) -> u32 {
+        match self {
+            StoppableProcess::Child(child) => child.pid(),
+            StoppableProcess::Adopted(adopted) => adopted.record.pid,
+        }
+    }
+
+    async fn stop(self, name: &str) -> ProcessStopReport {
+        match self {
+            StoppableProcess::Child(child) => terminate_process_tree(name, child, GRACEFUL_SHUTDOWN_TIMEOUT).await,
+            StoppableProcess::Adopted(adopted) => kill_recorded_process(&adopted.app_handle, &adopted.record).await,
+        }
+    }
 }
 
 /// Represents the final result of a managed command execution.
@@ -27,22 +68,96 @@ pub struct CommandResult {
 impl ProcessManager {
     pub fn is_process_running(&self, process_name: &str) -> bool {
         self.active_processes.lock().unwrap().contains_key(process_name)
+            || self.adopted_processes.lock().unwrap().contains_key(process_name)
+    }
+
+    /// Tracks an orphan from an earlier run under its recorded name, so it is stopped like our own.
+    pub fn adopt_process(&self, app_handle: &AppHandle<Wry>, record: ProcessRecord) {
+        let adopted = AdoptedProcess { record, app_handle: app_handle.clone() };
+        self.adopted_processes.lock().unwrap().insert(adopted.record.name.clone(), adopted);
     }
 
+    fn take_for_stop(&self, process_name: &str) -> Option<StoppableProcess> {
+        self.untrack_process(process_name)
+            .map(StoppableProcess::Child)
+            .or_else(|| self.adopted_processes.lock().unwrap().remove(process_name).map(StoppableProcess::Adopted))
+    }
+
+    /// Stops a process and its children in the background (see `process_tree`). The process is
+    /// untracked right away, so `is_process_running` is false before it has actually exited;
+    /// use `stop_process_and_wait` when the caller needs it gone.
     pub fn stop_process(&self, process_name: &str) {
-        if let Some(child) = self.active_processes.lock().unwrap().remove(process_name) {
-            info!("Attempting to kill process: {}", process_name);
-            match child.kill() {
-                Ok(_) => info!("Successfully sent kill signal to process: {}", process_name),
-                Err(e) => error!("Failed to kill process '{}': {}", process_name, e),
-            }
+        if let Some(process) = self.take_for_stop(process_name) {
+            info!("Stopping process: {}", process_name);
+            let name = process_name.to_string();
+            tauri::async_runtime::spawn(async move {
+                process.stop(&name).await;
+            });
         }
     }
+
+    /// Like `stop_process`, but returns once the process tree is gone (or the forced kill gave up).
+    pub async fn stop_process_and_wait(&self, process_name: &str) -> Option<ProcessStopReport> {
+        let process = self.take_for_stop(process_name)?;
+        info!("Stopping process and waiting for it to exit: {}", process_name);
+        Some(process.stop(process_name).await)
+    }
+
     /// Creates a new, empty ProcessManager.
     pub fn new() -> Self {
         Self {
             active_processes: Mutex::new(HashMap::new()),
+            process_groups: Mutex::new(HashMap::new()),
+            adopted_processes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `future` so that every process it spawns through the ProcessManager is tagged with `group`.
+    /// Only processes spawned on the same task are tagged; `tauri::async_runtime::spawn` starts a new scope.
+    pub async fn run_in_group<F: Future>(group: &str, future: F) -> F::Output {
+        PROCESS_GROUP.scope(group.to_string(), future).await
+    }
+
+    fn track_process(&self, process_name: &str, child: CommandChild) {
+        self.active_processes.lock().unwrap().insert(process_name.to_string(), child);
+        if let Ok(group) = PROCESS_GROUP.try_with(|g| g.clone()) {
+            self.process_groups.lock().unwrap().insert(process_name.to_string(), group);
+        }
+    }
+
+    fn untrack_process(&self, process_name: &str) -> Option<CommandChild> {
+        self.process_groups.lock().unwrap().remove(process_name);
+        self.active_processes.lock().unwrap().remove(process_name)
+    }
+
+    // Untracks `process_name` only if it is still the process with `pid`. A stopped process can
+    // take a while to exit, and by then a new one may have been started under the same name.
+    fn untrack_process_if_pid(&self, process_name: &str, pid: u32) -> bool {
+        let mut processes = self.active_processes.lock().unwrap();
+        if processes.get(process_name).map(|child| child.pid()) != Some(pid) {
+            return false;
         }
+        processes.remove(process_name);
+        self.process_groups.lock().unwrap().remove(process_name);
+        true
+    }
+
+    /// Stops every tracked process belonging to `group` (in the background, like `stop_process`)
+    /// and returns their names.
+    pub fn stop_process_group(&self, group: &str) -> Vec<String> {
+        let names: Vec<String> = self
+            .process_groups
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, g)| g.as_str() == group)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &names {
+            info!("Stopping process '{}' from group '{}'", name, group);
+            self.stop_process(name);
+        }
+        names
     }
 
     /// Spawns a new managed process that runs in the background.
@@ -51,6 +166,17 @@ impl ProcessManager {
         app_handle: &AppHandle<Wry>,
         process_name: String,
         command: Command,
+    ) -> Result<(), String> {
+        Self::spawn_managed_process_with_line_handler(app_handle, process_name, command, |_, _| {}).await
+    }
+
+    /// Like `spawn_managed_process`, but also passes each output line to `on_line(line, is_stderr)`
+    /// on the monitoring task, e.g. to parse the sidecar's startup output.
+    pub async fn spawn_managed_process_with_line_handler<F: FnMut(&str, bool) + Send + 'static>(
+        app_handle: &AppHandle<Wry>,
+        process_name: String,
+        command: Command,
+        mut on_line: F,
     ) -> Result<(), String> {
         info!("Spawning background managed process: {}", process_name);
         let process_manager = app_handle.state::<ProcessManager>();
@@ -61,11 +187,10 @@ impl ProcessManager {
         })?;
 
         // Add the child to the process manager
-        process_manager
-            .active_processes
-            .lock()
-            .unwrap()
-            .insert(process_name.clone(), child);
+        let pid = child.pid();
+        process_manager.track_process(&process_name, child);
+        // Lets the next launch find this process if the app crashes before it is stopped.
+        process_records::record_spawned_process(app_handle, &process_name, pid).await;
 
         let handle = app_handle.clone();
         let name = process_name.clone();
@@ -85,25 +210,27 @@ impl ProcessManager {
                         error!("Error from managed process '{}': {}", name, e);
                     }
                     CommandEvent::Stdout(line) => {
-                        info!("[{}_stdout] {}", name, String::from_utf8_lossy(&line));
+                        let text = String::from_utf8_lossy(&line);
+                        info!("[{}_stdout] {}", name, text);
+                        on_line(&text, false);
                     }
                     CommandEvent::Stderr(line) => {
                         // Stderr should probably be at a higher level, like warn! or error!
                         // For now, let's use info! to ensure visibility, but we can refine this.
                         // Let's use warn! to make it stand out.
-                        warn!("[{}_stderr] {}", name, String::from_utf8_lossy(&line));
+                        let text = String::from_utf8_lossy(&line);
+                        warn!("[{}_stderr] {}", name, text);
+                        on_line(&text, true);
                     }
                     _ => {}
                 }
             }
             // Remove the child from the process manager once it has terminated
             let process_manager = handle.state::<ProcessManager>();
-            process_manager
-                .active_processes
-                .lock()
-                .unwrap()
-                .remove(&name);
-            info!("Removed managed process '{}' from tracking.", name);
+            if process_manager.untrack_process_if_pid(&name, pid) {
+                info!("Removed managed process '{}' from tracking.", name);
+            }
+            process_records::remove_process_record(&handle, &name, pid).await;
         });
 
         Ok(())
@@ -115,6 +242,17 @@ impl ProcessManager {
         app_handle: &AppHandle<Wry>,
         command: Command,
         process_base_name: &str,
+    ) -> Result<CommandResult, String> {
+        Self::spawn_and_wait_for_process_with_line_handler(app_handle, command, process_base_name, |_, _| {}).await
+    }
+
+    /// Like `spawn_and_wait_for_process`, but calls `on_line(line, is_stderr)` for each output line
+    /// as it arrives, so callers can report progress while the process is still running.
+    pub async fn spawn_and_wait_for_process_with_line_handler<F: FnMut(&str, bool)>(
+        app_handle: &AppHandle<Wry>,
+        command: Command,
+        process_base_name: &str,
+        mut on_line: F,
     ) -> Result<CommandResult, String> {
         let process_name = format!("{}_{}", process_base_name, Uuid::new_v4());
         info!("Spawning synchronous managed process: {}", process_name);
@@ -127,7 +265,7 @@ impl ProcessManager {
         })?;
 
         // Add to tracking
-        process_manager.active_processes.lock().unwrap().insert(process_name.clone(), child);
+        process_manager.track_process(&process_name, child);
 
         let mut stdout_lines = Vec::new();
         let mut stderr_lines = Vec::new();
@@ -139,11 +277,13 @@ impl ProcessManager {
                 CommandEvent::Stdout(line) => {
                     let line_str = String::from_utf8_lossy(&line).to_string();
                     info!("[{}_stdout] {}", process_name, line_str);
+                    on_line(&line_str, false);
                     stdout_lines.push(line_str);
                 }
                 CommandEvent::Stderr(line) => {
                     let line_str = String::from_utf8_lossy(&line).to_string();
                     error!("[{}_stderr] {}", process_name, line_str);
+                    on_line(&line_str, true);
                     stderr_lines.push(line_str);
                 }
                 CommandEvent::Terminated(payload) => {
@@ -155,7 +295,7 @@ impl ProcessManager {
                 CommandEvent::Error(e) => {
                     error!("Error from sync process '{}': {}", process_name, e);
                     // Remove from tracking on error
-                    process_manager.active_processes.lock().unwrap().remove(&process_name);
+                    process_manager.untrack_process(&process_name);
                     return Err(format!("Error executing command '{}': {}", process_name, e));
                 }
                 _ => {}
@@ -163,7 +303,7 @@ impl ProcessManager {
         }
 
         // Remove from tracking after completion
-        process_manager.active_processes.lock().unwrap().remove(&process_name);
+        process_manager.untrack_process(&process_name);
         info!("Removed sync process '{}' from tracking.", process_name);
 
         Ok(CommandResult {
@@ -175,91 +315,54 @@ impl ProcessManager {
     }
 
 
-    /// Shuts down all tracked processes gracefully.
-    /// This should be called during the application's shutdown sequence.
-    /// It sends a kill signal and waits for each process to terminate, with a timeout.
-    /// Shuts down all tracked processes gracefully.
-    /// This should be called during the application's shutdown sequence.
-    /// It sends a kill signal and waits for each process to terminate, with a timeout.
-    pub async fn shutdown_all_processes(&self, app_handle: &AppHandle<Wry>) {
+    /// Shuts down all tracked processes during the application's shutdown sequence.
+    /// Each process tree is stopped in stages (polite signal, wait, forced kill; see `process_tree`),
+    /// all of them concurrently, and the outcome for every process is logged and returned.
+    pub async fn shutdown_all_processes(&self, app_handle: &AppHandle<Wry>) -> Vec<ProcessStopReport> {
         info!("Shutting down all managed processes...");
-        let shell = app_handle.shell();
 
-        let processes_to_kill: Vec<(String, u32)> = {
-            let processes_map = self.active_processes.lock().unwrap();
-            if processes_map.is_empty() {
-                info!("No active processes to shut down.");
-                return;
-            }
-            processes_map
-                .iter()
-                .map(|(name, child)| (name.clone(), child.pid()))
-                .collect()
+        let processes: Vec<(String, StoppableProcess)> = {
+            let mut names: Vec<String> = self.active_processes.lock().unwrap().keys().cloned().collect();
+            names.extend(self.adopted_processes.lock().unwrap().keys().cloned());
+            names.into_iter().filter_map(|name| self.take_for_stop(&name).map(|process| (name, process))).collect()
         };
-
-        if !processes_to_kill.is_empty() {
-            info!(
-                "Terminating processes: {:?}",
-                processes_to_kill
-                    .iter()
-                    .map(|(n, p)| format!("{}({})", n, p))
-                    .collect::<Vec<_>>()
-            );
+        if processes.is_empty() {
+            info!("No active processes to shut down.");
+            return Vec::new();
         }
+        info!(
+            "Terminating processes: {:?}",
+            processes.iter().map(|(n, p)| format!("{}({})", n, p.pid())).collect::<Vec<_>>()
+        );
 
-        for (name, pid) in processes_to_kill {
-            info!("Attempting to kill process: {} (PID: {})", name, pid);
-            let kill_command = if cfg!(windows) {
-                shell.command("taskkill").args(["/F", "/PID", &pid.to_string()])
-            } else {
-                shell.command("kill").args(["-9", &pid.to_string()])
-            };
-
-            match kill_command.output().await {
-                Ok(output) if output.status.success() => {
-                    info!(
-                        "Successfully sent kill signal to process: {} (PID: {})",
-                        name, pid
-                    );
-                }
-                Ok(output) => {
-                    error!(
-                        "Kill command for process '{}' (PID: {}) failed with status: {:?}. Stderr: {}",
-                        name,
-                        pid,
-                        output.status,
-                        String::from_utf8_lossy(&output.stderr)
-                    );
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to execute kill command for process '{}' (PID: {}): {}",
-                        name, pid, e
-                    );
-                }
+        // Ask ComfyUI to stop the running generation first, so it isn't torn down mid-prompt.
+        if processes.iter().any(|(name, _)| name == "comfyui_sidecar") {
+            let client = reqwest::Client::builder().timeout(Duration::from_secs(2)).build().unwrap_or_default();
+            match client.post(comfyui_url(app_handle, "/interrupt")).send().await {
+                Ok(response) => info!("Sent /interrupt to ComfyUI before shutdown: {}", response.status()),
+                Err(e) => warn!("Could not send /interrupt to ComfyUI before shutdown: {}", e),
             }
+        }
 
-            let wait_for_termination = async {
-                let check_interval = Duration::from_millis(100);
-                loop {
-                    if !self.is_process_running(&name) {
-                        info!("Process '{}' terminated successfully.", name);
-                        break;
-                    }
-                    tokio::time::sleep(check_interval).await;
+        let reports = futures_util::future::join_all(
+            processes.into_iter().map(|(name, process)| async move { process.stop(&name).await }),
+        )
+        .await;
+
+        for report in &reports {
+            match report.outcome {
+                StopOutcome::Failed => error!(
+                    "Process '{}' (PID {}) could not be stopped: survivors {:?}, error: {:?}",
+                    report.name, report.pid, report.survivors, report.error
+                ),
+                outcome => {
+                    info!("Process '{}' (PID {}): {:?} in {} ms", report.name, report.pid, outcome, report.elapsed_ms);
+                    // The monitoring task may not get to it before the app exits.
+                    process_records::remove_process_record(app_handle, &report.name, report.pid).await;
                 }
-            };
-
-            if let Err(_) = tokio::time::timeout(Duration::from_secs(5), wait_for_termination).await
-            {
-                error!(
-                    "Timeout reached while waiting for process '{}' to terminate. Force-removing from tracking.",
-                    name
-                );
-                self.active_processes.lock().unwrap().remove(&name);
             }
         }
-
-        info!("All tracked processes have been signaled for termination.");
+        info!("All tracked processes have been shut down.");
+        reports
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,329 @@
+// metamorphosis-app/src-tauri/src/process_records.rs
+//
+// Crash recovery for managed processes. If the app dies without shutting down, ComfyUI keeps
+// running: the next launch then either finds 8188 taken or quietly talks to the stale server.
+//
+// Every process started by `ProcessManager::spawn_managed_process` gets a record in
+// `<app data>/process_records/<name>.json` with its PID, the OS-reported start time and its
+// command line; the record is removed when the process exits. Records still on disk at startup
+// belong to processes of an earlier run. Each one is checked against the live process: only if
+// the PID is alive AND its start time and command line match is it a verified orphan. Anything
+// else means the process is gone, or the PID was reused by an unrelated program; those records
+// are dropped and the PID is never touched.
+//
+// Verified orphans are announced with `orphaned-processes-detected`; the user can adopt them
+// (the ProcessManager then tracks and eventually stops them like its own) or kill them. A
+// leftover sidecar that nobody decided on yet is handled when the sidecar is about to start:
+// it is adopted if it still answers, otherwise killed, so we never run two of them.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_http::reqwest;
+use tokio::time::Duration;
+
+use crate::process_manager::ProcessManager;
+use crate::process_tree::{terminate_pid_tree, ProcessStopReport, StopOutcome, GRACEFUL_SHUTDOWN_TIMEOUT};
+use crate::sidecar_manager::endpoint::{set_active_port, COMFYUI_LISTEN_HOST};
+use crate::sidecar_manager::event_utils::emit_backend_status;
+
+const RECORDS_DIR_NAME: &str = "process_records";
+const SIDECAR_PROCESS_NAME: &str = "comfyui_sidecar";
+
+/// What identifies a process beyond its PID, which the OS hands out again once it exits.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessIdentity {
+    pub start_time: String, // OS-specific, only ever compared for equality
+    pub command_line: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessRecord {
+    pub name: String,
+    pub pid: u32,
+    #[serde(flatten)]
+    pub identity: ProcessIdentity,
+    pub recorded_at: u64, // Unix seconds
+    pub app_pid: u32,     // The app instance that started it
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OrphanAction {
+    Adopt,
+    Kill,
+}
+
+// Verified orphans from the startup scan that haven't been adopted or killed yet.
+// None until the scan ran; async so callers wait for a scan in progress instead of starting another.
+static PENDING_ORPHANS: Lazy<tokio::sync::Mutex<Option<Vec<ProcessRecord>>>> = Lazy::new(|| tokio::sync::Mutex::new(None));
+
+fn records_dir(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(RECORDS_DIR_NAME))
+}
+
+fn record_path(app_handle: &AppHandle<Wry>, process_name: &str) -> Result<PathBuf, String> {
+    Ok(records_dir(app_handle)?.join(format!("{}.json", process_name)))
+}
+
+/// Start time and command line of the live process `pid`, or None if there is no such process.
+#[cfg(target_os = "linux")]
+pub async fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    let stat = tokio::fs::read_to_string(format!("/proc/{}/stat", pid)).await.ok()?;
+    // The command name in parentheses may contain spaces, so count fields after the last ')'.
+    // Field 3 (state) is the first one after it, field 22 (starttime, in ticks since boot) the 20th.
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect();
+    if fields.first() == Some(&"Z") {
+        return None; // Exited, waiting to be reaped
+    }
+    let start_ticks = fields.get(19)?;
+    // Ticks since boot repeat across reboots; the boot id tells boots apart.
+    let boot_id = tokio::fs::read_to_string("/proc/sys/kernel/random/boot_id").await.unwrap_or_default();
+    let cmdline = tokio::fs::read(format!("/proc/{}/cmdline", pid)).await.ok()?;
+    let command_line = cmdline
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(ProcessIdentity { start_time: format!("{}:{}", boot_id.trim(), start_ticks), command_line })
+}
+
+/// Start time and command line of the live process `pid`, or None if there is no such process.
+#[cfg(all(unix, not(target_os = "linux")))]
+pub async fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    use crate::process_tree::command;
+    let ps = |field: &'static str| async move {
+        let output = command("ps").args(["-o", field, "-p", &pid.to_string()]).output().await.ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+    // `lstart` is the full start date, e.g. "Sat Oct 18 10:02:11 2026".
+    Some(ProcessIdentity { start_time: ps("lstart=").await?, command_line: ps("command=").await? })
+}
+
+/// Start time and command line of the live process `pid`, or None if there is no such process.
+#[cfg(windows)]
+pub async fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    use crate::process_tree::command;
+    let script = format!(
+        "$p = Get-CimInstance Win32_Process -Filter 'ProcessId = {}'; if ($p) {{ $p.CreationDate.ToUniversalTime().ToString('o'); $p.CommandLine }}",
+        pid
+    );
+    let output = command("powershell").args(["-NoProfile", "-NonInteractive", "-Command", &script]).output().await.ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let mut lines = stdout.lines();
+    let start_time = lines.next().map(str::trim).filter(|line| !line.is_empty())?.to_string();
+    let command_line = lines.collect::<Vec<_>>().join(" ").trim().to_string();
+    Some(ProcessIdentity { start_time, command_line })
+}
+
+/// Writes the record for a process `spawn_managed_process` just started.
+pub async fn record_spawned_process(app_handle: &AppHandle<Wry>, process_name: &str, pid: u32) {
+    let Some(identity) = process_identity(pid).await else {
+        warn!("[PROCESS_RECORDS] Could not read the identity of '{}' (PID {}); it won't be recoverable after a crash.", process_name, pid);
+        return;
+    };
+    let record = ProcessRecord {
+        name: process_name.to_string(),
+        pid,
+        identity,
+        recorded_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        app_pid: std::process::id(),
+    };
+    let result = async {
+        let path = record_path(app_handle, process_name)?;
+        tokio::fs::create_dir_all(records_dir(app_handle)?).await.map_err(|e| format!("Failed to create records dir: {}", e))?;
+        let json = serde_json::to_string_pretty(&record).map_err(|e| format!("Failed to serialize process record: {}", e))?;
+        tokio::fs::write(&path, json).await.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+    .await;
+    if let Err(e) = result {
+        error!("[PROCESS_RECORDS] {}", e);
+    }
+}
+
+/// Removes the record for `process_name` if it still describes `pid` (a newer process may
+/// already have replaced it).
+pub async fn remove_process_record(app_handle: &AppHandle<Wry>, process_name: &str, pid: u32) {
+    let Ok(path) = record_path(app_handle, process_name) else { return };
+    let Ok(json) = tokio::fs::read_to_string(&path).await else { return };
+    let matches = serde_json::from_str::<ProcessRecord>(&json).map(|record| record.pid == pid).unwrap_or(true);
+    if matches {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            warn!("[PROCESS_RECORDS] Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+async fn scan_for_orphans(app_handle: &AppHandle<Wry>) -> Vec<ProcessRecord> {
+    let mut orphans = Vec::new();
+    let Ok(dir) = records_dir(app_handle) else { return orphans };
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else { return orphans };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let record = match tokio::fs::read_to_string(&path).await.map_err(|e| e.to_string()).and_then(|json| serde_json::from_str::<ProcessRecord>(&json).map_err(|e| e.to_string())) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("[PROCESS_RECORDS] Dropping unreadable record {}: {}", path.display(), e);
+                let _ = tokio::fs::remove_file(&path).await;
+                continue;
+            }
+        };
+        if record.app_pid == std::process::id() {
+            continue; // Started by this run
+        }
+        match process_identity(record.pid).await {
+            None => info!("[PROCESS_RECORDS] '{}' (PID {}) from an earlier run has exited.", record.name, record.pid),
+            Some(identity) if identity != record.identity => warn!(
+                "[PROCESS_RECORDS] PID {} recorded for '{}' now belongs to another program ({}); leaving it alone.",
+                record.pid, record.name, identity.command_line
+            ),
+            Some(_) => {
+                warn!("[PROCESS_RECORDS] Found orphaned '{}' (PID {}) from an earlier run.", record.name, record.pid);
+                orphans.push(record);
+                continue; // Keep the record until the orphan is adopted or killed
+            }
+        }
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+    orphans
+}
+
+/// Verified orphans not dealt with yet. The first call scans the records and announces any
+/// orphans with `orphaned-processes-detected`.
+pub async fn pending_orphans(app_handle: &AppHandle<Wry>) -> Vec<ProcessRecord> {
+    let mut pending = PENDING_ORPHANS.lock().await;
+    if pending.is_none() {
+        let orphans = scan_for_orphans(app_handle).await;
+        if !orphans.is_empty() {
+            if let Err(e) = app_handle.emit("orphaned-processes-detected", &orphans) {
+                error!("[PROCESS_RECORDS] Failed to emit orphaned-processes-detected event: {}", e);
+            }
+        }
+        *pending = Some(orphans);
+    }
+    pending.clone().unwrap_or_default()
+}
+
+async fn take_pending_orphan(app_handle: &AppHandle<Wry>, matches: impl Fn(&ProcessRecord) -> bool) -> Option<ProcessRecord> {
+    pending_orphans(app_handle).await; // Make sure the scan ran
+    let mut pending = PENDING_ORPHANS.lock().await;
+    let orphans = pending.as_mut()?;
+    let index = orphans.iter().position(matches)?;
+    Some(orphans.remove(index))
+}
+
+/// Stops a recorded process and its children, after checking once more that the PID still is
+/// the recorded process.
+pub async fn kill_recorded_process(app_handle: &AppHandle<Wry>, record: &ProcessRecord) -> ProcessStopReport {
+    let report = if process_identity(record.pid).await.as_ref() == Some(&record.identity) {
+        terminate_pid_tree(&record.name, record.pid, GRACEFUL_SHUTDOWN_TIMEOUT).await
+    } else {
+        info!("[PROCESS_RECORDS] '{}' (PID {}) is no longer the recorded process; nothing to stop.", record.name, record.pid);
+        ProcessStopReport {
+            name: record.name.clone(),
+            pid: record.pid,
+            outcome: StopOutcome::AlreadyExited,
+            descendants: Vec::new(),
+            survivors: Vec::new(),
+            elapsed_ms: 0,
+            error: None,
+        }
+    };
+    if report.outcome != StopOutcome::Failed {
+        remove_process_record(app_handle, &record.name, record.pid).await;
+    }
+    report
+}
+
+// The `--port` a recorded sidecar was started with.
+fn recorded_sidecar_port(record: &ProcessRecord) -> Option<u16> {
+    let args: Vec<&str> = record.identity.command_line.split_whitespace().collect();
+    args.windows(2).find(|pair| pair[0] == "--port").and_then(|pair| pair[1].parse().ok())
+}
+
+/// Hands an orphan to the ProcessManager. A sidecar is only adopted if it still answers, and
+/// becomes the server the app talks to.
+async fn adopt_orphan(app_handle: &AppHandle<Wry>, record: ProcessRecord) -> Result<(), String> {
+    let process_manager = app_handle.state::<ProcessManager>();
+    if process_manager.is_process_running(&record.name) {
+        return Err(format!("'{}' is already running; stop it before adopting the old one.", record.name));
+    }
+    if record.name == SIDECAR_PROCESS_NAME {
+        let port = recorded_sidecar_port(&record).ok_or_else(|| "The orphaned ComfyUI was started without --port.".to_string())?;
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        let url = format!("http://{}:{}/queue", COMFYUI_LISTEN_HOST, port);
+        match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => return Err(format!("The orphaned ComfyUI on port {} answered HTTP {}.", port, response.status())),
+            Err(e) => return Err(format!("The orphaned ComfyUI on port {} is not responding: {}", port, e)),
+        }
+        set_active_port(app_handle, port);
+    }
+    info!("[PROCESS_RECORDS] Adopted orphaned '{}' (PID {}).", record.name, record.pid);
+    process_manager.adopt_process(app_handle, record);
+    Ok(())
+}
+
+/// Called right before the sidecar is spawned. Returns true if a leftover sidecar from an earlier
+/// run was adopted instead, so there is nothing to spawn. A leftover that doesn't answer is killed
+/// first, so the new one gets its port back.
+pub async fn settle_orphaned_sidecar(app_handle: &AppHandle<Wry>) -> bool {
+    let Some(record) = take_pending_orphan(app_handle, |r| r.name == SIDECAR_PROCESS_NAME).await else {
+        return false;
+    };
+    let pid = record.pid;
+    match adopt_orphan(app_handle, record.clone()).await {
+        Ok(()) => {
+            emit_backend_status(app_handle, "adopted_orphan", format!("Reconnected to the ComfyUI backend left running by the last session (PID {}).", pid), false);
+            true
+        }
+        Err(e) => {
+            warn!("[PROCESS_RECORDS] Not adopting orphaned sidecar (PID {}): {}. Stopping it.", pid, e);
+            kill_recorded_process(app_handle, &record).await;
+            false
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_orphaned_processes(app_handle: AppHandle<Wry>) -> Result<Vec<ProcessRecord>, String> {
+    Ok(pending_orphans(&app_handle).await)
+}
+
+/// Adopts or kills a verified orphan reported by `get_orphaned_processes`.
+#[tauri::command]
+pub async fn resolve_orphaned_process(app_handle: AppHandle<Wry>, pid: u32, action: OrphanAction) -> Result<(), String> {
+    let record = take_pending_orphan(&app_handle, |r| r.pid == pid)
+        .await
+        .ok_or_else(|| format!("No orphaned process with PID {} is pending.", pid))?;
+    match action {
+        OrphanAction::Adopt => {
+            if let Err(e) = adopt_orphan(&app_handle, record.clone()).await {
+                // Still unresolved; put it back so the user can choose again.
+                if let Some(pending) = PENDING_ORPHANS.lock().await.as_mut() {
+                    pending.push(record);
+                }
+                return Err(e);
+            }
+            Ok(())
+        }
+        OrphanAction::Kill => {
+            let report = kill_recorded_process(&app_handle, &record).await;
+            if report.outcome == StopOutcome::Failed {
+                return Err(format!("Failed to stop '{}' (PID {}): still alive {:?}", record.name, pid, report.survivors));
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// metamorphosis-app/src-tauri/src/process_tree.rs
+//
+// Staged termination of a managed process and everything it started. The sidecar is not one
+// process: the conda `run` wrapper starts Python, which starts its own workers, and killing only
+// the PID we spawned leaves the rest running (and holding the port and the GPU).
+//
+// Stopping a process therefore works on the whole tree, snapshotted before anything is signalled
+// so children that get re-parented when their parent exits are still found:
+//   1. ask politely: SIGTERM on Unix, `taskkill /T` without `/F` on Windows,
+//   2. wait up to the graceful timeout for the tree to exit,
+//   3. force-kill whatever is left (SIGKILL / `taskkill /F`) and wait a little longer.
+// Liveness is checked against the OS process table, not the ProcessManager's tracking map, so the
+// same logic works for processes that were already untracked. A PID freed during the graceful wait
+// can be reused by an unrelated process, so the forced stage only kills PIDs whose start time still
+// matches the one recorded in the snapshot.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use futures_util::future::join_all;
+use log::{error, info, warn};
+use serde::Serialize;
+use tauri_plugin_shell::process::CommandChild;
+use tokio::process::Command;
+
+use crate::process_records::process_identity;
+
+// How long a process gets to exit after the polite signal before it is force-killed.
+pub const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+// How long to wait for the OS to tear down the tree after the forced kill.
+const FORCED_KILL_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000; // Keeps ps/taskkill from flashing a console window
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StopOutcome {
+    AlreadyExited, // Gone before anything was signalled
+    Graceful,      // Exited after the polite signal
+    Forced,        // Had to be force-killed
+    Failed,        // Still (partly) alive after the forced kill, or the tree couldn't be inspected
+}
+
+/// What happened when a managed process was stopped.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessStopReport {
+    pub name: String,
+    pub pid: u32,
+    pub outcome: StopOutcome,
+    pub descendants: Vec<u32>, // Child processes found when the stop started
+    pub survivors: Vec<u32>,   // PIDs still alive at the end (only for Failed)
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+pub(crate) fn command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Parses `pid ppid` lines into a pid -> parent pid map, skipping anything that isn't two numbers.
+fn parse_process_table(output: &str) -> HashMap<u32, u32> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            Some((pid, ppid))
+        })
+        .collect()
+}
+
+/// Every live process as pid -> parent pid.
+#[cfg(unix)]
+pub async fn process_table() -> Result<HashMap<u32, u32>, String> {
+    let output = command("ps")
+        .args(["-A", "-o", "pid=,ppid=,stat="])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ps: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ps exited with {}", output.status));
+    }
+    // Zombies have already exited; they only wait for their parent to reap them.
+    let live: String = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.split_whitespace().nth(2).is_some_and(|stat| stat.starts_with('Z')))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    Ok(parse_process_table(&live))
+}
+
+/// Every live process as pid -> parent pid.
+#[cfg(windows)]
+pub async fn process_table() -> Result<HashMap<u32, u32>, String> {
+    let output = command("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId) $($_.ParentProcessId)\" }",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Process listing exited with {}", output.status));
+    }
+    Ok(parse_process_table(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// All descendants of `root` in `table`, parents before children.
+pub fn descendants(root: u32, table: &HashMap<u32, u32>) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&pid, &ppid) in table {
+        if pid != ppid {
+            children.entry(ppid).or_default().push(pid);
+        }
+    }
+    // Windows can report a recycled parent PID, which can make the "tree" cyclic.
+    let mut seen = HashSet::from([root]);
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(pid) = queue.pop_front() {
+        for &child in children.get(&pid).map(Vec::as_slice).unwrap_or_default() {
+            if seen.insert(child) {
+                found.push(child);
+                queue.push_back(child);
+            }
+        }
+    }
+    found
+}
+
+/// Sends the polite (`force == false`) or forced termination signal to each of `pids`.
+/// PIDs that already exited are not an error.
+#[cfg(unix)]
+async fn signal_processes(pids: &[u32], force: bool) -> Result<(), String> {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    // kill reports failure if any PID is already gone, so only a failure to run it counts.
+    command("kill")
+        .arg(signal)
+        .args(pids.iter().map(u32::to_string))
+        .output()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run kill {}: {}", signal, e))
+}
+
+/// Sends the polite (`force == false`) or forced termination signal to each of `pids`.
+/// PIDs that already exited are not an error.
+#[cfg(windows)]
+async fn signal_processes(pids: &[u32], force: bool) -> Result<(), String> {
+    for pid in pids {
+        let mut cmd = command("taskkill");
+        if force {
+            cmd.arg("/F");
+        }
+        // Console programs usually ignore the polite request; the forced stage handles them.
+        cmd.args(["/T", "/PID", &pid.to_string()])
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run taskkill for PID {}: {}", pid, e))?;
+    }
+    Ok(())
+}
+
+/// Polls the process table until none of `pids` are alive or `timeout` passes.
+/// Returns the ones still alive.
+async fn wait_for_exit(pids: &[u32], timeout: Duration) -> Result<Vec<u32>, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let table = process_table().await?;
+        let alive: Vec<u32> = pids.iter().copied().filter(|pid| table.contains_key(pid)).collect();
+        if alive.is_empty() || Instant::now() >= deadline {
+            return Ok(alive);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Start time of each of `pids` that is still alive, to tell it apart from a later process that
+/// reuses the PID.
+async fn start_times(pids: &[u32]) -> HashMap<u32, String> {
+    join_all(pids.iter().map(|&pid| async move { process_identity(pid).await.map(|identity| (pid, identity.start_time)) }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// The PIDs in `pids` that are still the processes recorded in `snapshot`.
+async fn still_same_processes(pids: &[u32], snapshot: &HashMap<u32, String>) -> Vec<u32> {
+    let current = start_times(pids).await;
+    pids.iter().copied().filter(|pid| current.get(pid).is_some_and(|start| snapshot.get(pid) == Some(start))).collect()
+}
+
+/// Stops `child` and all of its descendants in stages; see the module comment.
+pub async fn terminate_process_tree(name: &str, child: CommandChild, graceful_timeout: Duration) -> ProcessStopReport {
+    terminate_tree(name, child.pid(), Some(child), graceful_timeout).await
+}
+
+/// Like `terminate_process_tree`, for a process we have no handle to (e.g. one left over from a
+/// previous run). The caller must have checked that `pid` still is the process it means.
+pub async fn terminate_pid_tree(name: &str, pid: u32, graceful_timeout: Duration) -> ProcessStopReport {
+    terminate_tree(name, pid, None, graceful_timeout).await
+}
+
+// Last resort when the staged shutdown can't run: kill the root through its handle, if we have one.
+fn kill_directly(child: Option<CommandChild>) -> StopOutcome {
+    match child.map(|child| child.kill()) {
+        Some(Ok(())) => StopOutcome::Forced,
+        _ => StopOutcome::Failed,
+    }
+}
+
+async fn terminate_tree(name: &str, pid: u32, child: Option<CommandChild>, graceful_timeout: Duration) -> ProcessStopReport {
+    let started = Instant::now();
+    let mut report = ProcessStopReport {
+        name: name.to_string(),
+        pid,
+        outcome: StopOutcome::Failed,
+        descendants: Vec::new(),
+        survivors: Vec::new(),
+        elapsed_ms: 0,
+        error: None,
+    };
+
+    let table = match process_table().await {
+        Ok(table) => table,
+        Err(e) => {
+            // Without the process table there is no tree to walk; kill what we can reach directly.
+            warn!("[PROCESS_SHUTDOWN] Cannot list processes ({}); force-killing '{}' (PID {}) only.", e, name, pid);
+            report.outcome = kill_directly(child);
+            report.error = Some(e);
+            report.elapsed_ms = started.elapsed().as_millis() as u64;
+            return report;
+        }
+    };
+    // Once the root is gone its PID can be reused, so children "of" it may belong to someone else.
+    if !table.contains_key(&pid) {
+        report.outcome = StopOutcome::AlreadyExited;
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        return report;
+    }
+    report.descendants = descendants(pid, &table);
+
+    let mut tree = vec![pid];
+    tree.extend(&report.descendants);
+    let snapshot = start_times(&tree).await;
+    info!("[PROCESS_SHUTDOWN] Stopping '{}' (PID {}) and {} descendant(s): {:?}", name, pid, report.descendants.len(), report.descendants);
+
+    let result: Result<StopOutcome, String> = async {
+        signal_processes(&tree, false).await?;
+        let remaining = wait_for_exit(&tree, graceful_timeout).await?;
+        // Anything that exited during the wait may have had its PID reused by now.
+        let remaining = still_same_processes(&remaining, &snapshot).await;
+        if remaining.is_empty() {
+            return Ok(StopOutcome::Graceful);
+        }
+
+        warn!("[PROCESS_SHUTDOWN] '{}' did not exit within {:?}; force-killing {:?}", name, graceful_timeout, remaining);
+        signal_processes(&remaining, true).await?;
+        let survivors = wait_for_exit(&remaining, FORCED_KILL_TIMEOUT).await?;
+        report.survivors = still_same_processes(&survivors, &snapshot).await;
+        Ok(if report.survivors.is_empty() { StopOutcome::Forced } else { StopOutcome::Failed })
+    }
+    .await;
+
+    match result {
+        Ok(outcome) => report.outcome = outcome,
+        Err(e) => {
+            error!("[PROCESS_SHUTDOWN] Staged shutdown of '{}' failed: {}; force-killing PID {}", name, e, pid);
+            report.outcome = kill_directly(child);
+            report.error = Some(e);
+        }
+    }
+    report.elapsed_ms = started.elapsed().as_millis() as u64;
+    match report.outcome {
+        StopOutcome::Failed => error!("[PROCESS_SHUTDOWN] '{}' (PID {}) could not be fully stopped; still alive: {:?}", name, pid, report.survivors),
+        outcome => info!("[PROCESS_SHUTDOWN] '{}' (PID {}) stopped: {:?} after {} ms", name, pid, outcome, report.elapsed_ms),
+    }
+    report
+}
@@ -0,0 +1,125 @@
+// metamorphosis-app/src-tauri/src/settings.rs
+//
+// User-adjustable application settings, persisted as JSON in the app config directory.
+// Settings are read from disk on demand so that changes made through `update_app_settings`
+// are picked up by the next setup or sidecar launch without extra state plumbing.
+
+use std::fs;
+use std::path::PathBuf;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::setup_manager::env_backend::EnvBackendKind;
+use crate::sidecar_manager::endpoint::DEFAULT_COMFYUI_PORT;
+use crate::sidecar_manager::external_backend::{allow_external_backend_url, validate_base_url, BackendMode};
+use crate::sidecar_manager::launch_profile::LaunchProfile;
+use crate::sidecar_manager::lan_proxy::DEFAULT_LAN_PROXY_PORT;
+
+const SETTINGS_FILENAME: &str = "settings.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    /// Run the deep verification (models, custom nodes, Python env, torch device) on every startup.
+    pub deep_verification_on_startup: bool,
+    /// Where ComfyUI's Python environment comes from: a Miniconda env or a venv built from the
+    /// bundled interpreter. Changing it takes effect on the next setup run.
+    pub env_backend: EnvBackendKind,
+    /// ComfyUI-Manager style `custom-node-list.json` that user-installable node packs are listed
+    /// from. None uses `custom-node-list.json` in the app data directory.
+    pub custom_node_registry_path: Option<String>,
+    /// Port ComfyUI is started on. If it is taken at launch, a free port is used instead.
+    pub comfyui_port: u16,
+    /// Let other machines on the network use ComfyUI through the token-protected proxy.
+    /// ComfyUI itself always listens on loopback only.
+    pub lan_access_enabled: bool,
+    /// Port the LAN proxy listens on when LAN access is enabled.
+    pub lan_proxy_port: u16,
+    /// Run ComfyUI as a managed sidecar, or use an existing ComfyUI at `external_comfyui_url`
+    /// and skip setup entirely.
+    pub backend_mode: BackendMode,
+    /// Base URL of the external ComfyUI, e.g. `http://192.168.1.20:8188`.
+    pub external_comfyui_url: Option<String>,
+    /// ComfyUI launch flags and environment. None uses the preset for the detected VRAM.
+    pub launch_profile: Option<LaunchProfile>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            deep_verification_on_startup: false,
+            env_backend: EnvBackendKind::Conda,
+            custom_node_registry_path: None,
+            comfyui_port: DEFAULT_COMFYUI_PORT,
+            lan_access_enabled: false,
+            lan_proxy_port: DEFAULT_LAN_PROXY_PORT,
+            backend_mode: BackendMode::Managed,
+            external_comfyui_url: None,
+            launch_profile: None,
+        }
+    }
+}
+
+fn get_settings_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let config_dir = app_handle.path().app_config_dir().map_err(|e| format!("Failed to get app config dir: {}", e))?;
+    Ok(config_dir.join(SETTINGS_FILENAME))
+}
+
+/// Loads settings from disk. A missing or unreadable file yields the defaults.
+pub fn load_settings(app_handle: &AppHandle<Wry>) -> AppSettings {
+    let settings_path = match get_settings_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("[SETTINGS] {}. Using default settings.", e);
+            return AppSettings::default();
+        }
+    };
+    if !settings_path.exists() {
+        return AppSettings::default();
+    }
+    match fs::read_to_string(&settings_path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("[SETTINGS] Failed to parse {}: {}. Using default settings.", settings_path.display(), e);
+            AppSettings::default()
+        }),
+        Err(e) => {
+            warn!("[SETTINGS] Failed to read {}: {}. Using default settings.", settings_path.display(), e);
+            AppSettings::default()
+        }
+    }
+}
+
+pub fn save_settings(app_handle: &AppHandle<Wry>, settings: &AppSettings) -> Result<(), String> {
+    let settings_path = get_settings_path(app_handle)?;
+    if let Some(parent) = settings_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&settings_path, json).map_err(|e| format!("Failed to write settings to {}: {}", settings_path.display(), e))?;
+    info!("[SETTINGS] Saved settings to {}", settings_path.display());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_app_settings(app_handle: AppHandle<Wry>) -> Result<AppSettings, String> {
+    Ok(load_settings(&app_handle))
+}
+
+#[tauri::command]
+pub async fn update_app_settings(app_handle: AppHandle<Wry>, mut settings: AppSettings) -> Result<AppSettings, String> {
+    // Stored without surrounding whitespace or a trailing slash; an empty URL means none.
+    settings.external_comfyui_url = settings
+        .external_comfyui_url
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &settings.external_comfyui_url {
+        validate_base_url(url)?;
+    }
+    if settings.backend_mode == BackendMode::External && settings.external_comfyui_url.is_none() {
+        return Err("External backend mode needs the URL of the ComfyUI to use.".to_string());
+    }
+    save_settings(&app_handle, &settings)?;
+    allow_external_backend_url(&app_handle)?;
+    Ok(settings)
+}
@@ -0,0 +1,214 @@
+// metamorphosis-app/src-tauri/src/setup_manager/component_reset.rs
+//
+// Commands to reset (delete) a single installed component so the next setup run reinstalls it,
+// plus a full uninstall of everything setup downloaded. The install manifest is updated so that
+// the next startup reports exactly what was removed via `IncrementalSetupRequired`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::process_manager::ProcessManager;
+use super::env_backend::get_venv_path;
+use super::custom_node_manager::node_definitions::{CLIPSEG_NODE_NAME, CUSTOM_NODE_MANIFEST};
+use super::install_manifest::{forget_installed_component, get_master_marker_path, ComponentKind};
+use super::model_config::get_core_models_list;
+use super::orchestration::{get_app_root_path, SetupTaskState, MINICONDA_INSTALLED_MARKER, MINICONDA_INSTALL_DIR_NAME};
+use super::python_utils::get_comfyui_directory_path;
+
+const CONDA_ENV_NAME: &str = "comfyui_env";
+const CONDA_DEPS_MARKER: &str = ".conda_env_deps_installed.marker";
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedPath {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetReport {
+    pub component: String,
+    pub removed: Vec<RemovedPath>,
+    pub reclaimed_bytes: u64,
+}
+
+impl ResetReport {
+    fn new(component: &str) -> Self {
+        ResetReport { component: component.to_string(), removed: Vec::new(), reclaimed_bytes: 0 }
+    }
+
+    fn merge(&mut self, other: ResetReport) {
+        self.reclaimed_bytes += other.reclaimed_bytes;
+        self.removed.extend(other.removed);
+    }
+}
+
+/// Deleting files out from under a running ComfyUI or setup run leaves it in an undefined state, so refuse.
+pub(crate) fn ensure_backend_idle(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    let process_manager = app_handle.state::<ProcessManager>();
+    if process_manager.is_process_running("comfyui_sidecar") {
+        return Err("ComfyUI is running. Stop the backend before modifying installed components.".to_string());
+    }
+    if app_handle.state::<SetupTaskState>().is_running() {
+        return Err("Setup is running. Cancel it before modifying installed components.".to_string());
+    }
+    Ok(())
+}
+
+/// Rejects names that could escape the directory they are joined onto.
+fn validate_component_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') || name.contains('\\') {
+        return Err(format!("Invalid component name: '{}'", name));
+    }
+    Ok(())
+}
+
+fn path_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| path_size(&e.path())).sum())
+            .unwrap_or(0)
+    } else {
+        metadata.len()
+    }
+}
+
+/// Removes a file or directory if present and records it in the report.
+fn remove_path(path: &Path, report: &mut ResetReport) -> Result<(), String> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return Ok(()), // Nothing to remove
+    };
+    let bytes = path_size(path);
+    if metadata.is_dir() {
+        fs::remove_dir_all(path).map_err(|e| format!("Failed to remove directory {}: {}", path.display(), e))?;
+    } else {
+        fs::remove_file(path).map_err(|e| format!("Failed to remove file {}: {}", path.display(), e))?;
+    }
+    info!("[COMPONENT_RESET] Removed {} ({} bytes)", path.display(), bytes);
+    report.removed.push(RemovedPath { path: path.display().to_string(), bytes });
+    report.reclaimed_bytes += bytes;
+    Ok(())
+}
+
+fn miniconda_install_path() -> Result<PathBuf, String> {
+    Ok(get_app_root_path()?.join(MINICONDA_INSTALL_DIR_NAME))
+}
+
+fn reset_conda_env_inner(app_handle: &AppHandle<Wry>) -> Result<ResetReport, String> {
+    let mut report = ResetReport::new(CONDA_ENV_NAME);
+    remove_path(&miniconda_install_path()?.join("envs").join(CONDA_ENV_NAME), &mut report)?;
+    remove_path(&get_venv_path()?, &mut report)?;
+    remove_path(&get_comfyui_directory_path(app_handle)?.join(CONDA_DEPS_MARKER), &mut report)?;
+    forget_installed_component(app_handle, ComponentKind::PythonRequirements, CONDA_ENV_NAME)?;
+    Ok(report)
+}
+
+fn reset_miniconda_inner(app_handle: &AppHandle<Wry>) -> Result<ResetReport, String> {
+    let mut report = ResetReport::new(MINICONDA_INSTALL_DIR_NAME);
+    // The conda env lives inside the Miniconda directory, so its markers go too.
+    report.merge(reset_conda_env_inner(app_handle)?);
+    let app_root_path = get_app_root_path()?;
+    remove_path(&app_root_path.join(MINICONDA_INSTALL_DIR_NAME), &mut report)?;
+    remove_path(&app_root_path.join(MINICONDA_INSTALLED_MARKER), &mut report)?;
+    Ok(report)
+}
+
+fn reset_custom_node_inner(app_handle: &AppHandle<Wry>, node_name: &str) -> Result<ResetReport, String> {
+    validate_component_name(node_name)?;
+    let custom_nodes_dir = get_comfyui_directory_path(app_handle)?.join("custom_nodes");
+    let mut report = ResetReport::new(node_name);
+    remove_path(&custom_nodes_dir.join(node_name), &mut report)?;
+    if node_name == CLIPSEG_NODE_NAME {
+        // CLIPSeg's node file is copied out of the repo into custom_nodes.
+        remove_path(&custom_nodes_dir.join("clipseg.py"), &mut report)?;
+    }
+    if report.removed.is_empty() {
+        warn!("[COMPONENT_RESET] Custom node {} was not installed.", node_name);
+    }
+    forget_installed_component(app_handle, ComponentKind::CustomNode, node_name)?;
+    Ok(report)
+}
+
+fn reset_model_inner(app_handle: &AppHandle<Wry>, model_id: &str) -> Result<ResetReport, String> {
+    let model = get_core_models_list()
+        .into_iter()
+        .find(|m| m.id == model_id)
+        .ok_or_else(|| format!("Unknown model id: '{}'", model_id))?;
+    let models_base_path = get_comfyui_directory_path(app_handle)?.join("models");
+    let mut report = ResetReport::new(model_id);
+    remove_path(&models_base_path.join(&model.target_subdir).join(&model.target_filename), &mut report)?;
+    forget_installed_component(app_handle, ComponentKind::Model, model_id)?;
+    Ok(report)
+}
+
+fn reset_master_marker_inner(app_handle: &AppHandle<Wry>) -> Result<ResetReport, String> {
+    let mut report = ResetReport::new("master_marker");
+    remove_path(&get_master_marker_path(app_handle)?, &mut report)?;
+    Ok(report)
+}
+
+/// Removes Miniconda and the conda env together with their markers.
+#[tauri::command]
+pub async fn reset_miniconda(app_handle: AppHandle<Wry>) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    reset_miniconda_inner(&app_handle)
+}
+
+/// Removes the `comfyui_env` conda env (and the venv of the Venv backend, if any) and its
+/// dependency marker; Miniconda itself is kept.
+#[tauri::command]
+pub async fn reset_conda_env(app_handle: AppHandle<Wry>) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    reset_conda_env_inner(&app_handle)
+}
+
+#[tauri::command]
+pub async fn reset_custom_node(app_handle: AppHandle<Wry>, node_name: String) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    reset_custom_node_inner(&app_handle, &node_name)
+}
+
+#[tauri::command]
+pub async fn reset_model(app_handle: AppHandle<Wry>, model_id: String) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    reset_model_inner(&app_handle, &model_id)
+}
+
+/// Removes only the master marker, forcing a full (but idempotent) setup run on next start.
+#[tauri::command]
+pub async fn reset_master_marker(app_handle: AppHandle<Wry>) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    reset_master_marker_inner(&app_handle)
+}
+
+/// Removes everything setup downloaded: Miniconda and the env, core custom nodes, core models
+/// and the master marker. The bundled ComfyUI in `vendor` is left in place, and so are node packs
+/// the user installed from the registry (see `user_registry`).
+#[tauri::command]
+pub async fn uninstall_all_components(app_handle: AppHandle<Wry>) -> Result<ResetReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    info!("[COMPONENT_RESET] Uninstalling all downloaded components...");
+    let mut report = ResetReport::new("all");
+
+    // Remove the marker first so an interrupted uninstall still triggers a full setup next time.
+    report.merge(reset_master_marker_inner(&app_handle)?);
+    report.merge(reset_miniconda_inner(&app_handle)?);
+    for node in CUSTOM_NODE_MANIFEST {
+        report.merge(reset_custom_node_inner(&app_handle, node.name)?);
+    }
+    for model in get_core_models_list() {
+        report.merge(reset_model_inner(&app_handle, &model.id)?);
+    }
+
+    info!("[COMPONENT_RESET] Uninstall complete. Reclaimed {} bytes across {} paths.", report.reclaimed_bytes, report.removed.len());
+    Ok(report)
+}
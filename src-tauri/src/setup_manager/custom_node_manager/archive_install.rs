@@ -0,0 +1,154 @@
+// metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/archive_install.rs
+//
+// Installs a custom node without git by downloading the repository archive for the wanted
+// revision and extracting it into `custom_nodes/<name>`. Fresh Windows machines often have no
+// git, and cloning is the only thing setup needs it for.
+// A `.metamorphosis-source.json` file in the node directory records where the files came from,
+// so later runs can tell an archive install apart from a checkout and re-download on a new pin.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use futures_util::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+pub const ARCHIVE_SOURCE_FILENAME: &str = ".metamorphosis-source.json";
+
+/// Contents of `.metamorphosis-source.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSource {
+    pub repo_url: String,
+    pub revision: Option<String>, // Requested revision, None for the default branch
+    pub commit: Option<String>,   // Commit the revision resolved to, when GitHub told us
+    pub installed_at: String,
+}
+
+/// Splits `https://github.com/<owner>/<repo>(.git)` into (owner, repo).
+fn parse_github_url(repo_url: &str) -> Option<(String, String)> {
+    let path = repo_url.trim_end_matches('/').strip_prefix("https://github.com/")?;
+    let mut parts = path.split('/');
+    let owner = parts.next()?.to_string();
+    let repo = parts.next()?.trim_end_matches(".git").to_string();
+    if parts.next().is_some() || owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+    Some((owner, repo))
+}
+
+pub fn read_archive_source(node_dir: &Path) -> Option<ArchiveSource> {
+    let json = fs::read_to_string(node_dir.join(ARCHIVE_SOURCE_FILENAME)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent("MetamorphosisApp/1.0")
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(Duration::from_secs(60 * 10))
+        .build()
+        .map_err(|e| format!("Failed to build reqwest client: {}", e))
+}
+
+/// Asks GitHub which commit `git_ref` points at. Best effort: the unauthenticated API is rate limited.
+async fn resolve_commit(client: &reqwest::Client, owner: &str, repo: &str, git_ref: &str) -> Option<String> {
+    let url = format!("https://api.github.com/repos/{}/{}/commits/{}", owner, repo, git_ref);
+    let response = client.get(&url).header("Accept", "application/vnd.github.sha").send().await.ok()?;
+    if !response.status().is_success() {
+        warn!("[CUSTOM_NODE_SETUP] Could not resolve {}/{}@{} to a commit: HTTP {}", owner, repo, git_ref, response.status());
+        return None;
+    }
+    let sha = response.text().await.ok()?.trim().to_string();
+    (sha.len() == 40).then_some(sha)
+}
+
+/// Extracts a GitHub archive zip into `target_dir`, dropping the `<repo>-<ref>/` top-level directory.
+fn extract_repository_zip(archive_path: &Path, target_dir: &Path) -> Result<(), String> {
+    let file = fs::File::open(archive_path).map_err(|e| format!("Failed to open archive {}: {}", archive_path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Failed to read archive {}: {}", archive_path.display(), e))?;
+    fs::create_dir_all(target_dir).map_err(|e| format!("Failed to create {}: {}", target_dir.display(), e))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| format!("Error reading archive entry {}: {}", i, e))?;
+        let Some(enclosed) = entry.enclosed_name() else {
+            warn!("[CUSTOM_NODE_SETUP] Skipping archive entry with unsafe path: {}", entry.name());
+            continue;
+        };
+        let relative: PathBuf = enclosed.components().skip(1).collect();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let out_path = target_dir.join(&relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path).map_err(|e| format!("Failed to create {}: {}", out_path.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut out_file = fs::File::create(&out_path).map_err(|e| format!("Failed to create {}: {}", out_path.display(), e))?;
+        std::io::copy(&mut entry, &mut out_file).map_err(|e| format!("Failed to extract {}: {}", out_path.display(), e))?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&out_path, fs::Permissions::from_mode(mode));
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the archive of `repo_url` at `revision` (default branch when None) and extracts it
+/// into `target_dir`, which must not exist yet. Only GitHub repositories are supported.
+pub async fn install_from_archive(node_name: &str, repo_url: &str, revision: Option<&str>, target_dir: &Path) -> Result<ArchiveSource, String> {
+    let (owner, repo) = parse_github_url(repo_url)
+        .ok_or_else(|| format!("Cannot install {} without git: {} is not a GitHub repository.", node_name, repo_url))?;
+    let git_ref = revision.unwrap_or("HEAD");
+    let archive_url = format!("https://github.com/{}/{}/archive/{}.zip", owner, repo, git_ref);
+    info!("[CUSTOM_NODE_SETUP] Downloading {} from {}", node_name, archive_url);
+
+    let client = http_client()?;
+    let commit = resolve_commit(&client, &owner, &repo, git_ref).await;
+    let response = client.get(&archive_url).send().await.map_err(|e| format!("Failed to download {}: {}", archive_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: HTTP {}", archive_url, response.status()));
+    }
+
+    let parent = target_dir.parent().ok_or_else(|| format!("{} has no parent directory", target_dir.display()))?;
+    let archive_path = parent.join(format!(".{}.download.zip", node_name));
+    let staging_dir = parent.join(format!(".{}.partial", node_name));
+    let _cleanup = scopeguard::guard((archive_path.clone(), staging_dir.clone()), |(archive, staging)| {
+        let _ = fs::remove_file(archive);
+        let _ = fs::remove_dir_all(staging);
+    });
+
+    let mut archive_file = fs::File::create(&archive_path).map_err(|e| format!("Failed to create {}: {}", archive_path.display(), e))?;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Error while downloading {}: {}", archive_url, e))?;
+        archive_file.write_all(&chunk).map_err(|e| format!("Failed to write {}: {}", archive_path.display(), e))?;
+    }
+    drop(archive_file);
+
+    // Extract next to the target and rename, so a failed extraction never leaves a half-populated node.
+    let _ = fs::remove_dir_all(&staging_dir);
+    let (archive_for_extract, staging_for_extract) = (archive_path.clone(), staging_dir.clone());
+    tokio::task::spawn_blocking(move || extract_repository_zip(&archive_for_extract, &staging_for_extract))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))??;
+
+    let source = ArchiveSource {
+        repo_url: repo_url.to_string(),
+        revision: revision.map(|r| r.to_string()),
+        commit,
+        installed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let json = serde_json::to_string_pretty(&source).map_err(|e| format!("Failed to serialize archive source: {}", e))?;
+    fs::write(staging_dir.join(ARCHIVE_SOURCE_FILENAME), json).map_err(|e| format!("Failed to write archive source file: {}", e))?;
+    fs::rename(&staging_dir, target_dir).map_err(|e| format!("Failed to move {} into place: {}", node_name, e))?;
+
+    info!("[CUSTOM_NODE_SETUP] Installed {} from archive ({}).", node_name, source.commit.as_deref().unwrap_or(git_ref));
+    Ok(source)
+}
@@ -20,12 +20,17 @@ use crate::setup_manager::event_utils::{
 use crate::setup_manager::python_utils::get_comfyui_directory_path;
 use crate::process_manager::ProcessManager;
 
-/// Generic function to clone a custom node repository and install its dependencies.
+type InstallDependenciesFn = for<'a> fn(&'a AppHandle<Wry>, &str, &Path) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Generic function to clone a custom node repository, check out `revision` (if pinned) and
+/// install its dependencies. An existing checkout is verified against `revision` instead of
+/// being skipped outright.
 pub async fn clone_repository_to_custom_nodes(
     app_handle: &AppHandle<Wry>,
     node_name: &str,
     repo_url: &str,
-    install_dependencies_fn: Option<for<'a> fn(&'a AppHandle<Wry>, &str, &Path) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>>,
+    revision: Option<&str>,
+    install_dependencies_fn: Option<InstallDependenciesFn>,
 ) -> Result<(), String> {
     info!("[CUSTOM_NODE_SETUP] Attempting to clone {}...", node_name);
     emit_custom_node_clone_start(app_handle, node_name);
@@ -52,6 +57,16 @@ pub async fn clone_repository_to_custom_nodes(
         } else {
             info!("[CUSTOM_NODE_SETUP] Target directory {} for {} already exists and is not empty. Skipping clone.", target_dir.display(), node_name);
             emit_custom_node_already_exists(app_handle, node_name);
+            if let Some(source) = read_archive_source(&target_dir) {
+                // Installed from an archive: there is no checkout to verify, so re-download when the pin changed.
+                if revision.is_some() && source.revision.as_deref() != revision {
+                    info!("[CUSTOM_NODE_SETUP] {} was installed from an archive at {:?}; replacing it with {:?}.", node_name, source.revision, revision);
+                    std_fs::remove_dir_all(&target_dir).map_err(|e| e.to_string())?;
+                    install_from_archive_reporting(app_handle, node_name, repo_url, revision, &target_dir).await?;
+                }
+            } else {
+                ensure_pinned_checkout(app_handle, node_name, &target_dir, revision).await?;
+            }
             if let Some(install_fn) = install_dependencies_fn {
                 return install_fn(app_handle, node_name, &target_dir).await;
             }
@@ -59,18 +74,19 @@ pub async fn clone_repository_to_custom_nodes(
         }
     }
 
+    if !git_available().await {
+        warn!("[CUSTOM_NODE_SETUP] Git is not available. Installing {} from a repository archive instead.", node_name);
+        install_from_archive_reporting(app_handle, node_name, repo_url, revision, &target_dir).await?;
+        emit_custom_node_clone_success(app_handle, node_name);
+        if let Some(install_fn) = install_dependencies_fn {
+            return install_fn(app_handle, node_name, &target_dir).await;
+        }
+        return Ok(());
+    }
+
     info!("[CUSTOM_NODE_SETUP] Cloning {} into {}", repo_url, target_dir.display());
 
-    let git_target_path_arg_string = if cfg!(windows) {
-        let path_str_cow = target_dir.to_string_lossy();
-        if path_str_cow.starts_with("\\\\?\\") {
-            path_str_cow.trim_start_matches("\\\\?\\").to_string()
-        } else {
-            path_str_cow.into_owned()
-        }
-    } else {
-        target_dir.to_string_lossy().into_owned()
-    };
+    let git_target_path_arg_string = git_path_arg(&target_dir);
 
     let command = app_handle.shell().command("git")
         .args(&["clone", repo_url, &git_target_path_arg_string]);
@@ -92,6 +108,7 @@ pub async fn clone_repository_to_custom_nodes(
 
     if success {
         info!("[CUSTOM_NODE_SETUP] Successfully cloned {}.", node_name);
+        ensure_pinned_checkout(app_handle, node_name, &target_dir, revision).await?;
         emit_custom_node_clone_success(app_handle, node_name);
         if let Some(install_fn) = install_dependencies_fn {
             return install_fn(app_handle, node_name, &target_dir).await;
@@ -110,6 +127,73 @@ pub async fn clone_repository_to_custom_nodes(
     }
 }
 
+/// Archive install with failures reported through the clone-failed event.
+async fn install_from_archive_reporting(app_handle: &AppHandle<Wry>, node_name: &str, repo_url: &str, revision: Option<&str>, target_dir: &Path) -> Result<(), String> {
+    install_from_archive(node_name, repo_url, revision, target_dir).await.map(|_| ()).map_err(|e| {
+        error!("[CUSTOM_NODE_SETUP] {}", e);
+        emit_custom_node_clone_failed(app_handle, node_name, &e);
+        e
+    })
+}
+
+/// Checks out the pinned revision, if any. Reports failures through the clone-failed event like
+/// the clone itself.
+async fn ensure_pinned_checkout(app_handle: &AppHandle<Wry>, node_name: &str, target_dir: &Path, revision: Option<&str>) -> Result<(), String> {
+    let Some(revision) = revision else { return Ok(()) };
+    checkout_revision(app_handle, node_name, target_dir, revision).await.map(|_| ()).map_err(|e| {
+        let err_msg = format!("Failed to check out {} at pinned revision {}: {}", node_name, revision, e);
+        error!("[CUSTOM_NODE_SETUP] {}", err_msg);
+        emit_custom_node_clone_failed(app_handle, node_name, &err_msg);
+        err_msg
+    })
+}
+
+/// Installs a custom node as described by its manifest entry: clone or verify the checkout,
+/// install dependencies according to its strategy, then run its post-install hook.
+pub async fn install_custom_node(app_handle: &AppHandle<Wry>, node: &CustomNodeDefinition) -> Result<(), String> {
+    let install_fn: Option<InstallDependenciesFn> = match node.dependency_strategy {
+        DependencyStrategy::RequirementsTxt => Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
+        DependencyStrategy::None => None,
+    };
+    clone_repository_to_custom_nodes(app_handle, node.name, node.repo_url, node.revision, install_fn).await?;
+    if let Some(hook) = node.post_install {
+        run_post_install_hook(app_handle, node, hook).await?;
+    }
+    Ok(())
+}
+
+/// Installs the core custom node with the given directory name.
+pub async fn install_core_custom_node(app_handle: &AppHandle<Wry>, node_name: &str) -> Result<(), String> {
+    let node = find_custom_node(node_name).ok_or_else(|| format!("{} is not in the custom node manifest.", node_name))?;
+    install_custom_node(app_handle, node).await
+}
+
+pub(crate) async fn run_post_install_hook(app_handle: &AppHandle<Wry>, node: &CustomNodeDefinition, hook: PostInstallHook) -> Result<(), String> {
+    match hook {
+        PostInstallHook::CopyClipsegNode => {
+            let custom_nodes_dir = get_comfyui_directory_path(app_handle)?.join("custom_nodes");
+            let source_file_path = custom_nodes_dir.join(node.name).join("custom_nodes").join("clipseg.py");
+            let target_file_path = custom_nodes_dir.join("clipseg.py");
+
+            if !source_file_path.exists() {
+                let err_msg = format!("Expected clipseg.py not found in cloned repository at {}. Cannot copy file.", source_file_path.display());
+                error!("[CUSTOM_NODE_SETUP] {}", err_msg);
+                emit_custom_node_clone_failed(app_handle, node.name, &err_msg);
+                return Err(err_msg);
+            }
+            // Always copy, so the file matches the checked-out revision.
+            info!("[CUSTOM_NODE_SETUP] Copying {} to {}", source_file_path.display(), target_file_path.display());
+            fs::copy(&source_file_path, &target_file_path).await.map_err(|e| {
+                let err_msg = format!("Failed to copy clipseg.py from {} to {}: {}", source_file_path.display(), target_file_path.display(), e);
+                error!("[CUSTOM_NODE_SETUP] {}", err_msg);
+                emit_custom_node_clone_failed(app_handle, node.name, &err_msg);
+                err_msg
+            })?;
+            Ok(())
+        }
+    }
+}
+
 /// Generic function to clone a custom node repository to a temporary directory.
 /// This function is no longer used for CLIPSeg, but kept for potential future use.
 pub async fn clone_repository_to_temp(
@@ -134,16 +218,7 @@ pub async fn clone_repository_to_temp(
 
     info!("[CUSTOM_NODE_SETUP] Cloning {} to temporary directory: {}", repo_url, temp_clone_path.display());
 
-    let git_temp_clone_path_arg_string = if cfg!(windows) {
-        let path_str_cow = temp_clone_path.to_string_lossy();
-        if path_str_cow.starts_with("\\\\?\\") {
-            path_str_cow.trim_start_matches("\\\\?\\").to_string()
-        } else {
-            path_str_cow.into_owned()
-        }
-    } else {
-        temp_clone_path.to_string_lossy().into_owned()
-    };
+    let git_temp_clone_path_arg_string = git_path_arg(&temp_clone_path);
 
     let command = app_handle.shell().command("git")
         .args(&["clone", repo_url, &git_temp_clone_path_arg_string]);
@@ -183,160 +258,7 @@ pub async fn clone_repository_to_temp(
     Ok(temp_clone_path)
 }
 
-// Specific cloning functions for each custom node
-use super::node_definitions::{
-    IMPACT_PACK_NODE_NAME, IMPACT_PACK_REPO_URL,
-    IMPACT_SUBPACK_NODE_NAME, IMPACT_SUBPACK_REPO_URL,
-    SMZ_NODES_NODE_NAME, SMZ_NODES_REPO_URL,
-    CONTROLNET_AUX_NODE_NAME, CONTROLNET_AUX_REPO_URL,
-    CLIPSEG_NODE_NAME, CLIPSEG_REPO_URL,
-    RMBG_NODE_NAME, RMBG_REPO_URL,
-};
+use super::node_definitions::{find_custom_node, CustomNodeDefinition, DependencyStrategy, PostInstallHook};
+use super::archive_install::{install_from_archive, read_archive_source};
+use super::git_ops::{checkout_revision, git_available, git_path_arg};
 use super::installation::install_custom_node_dependencies;
-
-
-pub async fn clone_comfyui_impact_pack(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    clone_repository_to_custom_nodes(
-        app_handle,
-        IMPACT_PACK_NODE_NAME,
-        IMPACT_PACK_REPO_URL,
-        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
-    ).await
-}
-
-pub async fn clone_comfyui_impact_subpack(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    clone_repository_to_custom_nodes(
-        app_handle,
-        IMPACT_SUBPACK_NODE_NAME,
-        IMPACT_SUBPACK_REPO_URL,
-        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
-    ).await
-}
-
-pub async fn clone_comfyui_smz_nodes(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    clone_repository_to_custom_nodes(
-        app_handle,
-        SMZ_NODES_NODE_NAME,
-        SMZ_NODES_REPO_URL,
-        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
-    ).await
-}
-
-
-
-
-pub async fn clone_comfyui_controlnet_aux(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    clone_repository_to_custom_nodes(
-        app_handle,
-        CONTROLNET_AUX_NODE_NAME,
-        CONTROLNET_AUX_REPO_URL,
-        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
-    ).await
-}
-
-
-pub async fn clone_comfyui_clipseg(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    info!("[CUSTOM_NODE_SETUP] Attempting to clone ComfyUI-CLIPSeg and move clipseg.py...");
-    emit_custom_node_clone_start(app_handle, CLIPSEG_NODE_NAME);
-
-    let comfyui_base_path = get_comfyui_directory_path(app_handle)?;
-    let custom_nodes_dir = comfyui_base_path.join("custom_nodes");
-    let clipseg_final_target_path = custom_nodes_dir.join("clipseg.py");
-    let clipseg_repo_target_dir = custom_nodes_dir.join(CLIPSEG_NODE_NAME); // e.g., .../custom_nodes/ComfyUI-CLIPSeg
-
-    // 1. Check if the final clipseg.py already exists
-    if clipseg_final_target_path.exists() {
-        info!("[CUSTOM_NODE_SETUP] clipseg.py already exists in custom_nodes. Skipping clone and move for {}.", CLIPSEG_NODE_NAME);
-        emit_custom_node_already_exists(app_handle, CLIPSEG_NODE_NAME);
-        return Ok(());
-    }
-
-    // 2. Clone the repository directly into custom_nodes/ComfyUI-CLIPSeg
-    if !clipseg_repo_target_dir.exists() {
-        info!("[CUSTOM_NODE_SETUP] Cloning {} into {}", CLIPSEG_REPO_URL, clipseg_repo_target_dir.display());
-
-        let git_target_path_arg_string = if cfg!(windows) {
-            let path_str_cow = clipseg_repo_target_dir.to_string_lossy();
-            if path_str_cow.starts_with("\\\\?\\") {
-                path_str_cow.trim_start_matches("\\\\?\\").to_string()
-            } else {
-                path_str_cow.into_owned()
-            }
-        } else {
-            clipseg_repo_target_dir.to_string_lossy().into_owned()
-        };
-
-        let command = app_handle.shell().command("git")
-            .args(&["clone", CLIPSEG_REPO_URL, &git_target_path_arg_string]);
-
-        let result = ProcessManager::spawn_and_wait_for_process(app_handle, command, &format!("git_clone_{}", CLIPSEG_NODE_NAME)).await
-            .map_err(|e| {
-                let err_msg = if e.contains("No such file or directory") {
-                    "Git command not found. Please ensure Git is installed and in your system's PATH.".to_string()
-                } else {
-                    format!("Failed to execute git clone command for {}: {}", CLIPSEG_NODE_NAME, e)
-                };
-                error!("[CUSTOM_NODE_SETUP] {}", err_msg);
-                emit_custom_node_clone_failed(app_handle, CLIPSEG_NODE_NAME, &err_msg);
-                err_msg
-            })?;
-
-        let success = result.exit_code.map_or(false, |c| c == 0) && result.signal.is_none();
-
-        if !success {
-            let stderr_output = result.stderr.join("\n");
-            let stdout_output = result.stdout.join("\n");
-            let err_msg = format!(
-                "Failed to clone {}. Git command exited with code {:?}. Stderr: {}. Stdout: {}",
-                CLIPSEG_NODE_NAME, result.exit_code, stderr_output.trim(), stdout_output.trim()
-            );
-            error!("[CUSTOM_NODE_SETUP] {}", err_msg);
-            emit_custom_node_clone_failed(app_handle, CLIPSEG_NODE_NAME, &err_msg);
-            if clipseg_repo_target_dir.exists() {
-                if let Err(e_rm) = fs::remove_dir_all(&clipseg_repo_target_dir).await {
-                    warn!("[CUSTOM_NODE_SETUP] Failed to clean up cloned directory {} after failed clone: {}", clipseg_repo_target_dir.display(), e_rm);
-                }
-            }
-            return Err(err_msg);
-        }
-        info!("[CUSTOM_NODE_SETUP] Successfully cloned {} into {}", CLIPSEG_NODE_NAME, clipseg_repo_target_dir.display());
-    } else {
-        info!("[CUSTOM_NODE_SETUP] Target directory {} for {} already exists. Skipping clone.", clipseg_repo_target_dir.display(), CLIPSEG_NODE_NAME);
-    }
-
-    // 3. Copy clipseg.py from the cloned repository to custom_nodes
-    let source_file_path = clipseg_repo_target_dir.join("custom_nodes").join("clipseg.py");
-
-    if !source_file_path.exists() {
-        let err_msg = format!("Expected clipseg.py not found in cloned repository at {}. Cannot copy file.", source_file_path.display());
-        error!("[CUSTOM_NODE_SETUP] {}", err_msg);
-        emit_custom_node_clone_failed(app_handle, CLIPSEG_NODE_NAME, &err_msg);
-        return Err(err_msg);
-    }
-
-    info!("[CUSTOM_NODE_SETUP] Attempting to copy {} to {}", source_file_path.display(), clipseg_final_target_path.display());
-    match fs::copy(&source_file_path, &clipseg_final_target_path).await {
-        Ok(_) => {
-            info!("[CUSTOM_NODE_SETUP] Successfully copied clipseg.py for {}.", CLIPSEG_NODE_NAME);
-        },
-        Err(e) => {
-            let err_msg = format!("Failed to copy clipseg.py from {} to {}: {}", source_file_path.display(), clipseg_final_target_path.display(), e);
-            error!("[CUSTOM_NODE_SETUP] {}", err_msg);
-            emit_custom_node_clone_failed(app_handle, CLIPSEG_NODE_NAME, &err_msg);
-            return Err(err_msg);
-        }
-    }
-
-    emit_custom_node_clone_success(app_handle, CLIPSEG_NODE_NAME);
-    Ok(())
-}
-
-pub async fn clone_comfyui_rmbg(app_handle: &AppHandle<Wry>) -> Result<(), String> {
-    clone_repository_to_custom_nodes(
-        app_handle,
-        RMBG_NODE_NAME,
-        RMBG_REPO_URL,
-        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
-    ).await
-}
-
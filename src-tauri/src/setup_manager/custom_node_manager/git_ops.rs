@@ -0,0 +1,96 @@
+// metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/git_ops.rs
+//
+// Small wrappers around the git CLI for custom node checkouts: resolving and checking out pinned
+// revisions and verifying what a checkout is on.
+
+use std::path::{Path, PathBuf};
+use log::{error, info};
+use tauri::{AppHandle, Wry};
+use tauri_plugin_shell::ShellExt;
+
+use crate::process_manager::ProcessManager;
+use crate::setup_manager::python_utils::execute_command_to_string;
+
+/// Path argument for git. Git for Windows does not understand `\\?\` verbatim paths, which
+/// `canonicalize` produces.
+pub fn git_path_arg(path: &Path) -> String {
+    let path_str = path.to_string_lossy();
+    if cfg!(windows) && path_str.starts_with("\\\\?\\") {
+        path_str.trim_start_matches("\\\\?\\").to_string()
+    } else {
+        path_str.into_owned()
+    }
+}
+
+/// Whether a `git` executable can be run.
+pub async fn git_available() -> bool {
+    execute_command_to_string(&PathBuf::from("git"), &["--version"], None).await.is_ok()
+}
+
+/// Runs `git -C <repo_dir> <args>` and returns its stdout lines.
+pub async fn run_git(app_handle: &AppHandle<Wry>, repo_dir: &Path, args: &[&str], process_name: &str) -> Result<Vec<String>, String> {
+    let repo_arg = git_path_arg(repo_dir);
+    let mut full_args = vec!["-C", repo_arg.as_str()];
+    full_args.extend_from_slice(args);
+    let command = app_handle.shell().command("git").args(&full_args);
+
+    let result = ProcessManager::spawn_and_wait_for_process(app_handle, command, process_name).await.map_err(|e| {
+        if e.contains("No such file or directory") {
+            "Git command not found. Please ensure Git is installed and in your system's PATH.".to_string()
+        } else {
+            format!("Failed to execute git {}: {}", args.join(" "), e)
+        }
+    })?;
+    if result.exit_code == Some(0) && result.signal.is_none() {
+        Ok(result.stdout.iter().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+    } else {
+        Err(format!("git {} exited with code {:?}. Stderr: {}", args.join(" "), result.exit_code, result.stderr.join("\n").trim()))
+    }
+}
+
+/// Commit hash the checkout is currently on.
+pub async fn head_commit(app_handle: &AppHandle<Wry>, repo_dir: &Path) -> Result<String, String> {
+    run_git(app_handle, repo_dir, &["rev-parse", "HEAD"], "git_rev_parse_head").await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("git rev-parse HEAD printed nothing in {}", repo_dir.display()))
+}
+
+/// Resolves a commit, tag or branch name to a commit hash using only what is already fetched.
+pub async fn resolve_revision(app_handle: &AppHandle<Wry>, repo_dir: &Path, revision: &str) -> Result<String, String> {
+    let spec = format!("{}^{{commit}}", revision);
+    run_git(app_handle, repo_dir, &["rev-parse", "--verify", "--quiet", &spec], "git_rev_parse").await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Revision {} not found in {}", revision, repo_dir.display()))
+}
+
+/// Checks out `revision` (detached) unless the checkout is already on it, fetching from origin
+/// when the revision isn't known locally. Returns the commit the checkout ends up on.
+pub async fn checkout_revision(app_handle: &AppHandle<Wry>, node_name: &str, repo_dir: &Path, revision: &str) -> Result<String, String> {
+    let target = match resolve_revision(app_handle, repo_dir, revision).await {
+        Ok(commit) => commit,
+        Err(_) => {
+            info!("[CUSTOM_NODE_SETUP] Revision {} of {} is not available locally. Fetching from origin...", revision, node_name);
+            run_git(app_handle, repo_dir, &["fetch", "--tags", "origin"], &format!("git_fetch_{}", node_name)).await?;
+            resolve_revision(app_handle, repo_dir, revision).await?
+        }
+    };
+
+    let head = head_commit(app_handle, repo_dir).await?;
+    if head == target {
+        info!("[CUSTOM_NODE_SETUP] {} is already at pinned revision {} ({}).", node_name, revision, target);
+        return Ok(target);
+    }
+
+    info!("[CUSTOM_NODE_SETUP] Checking out {} at {} ({}), was {}.", node_name, revision, target, head);
+    run_git(app_handle, repo_dir, &["checkout", "--quiet", "--detach", &target], &format!("git_checkout_{}", node_name)).await?;
+
+    let new_head = head_commit(app_handle, repo_dir).await?;
+    if new_head != target {
+        let err_msg = format!("Checkout of {} at {} did not take effect: HEAD is {}", node_name, target, new_head);
+        error!("[CUSTOM_NODE_SETUP] {}", err_msg);
+        return Err(err_msg);
+    }
+    Ok(target)
+}
@@ -4,30 +4,49 @@ use tauri::{AppHandle, Wry};
 use log::{info, warn};
 
 use crate::setup_manager::dependency_manager::command_runner::run_command_for_setup_progress;
-use crate::setup_manager::python_utils::get_conda_executable_path;
+use crate::setup_manager::env_backend::pip_install_command;
+use crate::setup_manager::dependency_manager::lockfile::is_covered_by_lockfile;
+use crate::setup_manager::dependency_manager::requirement_conflicts::{check_requirement_conflicts_for, constraints_file_path};
+use crate::setup_manager::env_snapshot::snapshot_before_change;
+use crate::setup_manager::event_utils::{emit_event, emit_setup_progress};
+use crate::setup_manager::setup_journal;
 
 
-/// Installs custom node dependencies using `conda run`.
+/// Installs custom node dependencies with pip inside the selected environment backend
+/// (`conda run` for Conda, the venv's pip or `uv pip` for Venv).
 pub async fn install_custom_node_dependencies(app_handle: &AppHandle<Wry>, node_name: String, pack_dir: std::path::PathBuf) -> Result<(), String> {
-    let conda_executable = get_conda_executable_path(app_handle).await?;
-    let env_name = "comfyui_env";
-    info!("[CUSTOM_NODE_DEPENDENCY_INSTALL] Installing dependencies for {} using conda run...", node_name);
+    if is_covered_by_lockfile(app_handle, &node_name) {
+        info!("[CUSTOM_NODE_DEPENDENCY_INSTALL] Dependencies for {} are pinned in the Python lockfile. Skipping requirements.txt.", node_name);
+        return Ok(());
+    }
+    let (installer, install_args) = pip_install_command(app_handle).await?;
+    info!("[CUSTOM_NODE_DEPENDENCY_INSTALL] Installing dependencies for {} using {}...", node_name, installer.display());
 
     let requirements_path = pack_dir.join("requirements.txt");
     if requirements_path.exists() {
         info!("[CUSTOM_NODE_DEPENDENCY_INSTALL] Found requirements.txt for {}. Installing...", node_name);
         
-        let args: Vec<String> = vec![
-            "run".to_string(),
-            "-n".to_string(),
-            env_name.to_string(),
-            "python".to_string(),
-            "-m".to_string(),
-            "pip".to_string(),
-            "install".to_string(),
-            "-r".to_string(),
-            requirements_path.to_str().unwrap().to_string(),
-        ];
+        // Report requirements that clash with ComfyUI's or another node's before pip silently
+        // resolves them by replacing a package. Conflicts are warnings; the install goes ahead.
+        match check_requirement_conflicts_for(app_handle, Some((&node_name, &requirements_path))) {
+            Ok(report) if !report.conflicts.is_empty() => {
+                let summary = report.conflicts.iter().map(|c| c.message.clone()).collect::<Vec<_>>().join("; ");
+                setup_journal::record_note(app_handle, &format!("requirement_conflicts_{}", node_name), &summary);
+                emit_setup_progress(app_handle, "installing_custom_nodes", &format!("Requirement conflicts in {}", node_name), 0, Some(summary), None);
+                emit_event(app_handle, "requirement-conflicts", Some(report));
+            }
+            Ok(_) => {}
+            Err(e) => warn!("[CUSTOM_NODE_DEPENDENCY_INSTALL] Could not check requirement conflicts for {}: {}", node_name, e),
+        }
+
+        let mut args: Vec<String> = install_args;
+        args.push("-r".to_string());
+        args.push(requirements_path.to_str().unwrap().to_string());
+        if let Some(constraints) = constraints_file_path(app_handle) {
+            // Team-maintained pins that node requirements must not move.
+            args.push("-c".to_string());
+            args.push(constraints.to_string_lossy().to_string());
+        }
         let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
         let phase = "installing_custom_nodes"; // Consistent phase name
@@ -37,13 +56,16 @@ pub async fn install_custom_node_dependencies(app_handle: &AppHandle<Wry>, node_
         let initial_message = format!("Installing dependencies for {} from requirements.txt", node_name);
         let success_message = format!("Dependencies for {} installed.", node_name);
 
+        // Node requirements can downgrade or replace packages the env depends on.
+        snapshot_before_change(app_handle, &format!("installing dependencies for {}", node_name)).await;
+
         run_command_for_setup_progress(
             app_handle,
             phase,
             current_step_base,
             progress_current_phase,
             progress_weight_of_this_command,
-            &conda_executable,
+            &installer,
             &args_refs,
             &pack_dir,
             &initial_message,
@@ -3,16 +3,17 @@
 pub mod node_definitions;
 pub mod cloning;
 pub mod installation;
+pub mod git_ops;
+pub mod archive_install;
+pub mod updates;
+pub mod workflow_requirements;
+pub mod user_registry;
 
 // Re-export public functions from sub-modules
 pub use cloning::{
-    clone_comfyui_impact_pack,
-    clone_comfyui_impact_subpack,
-    clone_comfyui_smz_nodes,
-    clone_comfyui_controlnet_aux,
-    clone_comfyui_clipseg,
-    clone_comfyui_rmbg,
     clone_repository_to_custom_nodes, // Re-export this as it's used by orchestration
+    install_custom_node,
+    install_core_custom_node,
 };
 pub use installation::{
     install_custom_node_dependencies, // Re-export this as it's used by clipseg_handler
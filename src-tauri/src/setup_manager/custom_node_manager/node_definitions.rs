@@ -1,5 +1,7 @@
 // metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/node_definitions.rs
 
+use serde::Serialize;
+
 pub const IMPACT_PACK_NODE_NAME: &str = "ComfyUI-Impact-Pack";
 pub const IMPACT_PACK_REPO_URL: &str = "https://github.com/ltdrdata/ComfyUI-Impact-Pack";
 
@@ -20,4 +22,89 @@ pub const RMBG_REPO_URL: &str = "https://github.com/1038lab/ComfyUI-RMBG";
 
 
 pub const ONNXRUNTIME_PACKAGE: &str = "onnxruntime";
-pub const CHECK_ONNX_SCRIPT_NAME: &str = "check_onnx.py";
\ No newline at end of file
+pub const CHECK_ONNX_SCRIPT_NAME: &str = "check_onnx.py";
+
+/// How a node pack's Python dependencies are installed after it is cloned.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyStrategy {
+    /// `pip install -r requirements.txt` from the pack directory (skipped when the lockfile covers it).
+    RequirementsTxt,
+    /// Nothing to install.
+    None,
+}
+
+/// Extra work some packs need after they are cloned and checked out.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PostInstallHook {
+    /// ComfyUI-CLIPSeg keeps its node in `custom_nodes/clipseg.py` inside the repo; copy it into
+    /// ComfyUI's custom_nodes so ComfyUI loads it.
+    CopyClipsegNode,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeDefinition {
+    pub name: &'static str, // Directory name under custom_nodes
+    pub repo_url: &'static str,
+    // Commit or tag to check out. `None` tracks the default branch HEAD at clone time, which
+    // upstream can change at any moment; deep verification warns about every unpinned node.
+    pub revision: Option<&'static str>,
+    pub post_install: Option<PostInstallHook>,
+    pub dependency_strategy: DependencyStrategy,
+}
+
+/// Core custom nodes installed by the setup flow. The install manifest is built from this list,
+/// so changing a revision here makes the next startup re-checkout that node.
+// TODO: Pin every entry to the commit SHA last tested with the bundled workflows (take it from
+// `git -C custom_nodes/<name> rev-parse HEAD` on a verified install).
+pub const CUSTOM_NODE_MANIFEST: &[CustomNodeDefinition] = &[
+    CustomNodeDefinition {
+        name: IMPACT_PACK_NODE_NAME,
+        repo_url: IMPACT_PACK_REPO_URL,
+        revision: None,
+        post_install: None,
+        dependency_strategy: DependencyStrategy::RequirementsTxt,
+    },
+    CustomNodeDefinition {
+        name: IMPACT_SUBPACK_NODE_NAME,
+        repo_url: IMPACT_SUBPACK_REPO_URL,
+        revision: None,
+        post_install: None,
+        dependency_strategy: DependencyStrategy::RequirementsTxt,
+    },
+    CustomNodeDefinition {
+        name: SMZ_NODES_NODE_NAME,
+        repo_url: SMZ_NODES_REPO_URL,
+        revision: None,
+        post_install: None,
+        dependency_strategy: DependencyStrategy::RequirementsTxt,
+    },
+    CustomNodeDefinition {
+        name: CONTROLNET_AUX_NODE_NAME,
+        repo_url: CONTROLNET_AUX_REPO_URL,
+        revision: None,
+        post_install: None,
+        dependency_strategy: DependencyStrategy::RequirementsTxt,
+    },
+    CustomNodeDefinition {
+        name: CLIPSEG_NODE_NAME,
+        repo_url: CLIPSEG_REPO_URL,
+        revision: None,
+        post_install: Some(PostInstallHook::CopyClipsegNode),
+        dependency_strategy: DependencyStrategy::None,
+    },
+    CustomNodeDefinition {
+        name: RMBG_NODE_NAME,
+        repo_url: RMBG_REPO_URL,
+        revision: None,
+        post_install: None,
+        dependency_strategy: DependencyStrategy::RequirementsTxt,
+    },
+];
+
+/// Looks up a core custom node by directory name.
+pub fn find_custom_node(name: &str) -> Option<&'static CustomNodeDefinition> {
+    CUSTOM_NODE_MANIFEST.iter().find(|n| n.name == name)
+}
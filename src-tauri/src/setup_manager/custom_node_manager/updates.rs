@@ -0,0 +1,251 @@
+// metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/updates.rs
+//
+// Updating installed custom nodes. Setup only clones nodes that are missing, so without these
+// commands an installed node could never move off the commit it was cloned at.
+// Every update re-runs the node's dependency installation and records the commit it replaced in
+// `custom_node_history.json`, so `rollback_custom_node` can go back if ComfyUI can no longer
+// load the node afterwards.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::setup_manager::component_reset::ensure_backend_idle;
+use crate::setup_manager::deep_verification::{node_import_target, probe_node_imports};
+use crate::setup_manager::env_backend::COMFYUI_ENV_NAME;
+use crate::setup_manager::python_utils::{get_comfyui_directory_path, get_conda_env_python_executable_path};
+use super::cloning::run_post_install_hook;
+use super::git_ops::{checkout_revision, head_commit, resolve_revision, run_git};
+use super::installation::install_custom_node_dependencies;
+use super::node_definitions::{find_custom_node, CustomNodeDefinition, DependencyStrategy, CUSTOM_NODE_MANIFEST};
+
+const CUSTOM_NODE_HISTORY_FILENAME: &str = "custom_node_history.json";
+const MAX_HISTORY_PER_NODE: usize = 10;
+
+/// A commit a node was on before an update replaced it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeRevisionRecord {
+    pub commit: String,
+    pub replaced_at: String,
+    pub replaced_by: String,
+}
+
+type CustomNodeHistory = HashMap<String, Vec<CustomNodeRevisionRecord>>;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeUpdateStatus {
+    pub name: String,
+    pub current_commit: Option<String>,
+    pub latest_commit: Option<String>,
+    pub pinned_revision: Option<String>,
+    pub update_available: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeUpdateResult {
+    pub name: String,
+    pub previous_commit: Option<String>,
+    pub new_commit: Option<String>,
+    pub loads: Option<bool>, // None when the import check could not run
+    pub load_error: Option<String>,
+    pub error: Option<String>,
+}
+
+fn get_history_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(CUSTOM_NODE_HISTORY_FILENAME))
+}
+
+fn read_history(app_handle: &AppHandle<Wry>) -> CustomNodeHistory {
+    get_history_path(app_handle)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn write_history(app_handle: &AppHandle<Wry>, history: &CustomNodeHistory) -> Result<(), String> {
+    let path = get_history_path(app_handle)?;
+    let json = serde_json::to_string_pretty(history).map_err(|e| format!("Failed to serialize custom node history: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write custom node history {}: {}", path.display(), e))
+}
+
+/// Manifest entry and checkout directory of an installed core node.
+fn installed_node(app_handle: &AppHandle<Wry>, node_name: &str) -> Result<(&'static CustomNodeDefinition, PathBuf), String> {
+    let node = find_custom_node(node_name).ok_or_else(|| format!("{} is not in the custom node manifest.", node_name))?;
+    let repo_dir = get_comfyui_directory_path(app_handle)?.join("custom_nodes").join(node.name);
+    if !repo_dir.join(".git").exists() {
+        return Err(format!("{} is not installed as a git checkout.", node.name));
+    }
+    Ok((node, repo_dir))
+}
+
+/// Fetches origin and returns the commit its default branch points at.
+async fn fetch_upstream_head(app_handle: &AppHandle<Wry>, node_name: &str, repo_dir: &PathBuf) -> Result<String, String> {
+    run_git(app_handle, repo_dir, &["fetch", "--tags", "origin"], &format!("git_fetch_{}", node_name)).await?;
+    if let Ok(commit) = resolve_revision(app_handle, repo_dir, "origin/HEAD").await {
+        return Ok(commit);
+    }
+    // origin/HEAD is only set by clone; repair it for checkouts that lack it.
+    run_git(app_handle, repo_dir, &["remote", "set-head", "origin", "--auto"], &format!("git_set_head_{}", node_name)).await?;
+    resolve_revision(app_handle, repo_dir, "origin/HEAD").await
+}
+
+/// Re-runs dependency installation and the post-install hook after the checkout changed.
+async fn reinstall_node(app_handle: &AppHandle<Wry>, node: &CustomNodeDefinition, repo_dir: &PathBuf) -> Result<(), String> {
+    if node.dependency_strategy == DependencyStrategy::RequirementsTxt {
+        install_custom_node_dependencies(app_handle, node.name.to_string(), repo_dir.clone()).await?;
+    }
+    if let Some(hook) = node.post_install {
+        run_post_install_hook(app_handle, node, hook).await?;
+    }
+    Ok(())
+}
+
+/// Imports the node the way ComfyUI would. Fills `loads`/`load_error` on the result.
+async fn check_node_loads(app_handle: &AppHandle<Wry>, node: &CustomNodeDefinition, result: &mut CustomNodeUpdateResult) {
+    let probe = async {
+        let python_exe = get_conda_env_python_executable_path(app_handle, COMFYUI_ENV_NAME).await?;
+        let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+        let target = node_import_target(node.name);
+        probe_node_imports(app_handle, &python_exe, &comfyui_dir, &[target.as_str()]).await
+    };
+    match probe.await {
+        Ok(results) => {
+            let node_result = results.into_iter().next();
+            result.loads = node_result.as_ref().map(|r| r.ok);
+            result.load_error = node_result.and_then(|r| r.error);
+            if result.loads == Some(false) {
+                warn!("[CUSTOM_NODE_UPDATE] {} no longer loads after switching to {:?}. It can be rolled back.", node.name, result.new_commit);
+            }
+        }
+        Err(e) => warn!("[CUSTOM_NODE_UPDATE] Could not check whether {} loads: {}", node.name, e),
+    }
+}
+
+/// Checks out `revision`, reinstalls dependencies and checks the node still loads.
+/// When `record_history` is set, the replaced commit is added to the node's history.
+async fn switch_node_revision(app_handle: &AppHandle<Wry>, node_name: &str, revision: Option<&str>, record_history: bool) -> Result<CustomNodeUpdateResult, String> {
+    let (node, repo_dir) = installed_node(app_handle, node_name)?;
+    let previous = head_commit(app_handle, &repo_dir).await?;
+    let target = match revision {
+        Some(r) => r.to_string(),
+        None => fetch_upstream_head(app_handle, node.name, &repo_dir).await?,
+    };
+
+    let new_commit = checkout_revision(app_handle, node.name, &repo_dir, &target).await?;
+    let mut result = CustomNodeUpdateResult {
+        name: node.name.to_string(),
+        previous_commit: Some(previous.clone()),
+        new_commit: Some(new_commit.clone()),
+        ..Default::default()
+    };
+    if new_commit == previous {
+        info!("[CUSTOM_NODE_UPDATE] {} is already at {}.", node.name, new_commit);
+        return Ok(result);
+    }
+
+    if record_history {
+        let mut history = read_history(app_handle);
+        let records = history.entry(node.name.to_string()).or_default();
+        records.push(CustomNodeRevisionRecord { commit: previous, replaced_at: chrono::Utc::now().to_rfc3339(), replaced_by: new_commit.clone() });
+        let excess = records.len().saturating_sub(MAX_HISTORY_PER_NODE);
+        records.drain(..excess);
+        write_history(app_handle, &history)?;
+    }
+
+    reinstall_node(app_handle, node, &repo_dir).await?;
+    check_node_loads(app_handle, node, &mut result).await;
+    Ok(result)
+}
+
+/// Fetches every installed core node and reports whether upstream has new commits.
+#[tauri::command]
+pub async fn check_custom_node_updates(app_handle: AppHandle<Wry>) -> Result<Vec<CustomNodeUpdateStatus>, String> {
+    let mut statuses = Vec::new();
+    for node in CUSTOM_NODE_MANIFEST {
+        let mut status = CustomNodeUpdateStatus {
+            name: node.name.to_string(),
+            current_commit: None,
+            latest_commit: None,
+            pinned_revision: node.revision.map(|r| r.to_string()),
+            update_available: false,
+            error: None,
+        };
+        let checked: Result<(), String> = async {
+            let (_, repo_dir) = installed_node(&app_handle, node.name)?;
+            let current = head_commit(&app_handle, &repo_dir).await?;
+            let latest = fetch_upstream_head(&app_handle, node.name, &repo_dir).await?;
+            status.update_available = current != latest;
+            status.current_commit = Some(current);
+            status.latest_commit = Some(latest);
+            Ok(())
+        }.await;
+        if let Err(e) = checked {
+            warn!("[CUSTOM_NODE_UPDATE] Could not check {} for updates: {}", node.name, e);
+            status.error = Some(e);
+        }
+        statuses.push(status);
+    }
+    Ok(statuses)
+}
+
+/// Updates one node to `revision` (commit, tag or branch), or to upstream HEAD when none is given.
+#[tauri::command]
+pub async fn update_custom_node(app_handle: AppHandle<Wry>, node_name: String, revision: Option<String>) -> Result<CustomNodeUpdateResult, String> {
+    ensure_backend_idle(&app_handle)?;
+    info!("[CUSTOM_NODE_UPDATE] Updating {} to {}...", node_name, revision.as_deref().unwrap_or("upstream HEAD"));
+    switch_node_revision(&app_handle, &node_name, revision.as_deref(), true).await.map_err(|e| {
+        error!("[CUSTOM_NODE_UPDATE] Failed to update {}: {}", node_name, e);
+        e
+    })
+}
+
+/// Updates every installed core node to upstream HEAD. A failing node does not stop the others.
+#[tauri::command]
+pub async fn update_all_custom_nodes(app_handle: AppHandle<Wry>) -> Result<Vec<CustomNodeUpdateResult>, String> {
+    ensure_backend_idle(&app_handle)?;
+    let mut results = Vec::new();
+    for node in CUSTOM_NODE_MANIFEST {
+        if installed_node(&app_handle, node.name).is_err() {
+            continue;
+        }
+        let result = switch_node_revision(&app_handle, node.name, None, true).await.unwrap_or_else(|e| {
+            error!("[CUSTOM_NODE_UPDATE] Failed to update {}: {}", node.name, e);
+            CustomNodeUpdateResult { name: node.name.to_string(), error: Some(e), ..Default::default() }
+        });
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Restores the commit a node was on before its last update.
+#[tauri::command]
+pub async fn rollback_custom_node(app_handle: AppHandle<Wry>, node_name: String) -> Result<CustomNodeUpdateResult, String> {
+    ensure_backend_idle(&app_handle)?;
+    let mut history = read_history(&app_handle);
+    let record = history
+        .get(&node_name)
+        .and_then(|records| records.last().cloned())
+        .ok_or_else(|| format!("No earlier revision of {} to roll back to.", node_name))?;
+
+    info!("[CUSTOM_NODE_UPDATE] Rolling back {} to {}...", node_name, record.commit);
+    let result = switch_node_revision(&app_handle, &node_name, Some(&record.commit), false).await?;
+    if let Some(records) = history.get_mut(&node_name) {
+        records.pop();
+    }
+    write_history(&app_handle, &history)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_custom_node_history(app_handle: AppHandle<Wry>, node_name: String) -> Result<Vec<CustomNodeRevisionRecord>, String> {
+    Ok(read_history(&app_handle).remove(&node_name).unwrap_or_default())
+}
@@ -0,0 +1,249 @@
+// metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/user_registry.rs
+//
+// Extra node packs the user picks from a registry file, on top of the core packs setup installs.
+// The registry uses ComfyUI-Manager's `custom-node-list.json` format, so its list (or a trimmed
+// copy maintained by the team) can be dropped in as is. Only `git-clone` entries are supported;
+// they go through the same clone-and-install path as core packs.
+//
+// User-added packs are recorded in `user_custom_nodes.json` in the app data directory, never in
+// the install manifest, so core setup runs, resets and `uninstall_all_components` leave them alone.
+// Names that collide with a core pack are refused.
+
+use std::fs;
+use std::path::PathBuf;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::settings::load_settings;
+use crate::setup_manager::component_reset::ensure_backend_idle;
+use crate::setup_manager::dependency_manager::command_runner::run_command_for_setup_progress;
+use crate::setup_manager::env_backend::pip_install_command;
+use crate::setup_manager::python_utils::get_comfyui_directory_path;
+use super::cloning::clone_repository_to_custom_nodes;
+use super::installation::install_custom_node_dependencies;
+use super::node_definitions::find_custom_node;
+
+const DEFAULT_REGISTRY_FILENAME: &str = "custom-node-list.json";
+const USER_CUSTOM_NODES_FILENAME: &str = "user_custom_nodes.json";
+
+/// One entry of a ComfyUI-Manager `custom-node-list.json`. Unknown fields are ignored.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RegistryEntry {
+    pub id: Option<String>,
+    pub author: String,
+    pub title: String,
+    pub reference: String,
+    pub files: Vec<String>,
+    pub install_type: String,
+    pub description: String,
+    pub pip: Vec<String>, // Extra pip packages the pack needs beyond its requirements.txt
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct RegistryFile {
+    custom_nodes: Vec<RegistryEntry>,
+}
+
+impl RegistryEntry {
+    fn repo_url(&self) -> Option<&str> {
+        self.files.first().map(|f| f.trim_end_matches('/'))
+    }
+
+    /// Directory under custom_nodes: the repository name, as `git clone` would pick it.
+    pub fn directory_name(&self) -> Option<String> {
+        let name = self.repo_url()?.rsplit('/').next()?.trim_end_matches(".git");
+        let valid = !name.is_empty() && name != "." && name != ".." && !name.contains('\\');
+        valid.then(|| name.to_string())
+    }
+
+    /// ComfyUI-Manager ids are optional in older lists; fall back to the directory name.
+    pub fn pack_id(&self) -> Option<String> {
+        self.id.clone().filter(|id| !id.is_empty()).or_else(|| self.directory_name())
+    }
+}
+
+/// A pack from the registry, as listed to the frontend.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableCustomNodePack {
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    pub repo_url: String,
+    pub directory_name: String,
+    pub install_type: String,
+    pub supported: bool, // Only git-clone entries can be installed
+    pub is_core: bool,   // Already installed by setup; not managed from here
+    pub installed: bool,
+}
+
+/// A pack the user installed, recorded in `user_custom_nodes.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserCustomNode {
+    pub id: String,
+    pub name: String, // Directory name under custom_nodes
+    pub title: String,
+    pub repo_url: String,
+    pub installed_at: String,
+}
+
+fn app_data_path(app_handle: &AppHandle<Wry>, filename: &str) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(filename))
+}
+
+/// The registry file: the explicit path if given, else the one in settings, else
+/// `custom-node-list.json` in the app data directory.
+fn registry_path(app_handle: &AppHandle<Wry>, explicit: Option<String>) -> Result<PathBuf, String> {
+    match explicit.or_else(|| load_settings(app_handle).custom_node_registry_path) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => app_data_path(app_handle, DEFAULT_REGISTRY_FILENAME),
+    }
+}
+
+pub fn load_registry(app_handle: &AppHandle<Wry>, explicit_path: Option<String>) -> Result<Vec<RegistryEntry>, String> {
+    let path = registry_path(app_handle, explicit_path)?;
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read custom node registry {}: {}", path.display(), e))?;
+    let registry: RegistryFile = serde_json::from_str(&json).map_err(|e| format!("Failed to parse custom node registry {}: {}", path.display(), e))?;
+    info!("[USER_CUSTOM_NODES] Loaded {} entries from {}", registry.custom_nodes.len(), path.display());
+    Ok(registry.custom_nodes)
+}
+
+pub fn read_user_custom_nodes(app_handle: &AppHandle<Wry>) -> Vec<UserCustomNode> {
+    app_data_path(app_handle, USER_CUSTOM_NODES_FILENAME)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn write_user_custom_nodes(app_handle: &AppHandle<Wry>, nodes: &[UserCustomNode]) -> Result<(), String> {
+    let path = app_data_path(app_handle, USER_CUSTOM_NODES_FILENAME)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(nodes).map_err(|e| format!("Failed to serialize user custom nodes: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Installs the extra pip packages a registry entry lists.
+async fn install_extra_pip_packages(app_handle: &AppHandle<Wry>, node_name: &str, packages: &[String], pack_dir: &PathBuf) -> Result<(), String> {
+    let (installer, mut args) = pip_install_command(app_handle).await?;
+    args.extend(packages.iter().cloned());
+    let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    run_command_for_setup_progress(
+        app_handle,
+        "installing_custom_nodes",
+        &format!("Installing extra packages for {}", node_name),
+        0,
+        100,
+        &installer,
+        &args_refs,
+        pack_dir,
+        &format!("Installing {} for {}", packages.join(", "), node_name),
+        &format!("Failed to install extra packages for {}", node_name),
+    ).await.map(|_| ())
+}
+
+/// Lists the registry's packs with their install state.
+#[tauri::command]
+pub async fn list_available_custom_node_packs(app_handle: AppHandle<Wry>, registry_path: Option<String>) -> Result<Vec<AvailableCustomNodePack>, String> {
+    let entries = load_registry(&app_handle, registry_path)?;
+    let user_nodes = read_user_custom_nodes(&app_handle);
+    let mut packs = Vec::new();
+    for entry in entries {
+        let (Some(id), Some(directory_name), Some(repo_url)) = (entry.pack_id(), entry.directory_name(), entry.repo_url()) else {
+            warn!("[USER_CUSTOM_NODES] Skipping registry entry '{}' without a usable repository URL.", entry.title);
+            continue;
+        };
+        packs.push(AvailableCustomNodePack {
+            id,
+            title: entry.title.clone(),
+            author: entry.author.clone(),
+            description: entry.description.clone(),
+            repo_url: repo_url.to_string(),
+            is_core: find_custom_node(&directory_name).is_some(),
+            installed: user_nodes.iter().any(|n| n.name == directory_name),
+            directory_name,
+            supported: entry.install_type == "git-clone",
+            install_type: entry.install_type,
+        });
+    }
+    Ok(packs)
+}
+
+#[tauri::command]
+pub async fn list_user_custom_nodes(app_handle: AppHandle<Wry>) -> Result<Vec<UserCustomNode>, String> {
+    Ok(read_user_custom_nodes(&app_handle))
+}
+
+/// Clones a registry pack into custom_nodes and installs its requirements.
+#[tauri::command]
+pub async fn install_user_custom_node(app_handle: AppHandle<Wry>, pack_id: String, registry_path: Option<String>) -> Result<UserCustomNode, String> {
+    ensure_backend_idle(&app_handle)?;
+    let entry = load_registry(&app_handle, registry_path)?
+        .into_iter()
+        .find(|e| e.pack_id().as_deref() == Some(pack_id.as_str()))
+        .ok_or_else(|| format!("{} is not in the custom node registry.", pack_id))?;
+    if entry.install_type != "git-clone" {
+        return Err(format!("{} uses install type '{}'; only git-clone packs can be installed.", entry.title, entry.install_type));
+    }
+    let (name, repo_url) = match (entry.directory_name(), entry.repo_url()) {
+        (Some(name), Some(url)) => (name, url.to_string()),
+        _ => return Err(format!("Registry entry {} has no usable repository URL.", pack_id)),
+    };
+    if find_custom_node(&name).is_some() {
+        return Err(format!("{} is a core node pack and is installed by setup.", name));
+    }
+
+    info!("[USER_CUSTOM_NODES] Installing {} ({}) from {}", entry.title, name, repo_url);
+    clone_repository_to_custom_nodes(
+        &app_handle,
+        &name,
+        &repo_url,
+        None,
+        Some(|h, n, p| Box::pin(install_custom_node_dependencies(h, n.to_string(), p.to_path_buf()))),
+    ).await.map_err(|e| {
+        error!("[USER_CUSTOM_NODES] Failed to install {}: {}", name, e);
+        e
+    })?;
+    if !entry.pip.is_empty() {
+        let pack_dir = get_comfyui_directory_path(&app_handle)?.join("custom_nodes").join(&name);
+        install_extra_pip_packages(&app_handle, &name, &entry.pip, &pack_dir).await?;
+    }
+
+    let record = UserCustomNode {
+        id: pack_id,
+        name,
+        title: entry.title,
+        repo_url,
+        installed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut nodes = read_user_custom_nodes(&app_handle);
+    nodes.retain(|n| n.name != record.name);
+    nodes.push(record.clone());
+    write_user_custom_nodes(&app_handle, &nodes)?;
+    Ok(record)
+}
+
+/// Removes a user-installed pack. Its Python dependencies stay in the env, since other packs may share them.
+#[tauri::command]
+pub async fn uninstall_user_custom_node(app_handle: AppHandle<Wry>, node_name: String) -> Result<(), String> {
+    ensure_backend_idle(&app_handle)?;
+    let mut nodes = read_user_custom_nodes(&app_handle);
+    if !nodes.iter().any(|n| n.name == node_name) {
+        return Err(format!("{} was not installed from the registry.", node_name));
+    }
+    let node_dir = get_comfyui_directory_path(&app_handle)?.join("custom_nodes").join(&node_name);
+    if node_dir.exists() {
+        fs::remove_dir_all(&node_dir).map_err(|e| format!("Failed to remove {}: {}", node_dir.display(), e))?;
+    }
+    info!("[USER_CUSTOM_NODES] Uninstalled {}", node_name);
+    nodes.retain(|n| n.name != node_name);
+    write_user_custom_nodes(&app_handle, &nodes)
+}
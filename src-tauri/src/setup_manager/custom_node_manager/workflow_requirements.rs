@@ -0,0 +1,247 @@
+// metamorphosis-app/src-tauri/src/setup_manager/custom_node_manager/workflow_requirements.rs
+//
+// Works out which custom node packs a workflow needs. Every node in an API-format workflow names
+// its `class_type`; the table below maps the classes our workflows use to the pack that provides
+// them, so a missing pack is reported as "missing node pack X" before the prompt is queued
+// instead of surfacing later as a ComfyUI validation error.
+// When ComfyUI is running, `/object_info` is consulted as well. It lists every class ComfyUI
+// actually loaded, which also catches packs that are on disk but failed to import, and classes
+// the table doesn't know about.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Wry};
+
+use crate::setup_manager::deep_verification::node_import_target;
+use crate::setup_manager::python_utils::get_comfyui_directory_path;
+use crate::sidecar_manager::comfyui_url;
+use crate::sidecar_manager::external_backend::is_external_mode;
+use super::node_definitions::{
+    find_custom_node, CLIPSEG_NODE_NAME, CONTROLNET_AUX_NODE_NAME, IMPACT_PACK_NODE_NAME,
+    IMPACT_SUBPACK_NODE_NAME, RMBG_NODE_NAME, SMZ_NODES_NODE_NAME,
+};
+
+/// Used by the fullbody templates. Setup does not install it.
+pub const IPADAPTER_PLUS_NODE_NAME: &str = "ComfyUI_IPAdapter_plus";
+
+/// class_type -> directory name of the pack that provides it.
+/// Classes not listed here are assumed to be built into ComfyUI unless `/object_info` says otherwise.
+const NODE_CLASS_PROVIDERS: &[(&str, &str)] = &[
+    // ComfyUI-Impact-Pack
+    ("ImpactSwitch", IMPACT_PACK_NODE_NAME),
+    ("DetailerForEach", IMPACT_PACK_NODE_NAME),
+    ("DetailerForEachDebug", IMPACT_PACK_NODE_NAME),
+    ("FaceDetailer", IMPACT_PACK_NODE_NAME),
+    ("FaceDetailerPipe", IMPACT_PACK_NODE_NAME),
+    ("FromBasicPipe", IMPACT_PACK_NODE_NAME),
+    ("FromBasicPipe_v2", IMPACT_PACK_NODE_NAME),
+    ("ToBasicPipe", IMPACT_PACK_NODE_NAME),
+    ("ImpactControlNetApplyAdvancedSEGS", IMPACT_PACK_NODE_NAME),
+    ("ImpactControlNetApplySEGS", IMPACT_PACK_NODE_NAME),
+    ("ImpactDilateMask", IMPACT_PACK_NODE_NAME),
+    ("MaskToSEGS", IMPACT_PACK_NODE_NAME),
+    ("SEGSToImageList", IMPACT_PACK_NODE_NAME),
+    ("SAMLoader", IMPACT_PACK_NODE_NAME),
+    ("BboxDetectorSEGS", IMPACT_PACK_NODE_NAME),
+    ("SegmDetectorSEGS", IMPACT_PACK_NODE_NAME),
+    // ComfyUI-Impact-Subpack
+    ("UltralyticsDetectorProvider", IMPACT_SUBPACK_NODE_NAME),
+    // ComfyUI_smZNodes
+    ("smZ CLIPTextEncode", SMZ_NODES_NODE_NAME),
+    ("smZ Settings", SMZ_NODES_NODE_NAME),
+    // comfyui_controlnet_aux
+    ("DWPreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("OpenposePreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("Zoe-DepthMapPreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("MiDaS-DepthMapPreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("DepthAnythingPreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("CannyEdgePreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("LineArtPreprocessor", CONTROLNET_AUX_NODE_NAME),
+    ("AIO_Preprocessor", CONTROLNET_AUX_NODE_NAME),
+    // ComfyUI-CLIPSeg
+    ("CLIPSeg", CLIPSEG_NODE_NAME),
+    ("CombineSegMasks", CLIPSEG_NODE_NAME),
+    // ComfyUI-RMBG
+    ("AILab_MaskEnhancer", RMBG_NODE_NAME),
+    ("AILab_MaskCombiner", RMBG_NODE_NAME),
+    ("AILab_ImageMaskConvert", RMBG_NODE_NAME),
+    ("RMBG", RMBG_NODE_NAME),
+    ("FaceSegment", RMBG_NODE_NAME),
+    ("BodySegment", RMBG_NODE_NAME),
+    ("ClothesSegment", RMBG_NODE_NAME),
+    ("FashionSegmentClothing", RMBG_NODE_NAME),
+    // ComfyUI_IPAdapter_plus
+    ("IPAdapterFaceID", IPADAPTER_PLUS_NODE_NAME),
+    ("IPAdapterInsightFaceLoader", IPADAPTER_PLUS_NODE_NAME),
+    ("IPAdapterModelLoader", IPADAPTER_PLUS_NODE_NAME),
+    ("IPAdapterAdvanced", IPADAPTER_PLUS_NODE_NAME),
+    ("IPAdapterUnifiedLoader", IPADAPTER_PLUS_NODE_NAME),
+    ("IPAdapterUnifiedLoaderFaceID", IPADAPTER_PLUS_NODE_NAME),
+];
+
+/// The pack that provides `class_type`, if it comes from a custom node pack we know of.
+pub fn provider_of(class_type: &str) -> Option<&'static str> {
+    NODE_CLASS_PROVIDERS.iter().find(|(class, _)| *class == class_type).map(|(_, pack)| *pack)
+}
+
+/// Collects the node classes a workflow uses. Accepts both the API format
+/// (`{"<id>": {"class_type": ...}}`) and the UI format (`{"nodes": [{"type": ...}]}`).
+pub fn workflow_class_types(workflow: &Value) -> BTreeSet<String> {
+    let mut classes = BTreeSet::new();
+    if let Some(nodes) = workflow.get("nodes").and_then(|n| n.as_array()) {
+        for node in nodes {
+            if let Some(class_type) = node.get("type").and_then(|t| t.as_str()) {
+                classes.insert(class_type.to_string());
+            }
+        }
+    } else if let Some(nodes) = workflow.as_object() {
+        for node in nodes.values() {
+            if let Some(class_type) = node.get("class_type").and_then(|t| t.as_str()) {
+                classes.insert(class_type.to_string());
+            }
+        }
+    }
+    classes
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingNodePack {
+    pub name: String,
+    pub repo_url: Option<String>, // Set for packs in the core manifest
+    pub class_types: Vec<String>,
+    pub installed: bool,          // On disk but ComfyUI did not load it
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRequirements {
+    pub required_packs: Vec<String>,
+    pub missing_packs: Vec<MissingNodePack>,
+    // Classes ComfyUI does not know that no pack in the table provides.
+    pub unknown_class_types: Vec<String>,
+    // Whether `/object_info` from a running ComfyUI was used.
+    pub checked_against_comfyui: bool,
+}
+
+impl WorkflowRequirements {
+    pub fn is_satisfied(&self) -> bool {
+        self.missing_packs.is_empty() && self.unknown_class_types.is_empty()
+    }
+
+    /// "Missing node pack X (needed for A, B)", one entry per problem.
+    pub fn describe_problems(&self) -> String {
+        let mut problems: Vec<String> = self
+            .missing_packs
+            .iter()
+            .map(|pack| {
+                let reason = if pack.installed { "installed but failed to load" } else { "missing node pack" };
+                format!("{} {} (needed for {})", reason, pack.name, pack.class_types.join(", "))
+            })
+            .collect();
+        if !self.unknown_class_types.is_empty() {
+            problems.push(format!("node types not available in ComfyUI: {}", self.unknown_class_types.join(", ")));
+        }
+        problems.join("; ")
+    }
+}
+
+fn pack_installed(custom_nodes_dir: &Path, pack: &str) -> bool {
+    custom_nodes_dir.join(node_import_target(pack)).exists()
+}
+
+/// Node classes the running ComfyUI has loaded, or None when it isn't reachable.
+async fn fetch_object_info_classes(app_handle: &AppHandle<Wry>) -> Option<BTreeSet<String>> {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().ok()?;
+    let url = comfyui_url(app_handle, "/object_info");
+    let response = client.get(&url).send().await.ok()?;
+    if !response.status().is_success() {
+        warn!("[WORKFLOW_REQUIREMENTS] {} returned HTTP {}", url, response.status());
+        return None;
+    }
+    let info: Value = response.json().await.ok()?;
+    Some(info.as_object()?.keys().cloned().collect())
+}
+
+/// Resolves `class_types` against the installed packs and, when given, the classes ComfyUI loaded.
+/// `custom_nodes_dir` is None when ComfyUI isn't installed locally (external backend mode).
+pub fn resolve_class_types(class_types: &BTreeSet<String>, custom_nodes_dir: Option<&Path>, loaded_classes: Option<&BTreeSet<String>>) -> WorkflowRequirements {
+    let mut by_pack: BTreeMap<&'static str, Vec<String>> = BTreeMap::new();
+    let mut unknown = Vec::new();
+    for class_type in class_types {
+        match provider_of(class_type) {
+            Some(pack) => by_pack.entry(pack).or_default().push(class_type.clone()),
+            None => {
+                if loaded_classes.is_some_and(|loaded| !loaded.contains(class_type)) {
+                    unknown.push(class_type.clone());
+                }
+            }
+        }
+    }
+
+    let mut requirements = WorkflowRequirements {
+        required_packs: by_pack.keys().map(|p| p.to_string()).collect(),
+        unknown_class_types: unknown,
+        checked_against_comfyui: loaded_classes.is_some(),
+        ..Default::default()
+    };
+    for (pack, classes) in by_pack {
+        let installed = custom_nodes_dir.is_some_and(|dir| pack_installed(dir, pack));
+        let loaded = match loaded_classes {
+            Some(loaded) => classes.iter().all(|c| loaded.contains(c)),
+            None => installed,
+        };
+        if !loaded {
+            requirements.missing_packs.push(MissingNodePack {
+                name: pack.to_string(),
+                repo_url: find_custom_node(pack).map(|node| node.repo_url.to_string()),
+                class_types: classes,
+                installed,
+            });
+        }
+    }
+    requirements
+}
+
+/// Resolves the node packs `workflow` needs and which of them are missing.
+pub async fn resolve_workflow_requirements(app_handle: &AppHandle<Wry>, workflow: &Value) -> Result<WorkflowRequirements, String> {
+    let class_types = workflow_class_types(workflow);
+    if class_types.is_empty() {
+        return Err("Workflow contains no nodes with a class_type.".to_string());
+    }
+    let custom_nodes_dir = if is_external_mode(app_handle) {
+        None
+    } else {
+        Some(get_comfyui_directory_path(app_handle)?.join("custom_nodes"))
+    };
+    let loaded_classes = fetch_object_info_classes(app_handle).await;
+    let requirements = resolve_class_types(&class_types, custom_nodes_dir.as_deref(), loaded_classes.as_ref());
+    info!(
+        "[WORKFLOW_REQUIREMENTS] {} node classes, packs required: {:?}, missing: {:?} (checked against ComfyUI: {})",
+        class_types.len(),
+        requirements.required_packs,
+        requirements.missing_packs.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        requirements.checked_against_comfyui
+    );
+    Ok(requirements)
+}
+
+/// Fails with a "missing node pack X" message when `workflow` cannot run with what is installed.
+pub async fn ensure_workflow_runnable(app_handle: &AppHandle<Wry>, workflow: &Value) -> Result<(), String> {
+    let requirements = resolve_workflow_requirements(app_handle, workflow).await?;
+    if requirements.is_satisfied() {
+        Ok(())
+    } else {
+        Err(format!("Workflow cannot run: {}.", requirements.describe_problems()))
+    }
+}
+
+#[tauri::command]
+pub async fn check_workflow_requirements(app_handle: AppHandle<Wry>, workflow_json: String) -> Result<WorkflowRequirements, String> {
+    let workflow: Value = serde_json::from_str(&workflow_json).map_err(|e| format!("Invalid workflow JSON: {}", e))?;
+    resolve_workflow_requirements(&app_handle, &workflow).await
+}
@@ -0,0 +1,397 @@
+// metamorphosis-app/src-tauri/src/setup_manager/deep_verification.rs
+//
+// Deep verification runs every check we know of against the installed backend and returns
+// a structured report, instead of stopping at the first failure like the quick verification.
+// It is much slower (it imports torch and every custom node), so it runs on demand or at
+// startup only when enabled in settings.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_shell::ShellExt;
+
+use crate::process_manager::ProcessManager;
+use super::custom_node_manager::archive_install::read_archive_source;
+use super::custom_node_manager::git_ops::{head_commit, resolve_revision};
+use super::custom_node_manager::node_definitions::{CLIPSEG_NODE_NAME, CUSTOM_NODE_MANIFEST};
+use super::env_backend::selected_backend;
+use super::model_config::get_core_models_list;
+use super::python_utils::{get_comfyui_directory_path, get_conda_env_python_executable_path, get_python_version};
+use super::torch_probe::{evaluate_torch_probe, run_torch_probe, TorchDeviceReport};
+use super::verification::check_python_package_import;
+
+// Import names (not distribution names) of the packages ComfyUI cannot start without.
+const KEY_PACKAGE_IMPORTS: &[&str] = &["torch", "torchvision", "numpy", "PIL", "safetensors", "onnxruntime"];
+
+const EVT_DEEP_VERIFICATION_REPORT: &str = "deep_verification_report";
+
+const NODE_IMPORT_PROBE_SCRIPT: &str = r#"
+import sys, os, json, importlib.util, traceback
+names = sys.argv[1:]
+# comfy.cli_args parses sys.argv on import; hide our arguments from it.
+sys.argv = sys.argv[:1]
+comfy_dir = os.getcwd()
+sys.path.insert(0, comfy_dir)
+results = []
+for name in names:
+    path = os.path.join(comfy_dir, "custom_nodes", name)
+    if os.path.isdir(path):
+        path = os.path.join(path, "__init__.py")
+    module_name = "probe_" + "".join(c if c.isalnum() else "_" for c in name)
+    try:
+        spec = importlib.util.spec_from_file_location(module_name, path)
+        module = importlib.util.module_from_spec(spec)
+        sys.modules[module_name] = module
+        spec.loader.exec_module(module)
+        results.append({"name": name, "ok": True, "error": None})
+    except BaseException:
+        results.append({"name": name, "ok": False, "error": traceback.format_exc(limit=3)[-1500:]})
+print("NODE_PROBE_RESULT " + json.dumps(results))
+"#;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NodeImportResult {
+    pub name: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckCategory {
+    Model,
+    CustomNode,
+    PythonEnv,
+    PythonPackage,
+    TorchDevice,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Passed,
+    Warning,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationCheck {
+    pub category: CheckCategory,
+    pub name: String,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepVerificationReport {
+    pub passed: bool, // False if any check Failed; warnings don't fail the report
+    pub checks: Vec<VerificationCheck>,
+    pub duration_ms: u64,
+}
+
+impl DeepVerificationReport {
+    pub fn failed_checks(&self) -> Vec<&VerificationCheck> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Failed).collect()
+    }
+}
+
+fn check(category: CheckCategory, name: &str, status: CheckStatus, details: Option<String>) -> VerificationCheck {
+    VerificationCheck { category, name: name.to_string(), status, details }
+}
+
+pub(crate) fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+async fn verify_models(comfyui_dir: &Path) -> Vec<VerificationCheck> {
+    let models_base_path = comfyui_dir.join("models");
+    let mut checks = Vec::new();
+
+    for model in get_core_models_list().into_iter().filter(|m| m.is_essential) {
+        let model_path = models_base_path.join(&model.target_subdir).join(&model.target_filename);
+        let metadata = match std::fs::metadata(&model_path) {
+            Ok(m) if m.is_file() => m,
+            _ => {
+                checks.push(check(CheckCategory::Model, &model.id, CheckStatus::Failed, Some(format!("Not found at {}", model_path.display()))));
+                continue;
+            }
+        };
+
+        if let Some(expected) = model.expected_size_bytes {
+            if metadata.len() != expected {
+                checks.push(check(CheckCategory::Model, &model.id, CheckStatus::Failed, Some(format!("Size is {} bytes, expected {}", metadata.len(), expected))));
+                continue;
+            }
+        }
+
+        if let Some(expected_hash) = model.expected_sha256.clone() {
+            let path_for_hash = model_path.clone();
+            let hash_result = tokio::task::spawn_blocking(move || sha256_file(&path_for_hash))
+                .await
+                .map_err(|e| format!("Hashing task panicked: {}", e))
+                .and_then(|r| r);
+            match hash_result {
+                Ok(actual) if actual.eq_ignore_ascii_case(&expected_hash) => {}
+                Ok(actual) => {
+                    checks.push(check(CheckCategory::Model, &model.id, CheckStatus::Failed, Some(format!("SHA-256 mismatch: {} (expected {})", actual, expected_hash))));
+                    continue;
+                }
+                Err(e) => {
+                    checks.push(check(CheckCategory::Model, &model.id, CheckStatus::Failed, Some(e)));
+                    continue;
+                }
+            }
+        }
+
+        checks.push(check(CheckCategory::Model, &model.id, CheckStatus::Passed, Some(format!("{} bytes", metadata.len()))));
+    }
+    checks
+}
+
+/// Name a core node is imported under in `custom_nodes`. CLIPSeg is installed as a single file.
+pub(crate) fn node_import_target(node_name: &str) -> String {
+    if node_name == CLIPSEG_NODE_NAME { "clipseg.py".to_string() } else { node_name.to_string() }
+}
+
+fn core_node_import_targets() -> Vec<(String, String)> {
+    CUSTOM_NODE_MANIFEST
+        .iter()
+        .map(|node| (node.name.to_string(), node_import_target(node.name)))
+        .collect()
+}
+
+/// Checks that nodes with a pinned revision are still checked out at it. Unpinned nodes are
+/// reported as warnings: they track the default branch and can change under us.
+async fn verify_custom_node_revisions(app_handle: &AppHandle<Wry>, comfyui_dir: &Path) -> Vec<VerificationCheck> {
+    let mut checks = Vec::new();
+    for node in CUSTOM_NODE_MANIFEST {
+        let name = format!("{} revision", node.name);
+        let Some(revision) = node.revision else {
+            checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Warning, Some("No pinned revision; installed from the default branch".to_string())));
+            continue;
+        };
+        let repo_dir = comfyui_dir.join("custom_nodes").join(node.name);
+        if let Some(source) = read_archive_source(&repo_dir) {
+            let status = if source.revision.as_deref() == Some(revision) { CheckStatus::Passed } else { CheckStatus::Warning };
+            checks.push(check(CheckCategory::CustomNode, &name, status, Some(format!("Archive install of {:?}, pinned revision {}", source.revision, revision))));
+            continue;
+        }
+        if !repo_dir.join(".git").exists() {
+            continue; // Missing nodes are reported by verify_custom_nodes
+        }
+        let result = match (resolve_revision(app_handle, &repo_dir, revision).await, head_commit(app_handle, &repo_dir).await) {
+            (Ok(expected), Ok(head)) if expected == head => check(CheckCategory::CustomNode, &name, CheckStatus::Passed, Some(format!("At {} ({})", revision, head))),
+            (Ok(expected), Ok(head)) => check(CheckCategory::CustomNode, &name, CheckStatus::Warning, Some(format!("Checked out at {}, pinned revision {} is {}", head, revision, expected))),
+            (Err(e), _) | (_, Err(e)) => check(CheckCategory::CustomNode, &name, CheckStatus::Warning, Some(e)),
+        };
+        checks.push(result);
+    }
+    checks
+}
+
+async fn verify_custom_nodes(app_handle: &AppHandle<Wry>, python_exe: Option<&Path>, comfyui_dir: &Path) -> Vec<VerificationCheck> {
+    let custom_nodes_dir = comfyui_dir.join("custom_nodes");
+    let mut checks = Vec::new();
+    let mut present_targets = Vec::new();
+
+    for (name, target) in core_node_import_targets() {
+        let target_path = custom_nodes_dir.join(&target);
+        let present = if target_path.is_dir() {
+            std::fs::read_dir(&target_path).map(|mut d| d.next().is_some()).unwrap_or(false)
+        } else {
+            target_path.is_file()
+        };
+        if present {
+            present_targets.push((name, target));
+        } else {
+            checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Failed, Some(format!("Not found at {}", target_path.display()))));
+        }
+    }
+
+    if present_targets.is_empty() {
+        return checks;
+    }
+
+    let python_exe = match python_exe {
+        Some(p) => p,
+        None => {
+            for (name, _) in present_targets {
+                checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Warning, Some("Present, import not checked: Python env unavailable".to_string())));
+            }
+            return checks;
+        }
+    };
+
+    let targets: Vec<&str> = present_targets.iter().map(|(_, t)| t.as_str()).collect();
+    match probe_node_imports(app_handle, python_exe, comfyui_dir, &targets).await {
+        Ok(results) => {
+            for (name, target) in present_targets {
+                match results.iter().find(|r| r.name == target) {
+                    Some(r) if r.ok => {
+                        checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Passed, None));
+                    }
+                    Some(r) => {
+                        checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Failed, r.error.clone()));
+                    }
+                    None => {
+                        checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Failed, Some("No result from import probe".to_string())));
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            warn!("[DEEP_VERIFY] Custom node import probe failed: {}", e);
+            for (name, _) in present_targets {
+                checks.push(check(CheckCategory::CustomNode, &name, CheckStatus::Failed, Some(format!("Import probe failed: {}", e))));
+            }
+        }
+    }
+    checks
+}
+
+/// Imports each custom node (directory or single file under `custom_nodes`) the way ComfyUI does
+/// and reports which ones fail.
+pub(crate) async fn probe_node_imports(app_handle: &AppHandle<Wry>, python_exe: &Path, comfyui_dir: &Path, targets: &[&str]) -> Result<Vec<NodeImportResult>, String> {
+    let json = run_probe_script(
+        app_handle,
+        python_exe,
+        comfyui_dir,
+        "deep_verify_node_imports.py",
+        NODE_IMPORT_PROBE_SCRIPT,
+        targets,
+        "NODE_PROBE_RESULT ",
+    ).await?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+/// Writes `script` to the app cache dir, runs it with the env Python and returns the text after `result_prefix`.
+pub(crate) async fn run_probe_script(
+    app_handle: &AppHandle<Wry>,
+    python_exe: &Path,
+    cwd: &Path,
+    script_name: &str,
+    script: &str,
+    args: &[&str],
+    result_prefix: &str,
+) -> Result<String, String> {
+    let cache_dir = app_handle.path().app_cache_dir().map_err(|e| e.to_string())?;
+    tokio::fs::create_dir_all(&cache_dir).await.map_err(|e| e.to_string())?;
+    let script_path = cache_dir.join(script_name);
+    tokio::fs::write(&script_path, script).await.map_err(|e| format!("Failed to write probe script {}: {}", script_path.display(), e))?;
+
+    let command = app_handle.shell().command(python_exe.to_string_lossy().as_ref())
+        .arg(&script_path)
+        .args(args)
+        .current_dir(cwd.to_string_lossy().as_ref());
+
+    let result = ProcessManager::spawn_and_wait_for_process(app_handle, command, "deep_verify_probe").await?;
+    if let Some(line) = result.stdout.iter().rev().find(|l| l.starts_with(result_prefix)) {
+        return Ok(line[result_prefix.len()..].trim().to_string());
+    }
+    Err(format!(
+        "Probe exited with code {:?} without a result. Stderr: {}",
+        result.exit_code,
+        result.stderr.iter().rev().take(10).rev().cloned().collect::<Vec<_>>().join("\n")
+    ))
+}
+
+async fn verify_torch_device(app_handle: &AppHandle<Wry>, python_exe: &Path, comfyui_dir: &Path) -> VerificationCheck {
+    let probe = match run_torch_probe(app_handle, python_exe, comfyui_dir).await {
+        Ok(p) => p,
+        Err(e) => return check(CheckCategory::TorchDevice, "torch device", CheckStatus::Failed, Some(e)),
+    };
+    let details = probe.summary();
+    match evaluate_torch_probe(app_handle, probe).await {
+        Ok(TorchDeviceReport { mismatch: Some(mismatch), .. }) => {
+            check(CheckCategory::TorchDevice, "torch device", CheckStatus::Warning, Some(mismatch.message))
+        }
+        _ => check(CheckCategory::TorchDevice, "torch device", CheckStatus::Passed, Some(details)),
+    }
+}
+
+/// Runs every check and collects the results. Individual check failures are reported, not returned as errors.
+pub async fn perform_deep_verification(app_handle: &AppHandle<Wry>) -> Result<DeepVerificationReport, String> {
+    let start = std::time::Instant::now();
+    info!("[DEEP_VERIFY] Starting deep verification...");
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+    let mut checks = Vec::new();
+
+    checks.extend(verify_models(&comfyui_dir).await);
+
+    // Python env (conda env or venv) and Python version
+    let python_exe: Option<PathBuf> = match get_conda_env_python_executable_path(app_handle, "comfyui_env").await {
+        Ok(p) if p.is_file() => Some(p),
+        Ok(p) => {
+            checks.push(check(CheckCategory::PythonEnv, "comfyui_env", CheckStatus::Failed, Some(format!("Python executable not found at {}", p.display()))));
+            None
+        }
+        Err(e) => {
+            checks.push(check(CheckCategory::PythonEnv, "comfyui_env", CheckStatus::Failed, Some(e)));
+            None
+        }
+    };
+
+    if let Some(python_exe) = &python_exe {
+        let expected_python_version = selected_backend(app_handle).expected_python_version();
+        match get_python_version(app_handle, python_exe).await {
+            Ok(version) if version == expected_python_version => {
+                checks.push(check(CheckCategory::PythonEnv, "comfyui_env", CheckStatus::Passed, Some(format!("Python {}", version))));
+            }
+            Ok(version) => {
+                checks.push(check(CheckCategory::PythonEnv, "comfyui_env", CheckStatus::Warning, Some(format!("Python {}, expected {}", version, expected_python_version))));
+            }
+            Err(e) => {
+                checks.push(check(CheckCategory::PythonEnv, "comfyui_env", CheckStatus::Failed, Some(e)));
+            }
+        }
+
+        for package in KEY_PACKAGE_IMPORTS {
+            match check_python_package_import(app_handle, package, python_exe, &comfyui_dir).await {
+                Ok(_) => checks.push(check(CheckCategory::PythonPackage, package, CheckStatus::Passed, None)),
+                Err(e) => checks.push(check(CheckCategory::PythonPackage, package, CheckStatus::Failed, Some(e))),
+            }
+        }
+
+        checks.push(verify_torch_device(app_handle, python_exe, &comfyui_dir).await);
+    }
+
+    checks.extend(verify_custom_nodes(app_handle, python_exe.as_deref(), &comfyui_dir).await);
+    checks.extend(verify_custom_node_revisions(app_handle, &comfyui_dir).await);
+
+    let report = DeepVerificationReport {
+        passed: !checks.iter().any(|c| c.status == CheckStatus::Failed),
+        checks,
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    if report.passed {
+        info!("[DEEP_VERIFY] Deep verification passed in {} ms.", report.duration_ms);
+    } else {
+        warn!("[DEEP_VERIFY] Deep verification found {} failed check(s): {:?}", report.failed_checks().len(), report.failed_checks());
+    }
+    if let Err(e) = app_handle.emit(EVT_DEEP_VERIFICATION_REPORT, report.clone()) {
+        error!("[DEEP_VERIFY] Failed to emit {}: {}", EVT_DEEP_VERIFICATION_REPORT, e);
+    }
+    Ok(report)
+}
+
+/// Runs the deep verification on demand, e.g. from a "Verify installation" button.
+#[tauri::command]
+pub async fn run_deep_verification(app_handle: AppHandle<Wry>) -> Result<DeepVerificationReport, String> {
+    perform_deep_verification(&app_handle).await
+}
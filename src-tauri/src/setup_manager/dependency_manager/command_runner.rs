@@ -3,7 +3,8 @@
 use std::path::PathBuf;
 use log::{info, error, debug, warn};
 use tauri::{AppHandle, Wry, Manager};
-use crate::setup_manager::event_utils::emit_setup_progress;
+use crate::setup_manager::event_utils::{emit_install_package_progress, emit_setup_progress};
+use super::install_progress::InstallProgressTracker;
 use crate::process_manager::ProcessManager;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
@@ -75,7 +76,25 @@ pub async fn run_command_for_setup_progress_with_env(
     let temp_log_path = temp_log_dir.join(format!("command_output_{}.log", Uuid::new_v4()));
     info!("Temporary command output being written to: {}", temp_log_path.display());
 
-    let result = ProcessManager::spawn_and_wait_for_process(app_handle, cmd, current_step_base).await?;
+    // Parse pip/uv/conda output as it streams in so the phase advances per package instead of
+    // jumping only when the whole command finishes.
+    let mut tracker = InstallProgressTracker::new();
+    let mut last_emitted_progress = progress_current_phase;
+    let result = ProcessManager::spawn_and_wait_for_process_with_line_handler(app_handle, cmd, current_step_base, |line, _is_stderr| {
+        if let Some(snapshot) = tracker.observe(line) {
+            let live_progress = progress_current_phase + (progress_weight_of_this_command as f32 * snapshot.fraction()) as u8;
+            if live_progress > last_emitted_progress {
+                last_emitted_progress = live_progress;
+                let detail = match (&snapshot.current_package, snapshot.packages_total) {
+                    (Some(package), total) if total > 0 && snapshot.packages_total_known => format!("{} ({}/{})", package, snapshot.packages_done, total),
+                    (Some(package), _) => package.clone(),
+                    (None, _) => current_step_base.to_string(),
+                };
+                emit_setup_progress(app_handle, phase, current_step_base, live_progress.min(100), Some(detail), None);
+            }
+            emit_install_package_progress(app_handle, phase, current_step_base, snapshot);
+        }
+    }).await?;
 
     // Process stdout
     for line in &result.stdout {
@@ -90,7 +109,7 @@ pub async fn run_command_for_setup_progress_with_env(
         let is_noisy_info = lower_line.contains("looking in indexes:") || lower_line.contains("satisfied constraint") || lower_line.contains("source distribution") || lower_line.contains("cache entry deserialization failed") || lower_line.starts_with("running command ") || is_spinner_line;
         if is_key_action || (!is_noisy_info && !is_progress_bar_line) {
             let dynamic_step_message = if is_key_action { format!("Error during setup: {}", line_to_process) } else { current_step_base.to_string() };
-            emit_setup_progress(app_handle, phase, &dynamic_step_message, progress_current_phase, Some(line_to_process), None);
+            emit_setup_progress(app_handle, phase, &dynamic_step_message, last_emitted_progress, Some(line_to_process), None);
         } else {
             debug!("Filtered (stdout): {}", line_to_process);
         }
@@ -108,7 +127,7 @@ pub async fn run_command_for_setup_progress_with_env(
         let is_pure_progress_artifact = line_to_process.trim().chars().all(|c| c == '[' || c == 'A' || c.is_whitespace()) && line_to_process.len() < 50 && (line_to_process.contains('[') || line_to_process.contains('A'));
         let is_noisy_stderr_info = lower_line.contains("defaulting to user installation") || lower_line.contains("consider adding this directory to path") || (lower_line.starts_with("warning: the script ") && lower_line.contains("is installed in")) || (lower_line.contains("deprecated") && !lower_line.contains("error")) || lower_line.contains("skipping link:") || (lower_line.contains("note:") && !lower_line.contains("error")) || lower_line.contains("running build_ext") || lower_line.contains("running build_py") || lower_line.contains("running egg_info") || lower_line.contains("writing ") || lower_line.contains("copying ") || lower_line.contains("creating ") || is_progress_bar_line || is_spinner_line || is_pure_progress_artifact;
         if lower_line.contains("error:") || (lower_line.contains("warning:") && !is_noisy_stderr_info) || lower_line.contains("nvrtc-builtins64_124.dll") || lower_line.contains("condahttp") || lower_line.contains("connection failed") || lower_line.contains("http ") {
-            emit_setup_progress(app_handle, phase, current_step_base, progress_current_phase, Some(line_to_process.clone()), Some(line_to_process));
+            emit_setup_progress(app_handle, phase, current_step_base, last_emitted_progress, Some(line_to_process.clone()), Some(line_to_process));
         } else {
             info!("Filtered/Demoted (stderr): {}", line_to_process);
         }
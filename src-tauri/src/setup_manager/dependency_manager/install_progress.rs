@@ -0,0 +1,421 @@
+// metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/install_progress.rs
+//
+// Turns pip, uv and conda output into structured, package-level progress.
+// `parse_install_line` recognises the lines that mark a package moving through
+// resolve -> download -> install, and `InstallProgressTracker` folds them into counts and bytes
+// that command_runner turns into `install-package-progress` events and a smoothly advancing
+// setup-progress value. Lines that aren't recognised are ignored here; command_runner still
+// streams the text as before.
+
+use std::collections::HashSet;
+use serde::Serialize;
+
+// While the package total is still unknown, the download share is half full after this many packages.
+const UNKNOWN_TOTAL_HALF_WAY_PACKAGES: f32 = 10.0;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallStage {
+    Resolving,
+    Downloading,
+    Installing,
+    Done,
+}
+
+/// One recognised line of installer output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallOutputEvent {
+    /// pip `Collecting X`, conda plan entries: a package that will be installed.
+    Collecting { package: String },
+    /// pip `Requirement already satisfied: X`: nothing to do for this package.
+    AlreadySatisfied { package: String },
+    /// pip `Downloading X (N MB)` / `Using cached X (N MB)`, conda progress bars.
+    Downloading { file: String, bytes: Option<u64> },
+    /// pip `Installing collected packages: a, b`, conda `Executing transaction`.
+    InstallingCollected { packages: Vec<String> },
+    /// pip `Successfully installed a-1 b-2`, conda `Executing transaction: done`.
+    SuccessfullyInstalled { count: usize },
+    /// uv `Resolved N packages`: the total is known up front.
+    Resolved { total: usize },
+    /// conda `Downloading and Extracting Packages`: the plan listed above it is complete.
+    PlanComplete,
+}
+
+/// Parses a size such as `906.4 MB`, `12 kB`, `1.2 GB` (pip, conda) or `2.4GiB` (uv, no space,
+/// binary units) into bytes.
+fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let unit_start = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let number: f64 = text[..unit_start].parse().ok()?;
+    let multiplier = match text[unit_start..].trim().to_ascii_lowercase().as_str() {
+        "bytes" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+/// Strips version specifiers and extras from a requirement: `numpy>=1.25 (from -r ...)` -> `numpy`.
+fn requirement_name(spec: &str) -> String {
+    spec.split(|c: char| " <>=!~;[(@".contains(c)).next().unwrap_or(spec).trim().to_string()
+}
+
+/// `Downloading https://host/torch-2.5.1%2Bcu124-cp310-cp310-win_amd64.whl (2510.8 MB)` -> (file name, bytes)
+fn parse_download(rest: &str) -> InstallOutputEvent {
+    let (target, size) = match rest.rfind(" (") {
+        Some(idx) if rest.ends_with(')') => (&rest[..idx], parse_size(&rest[idx + 2..rest.len() - 1])),
+        _ => (rest, None),
+    };
+    let file = target.trim().rsplit('/').next().unwrap_or(target).replace("%2B", "+");
+    InstallOutputEvent::Downloading { file, bytes: size }
+}
+
+/// pip >= 23 fetches `<wheel>.whl.metadata` while resolving; that isn't the package download.
+fn is_metadata_download(event: &InstallOutputEvent) -> bool {
+    matches!(event, InstallOutputEvent::Downloading { file, .. } if file.ends_with(".metadata"))
+}
+
+/// Recognises a single line of pip, uv or conda output.
+pub fn parse_install_line(line: &str) -> Option<InstallOutputEvent> {
+    let trimmed = line.trim();
+
+    // pip
+    if let Some(rest) = trimmed.strip_prefix("Collecting ") {
+        return Some(InstallOutputEvent::Collecting { package: requirement_name(rest) });
+    }
+    if let Some(rest) = trimmed.strip_prefix("Requirement already satisfied: ") {
+        return Some(InstallOutputEvent::AlreadySatisfied { package: requirement_name(rest) });
+    }
+    if trimmed.starts_with("Downloading and Extracting Packages") {
+        return Some(InstallOutputEvent::PlanComplete);
+    }
+    if let Some(rest) = trimmed.strip_prefix("Downloading ").or_else(|| trimmed.strip_prefix("Using cached ")) {
+        let event = parse_download(rest);
+        return if is_metadata_download(&event) { None } else { Some(event) };
+    }
+    if let Some(rest) = trimmed.strip_prefix("Installing collected packages: ") {
+        let packages = rest.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+        return Some(InstallOutputEvent::InstallingCollected { packages });
+    }
+    if let Some(rest) = trimmed.strip_prefix("Successfully installed ") {
+        return Some(InstallOutputEvent::SuccessfullyInstalled { count: rest.split_whitespace().count() });
+    }
+
+    // uv: "Resolved 45 packages in 1.2s", "Installed 12 packages in 3.4s", " + torch==2.5.1"
+    if let Some(rest) = trimmed.strip_prefix("Resolved ") {
+        if let Some(total) = rest.split_whitespace().next().and_then(|n| n.parse().ok()) {
+            return Some(InstallOutputEvent::Resolved { total });
+        }
+    }
+    if let Some(rest) = trimmed.strip_prefix("Installed ") {
+        if let Some(count) = rest.split_whitespace().next().and_then(|n| n.parse().ok()) {
+            return Some(InstallOutputEvent::SuccessfullyInstalled { count });
+        }
+    }
+    if let Some(rest) = trimmed.strip_prefix("+ ") {
+        return Some(InstallOutputEvent::Collecting { package: requirement_name(rest) });
+    }
+
+    // conda plan entries: "  pytorch            pytorch/win-64::pytorch-2.5.1-py3.10_cuda12.4_0"
+    let mut columns = trimmed.split_whitespace();
+    if let (Some(name), Some(source), None) = (columns.next(), columns.next(), columns.next()) {
+        if source.contains("::") {
+            return Some(InstallOutputEvent::Collecting { package: name.to_string() });
+        }
+    }
+    // conda progress bars: "pytorch-2.5.1        | 1.4 GB    | ########## | 100%"
+    let bar_columns: Vec<&str> = trimmed.split('|').map(|c| c.trim()).collect();
+    if bar_columns.len() >= 4 && bar_columns[3].starts_with("100%") {
+        return Some(InstallOutputEvent::Downloading { file: bar_columns[0].to_string(), bytes: parse_size(bar_columns[1]) });
+    }
+    if trimmed.starts_with("Executing transaction") {
+        return Some(if trimmed.ends_with("done") {
+            InstallOutputEvent::SuccessfullyInstalled { count: 0 }
+        } else {
+            InstallOutputEvent::InstallingCollected { packages: Vec::new() }
+        });
+    }
+    None
+}
+
+/// Snapshot of an install command's progress, sent as the `install-package-progress` payload.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProgressSnapshot {
+    pub stage: InstallStage,
+    pub current_package: Option<String>,
+    pub packages_done: usize,
+    pub packages_total: usize,
+    // False while pip is still discovering packages; `packages_total` only grows until then.
+    pub packages_total_known: bool,
+    pub bytes_downloaded: u64,
+}
+
+impl InstallProgressSnapshot {
+    /// Fraction of the command's work done, 0.0-1.0. Downloads are the bulk of the time, so they
+    /// cover up to 80%; unpacking and installing take the rest.
+    pub fn fraction(&self) -> f32 {
+        let done = self.packages_done as f32;
+        let done_ratio = if !self.packages_total_known {
+            // pip downloads while it resolves, so every package seen so far is also done. Approach
+            // the end of the download share without reaching it until the total is known.
+            done / (done + UNKNOWN_TOTAL_HALF_WAY_PACKAGES)
+        } else if self.packages_total == 0 {
+            0.0
+        } else {
+            done / self.packages_total as f32
+        };
+        match self.stage {
+            InstallStage::Resolving => 0.0,
+            InstallStage::Downloading => 0.8 * done_ratio.min(1.0),
+            InstallStage::Installing => 0.8,
+            InstallStage::Done => 1.0,
+        }
+    }
+}
+
+/// Folds parsed lines into a running snapshot.
+pub struct InstallProgressTracker {
+    snapshot: InstallProgressSnapshot,
+    downloaded: HashSet<String>,
+}
+
+impl InstallProgressTracker {
+    pub fn new() -> Self {
+        InstallProgressTracker {
+            snapshot: InstallProgressSnapshot {
+                stage: InstallStage::Resolving,
+                current_package: None,
+                packages_done: 0,
+                packages_total: 0,
+                packages_total_known: false,
+                bytes_downloaded: 0,
+            },
+            downloaded: HashSet::new(),
+        }
+    }
+
+    /// Feeds one output line. Returns the new snapshot when the line changed it.
+    pub fn observe(&mut self, line: &str) -> Option<InstallProgressSnapshot> {
+        let s = &mut self.snapshot;
+        match parse_install_line(line)? {
+            InstallOutputEvent::Collecting { package } => {
+                // pip discovers the total as it resolves; uv and conda report it up front.
+                if !s.packages_total_known && s.stage != InstallStage::Done {
+                    s.packages_total += 1;
+                }
+                s.current_package = Some(package);
+            }
+            InstallOutputEvent::AlreadySatisfied { package } => {
+                if !s.packages_total_known {
+                    s.packages_total += 1;
+                }
+                s.packages_done += 1;
+                s.current_package = Some(package);
+            }
+            InstallOutputEvent::Downloading { file, bytes } => {
+                // conda can repeat a finished progress bar; count each file once.
+                if !self.downloaded.insert(file.clone()) {
+                    return None;
+                }
+                s.stage = InstallStage::Downloading;
+                s.packages_done += 1;
+                s.packages_total = s.packages_total.max(s.packages_done);
+                s.bytes_downloaded += bytes.unwrap_or(0);
+                s.current_package = Some(file);
+            }
+            InstallOutputEvent::Resolved { total } => {
+                s.packages_total = total;
+                s.packages_total_known = true;
+            }
+            InstallOutputEvent::PlanComplete => {
+                s.packages_total_known = true;
+            }
+            InstallOutputEvent::InstallingCollected { packages } => {
+                // pip only starts installing once everything is resolved and downloaded.
+                s.stage = InstallStage::Installing;
+                s.packages_total = s.packages_total.max(s.packages_done);
+                s.packages_total_known = true;
+                s.current_package = if packages.is_empty() { None } else { Some(packages.join(", ")) };
+            }
+            InstallOutputEvent::SuccessfullyInstalled { count } => {
+                s.stage = InstallStage::Done;
+                s.packages_total = s.packages_total.max(count);
+                s.packages_done = s.packages_total;
+                s.packages_total_known = true;
+                s.current_package = None;
+            }
+        }
+        Some(self.snapshot.clone())
+    }
+}
+
+impl Default for InstallProgressTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured from `pip install -r requirements.txt` (pip 24).
+    const PIP_OUTPUT: &str = "\
+Collecting torchsde (from -r requirements.txt (line 1))
+  Downloading torchsde-0.2.6-py3-none-any.whl.metadata (5.3 kB)
+Collecting einops (from -r requirements.txt (line 2))
+  Using cached einops-0.8.0-py3-none-any.whl.metadata (12 kB)
+Requirement already satisfied: numpy>=1.25.0 in ./env/lib/python3.10/site-packages (from -r requirements.txt (line 3)) (1.26.4)
+Collecting trampoline>=0.1.2 (from torchsde->-r requirements.txt (line 1))
+  Downloading trampoline-0.1.2-py3-none-any.whl.metadata (10.0 kB)
+Downloading torchsde-0.2.6-py3-none-any.whl (61 kB)
+   ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━ 61.2/61.2 kB 2.1 MB/s eta 0:00:00
+Using cached einops-0.8.0-py3-none-any.whl (43 kB)
+Downloading trampoline-0.1.2-py3-none-any.whl (5.2 kB)
+Installing collected packages: trampoline, einops, torchsde
+Successfully installed einops-0.8.0 torchsde-0.2.6 trampoline-0.1.2
+";
+
+    // Captured from `uv pip install -r requirements.txt`.
+    const UV_OUTPUT: &str = "\
+Resolved 12 packages in 1.21s
+Downloading torch (2.4GiB)
+Prepared 3 packages in 2.50s
+Installed 3 packages in 45ms
+ + einops==0.8.0
+ + torch==2.5.1
+ + trampoline==0.1.2
+";
+
+    // Captured from `conda install pytorch torchaudio pytorch-cuda=12.4 -c pytorch -c nvidia -y`.
+    const CONDA_OUTPUT: &str = "\
+## Package Plan ##
+
+  environment location: /env
+
+  added / updated specs:
+    - pytorch
+    - pytorch-cuda=12.4
+    - torchaudio
+
+
+The following NEW packages will be INSTALLED:
+
+  pytorch            pytorch/linux-64::pytorch-2.5.1-py3.10_cuda12.4_cudnn9.1.0_0
+  pytorch-cuda       pytorch/linux-64::pytorch-cuda-12.4-hc786d27_7
+  torchaudio         pytorch/linux-64::torchaudio-2.5.1-py310_cu124
+
+
+Downloading and Extracting Packages:
+pytorch-cuda-12.4    | 7 KB      | ########## | 100%
+torchaudio-2.5.1     | 6.1 MB    | ########## | 100%
+pytorch-cuda-12.4    | 7 KB      | ########## | 100%
+pytorch-2.5.1        | 1.4 GB    | ########## | 100%
+Preparing transaction: done
+Verifying transaction: done
+Executing transaction: done
+";
+
+    /// Feeds `output` line by line and returns every snapshot produced.
+    fn track(output: &str) -> Vec<InstallProgressSnapshot> {
+        let mut tracker = InstallProgressTracker::new();
+        output.lines().filter_map(|line| tracker.observe(line)).collect()
+    }
+
+    fn assert_monotonic(snapshots: &[InstallProgressSnapshot]) {
+        for pair in snapshots.windows(2) {
+            assert!(pair[1].fraction() >= pair[0].fraction(), "progress went backwards: {:?} -> {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn pip_progress_does_not_jump_before_resolution_finishes() {
+        let snapshots = track(PIP_OUTPUT);
+        assert_monotonic(&snapshots);
+
+        let first_download = snapshots.iter().find(|s| s.stage == InstallStage::Downloading).unwrap();
+        assert!(!first_download.packages_total_known);
+        assert!(first_download.fraction() < 0.2, "first download already at {}", first_download.fraction());
+
+        let installing = snapshots.iter().find(|s| s.stage == InstallStage::Installing).unwrap();
+        assert!(installing.packages_total_known);
+        assert_eq!(installing.fraction(), 0.8);
+
+        let last = snapshots.last().unwrap();
+        assert_eq!(last.stage, InstallStage::Done);
+        assert_eq!((last.packages_done, last.packages_total), (4, 4));
+        assert_eq!(last.bytes_downloaded, 109_200);
+        assert_eq!(last.fraction(), 1.0);
+    }
+
+    #[test]
+    fn pip_metadata_fetches_are_not_package_downloads() {
+        assert_eq!(parse_install_line("  Downloading torchsde-0.2.6-py3-none-any.whl.metadata (5.3 kB)"), None);
+        assert_eq!(
+            parse_install_line("Downloading https://download.pytorch.org/whl/cu124/torch-2.5.1%2Bcu124-cp310-cp310-win_amd64.whl (2510.8 MB)"),
+            Some(InstallOutputEvent::Downloading { file: "torch-2.5.1+cu124-cp310-cp310-win_amd64.whl".to_string(), bytes: Some(2_510_800_000) })
+        );
+    }
+
+    #[test]
+    fn parses_decimal_and_binary_sizes() {
+        let cases = [
+            ("906.4 MB", Some(906_400_000)),
+            ("12 kB", Some(12_000)),
+            ("512 bytes", Some(512)),
+            ("2.4GiB", Some(2_576_980_377)),
+            ("15.1MiB", Some(15_833_497)),
+            ("64KiB", Some(65_536)),
+            ("1.4 GB", Some(1_400_000_000)),
+            ("fast", None),
+            ("12 parsecs", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_size(text), expected, "size of '{}'", text);
+        }
+    }
+
+    #[test]
+    fn uv_total_is_known_up_front() {
+        let snapshots = track(UV_OUTPUT);
+        assert_monotonic(&snapshots);
+
+        assert!(snapshots[0].packages_total_known);
+        assert_eq!(snapshots[0].packages_total, 12);
+        let download = &snapshots[1];
+        assert_eq!(download.stage, InstallStage::Downloading);
+        assert_eq!(download.bytes_downloaded, 2_576_980_377); // 2.4GiB
+        assert!((download.fraction() - 0.8 / 12.0).abs() < 1e-6);
+
+        let last = snapshots.last().unwrap();
+        assert_eq!(last.stage, InstallStage::Done);
+        assert_eq!(last.packages_total, 12);
+        assert_eq!(last.fraction(), 1.0);
+    }
+
+    #[test]
+    fn conda_total_comes_from_the_plan() {
+        let snapshots = track(CONDA_OUTPUT);
+        assert_monotonic(&snapshots);
+
+        let plan_done = snapshots.iter().find(|s| s.packages_total_known).unwrap();
+        assert_eq!(plan_done.stage, InstallStage::Resolving);
+        assert_eq!(plan_done.packages_total, 3);
+
+        // The repeated pytorch-cuda bar is counted once.
+        let downloads: Vec<_> = snapshots.iter().filter(|s| s.stage == InstallStage::Downloading).collect();
+        assert_eq!(downloads.len(), 3);
+        assert!((downloads[0].fraction() - 0.8 / 3.0).abs() < 1e-6);
+        assert_eq!(downloads[2].packages_done, 3);
+        assert_eq!(downloads[2].bytes_downloaded, 1_406_107_000);
+
+        assert_eq!(snapshots.last().unwrap().stage, InstallStage::Done);
+        assert_eq!(snapshots.last().unwrap().fraction(), 1.0);
+    }
+}
@@ -0,0 +1,269 @@
+// metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/lockfile.rs
+//
+// Hash-pinned Python dependency lockfiles. ComfyUI's and the custom nodes' requirements.txt
+// files are unpinned, so two machines set up a week apart end up with different packages.
+// We ship lockfiles in `resources/python-locks`, one per platform, Python version and torch
+// variant, in pip's `name==version --hash=sha256:...` format. When one matches this machine,
+// setup installs it with `--require-hashes` instead of the requirements files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::setup_manager::custom_node_manager::node_definitions::find_custom_node;
+use crate::setup_manager::deep_verification::sha256_file;
+use crate::setup_manager::env_backend::{find_bundled_python, pip_freeze, selected_backend, EnvBackendKind, COMFYUI_ENV_NAME};
+use crate::setup_manager::orchestration::get_app_root_path;
+use crate::setup_manager::python_utils::{execute_command_to_string, get_conda_env_python_executable_path};
+use super::torch_resolver::resolve_torch_for_host;
+
+const PYTHON_LOCKS_SUBDIR: &str = "resources/python-locks";
+// The bundler puts resources from outside src-tauri (`../resources/...`) under `_up_`.
+const BUNDLED_PYTHON_LOCKS_SUBDIR: &str = "_up_/resources/python-locks";
+const LOCKFILE_EXTENSION: &str = "lock.txt";
+// Packages that come from the PyTorch wheel index rather than PyPI.
+const TORCH_INDEX_PACKAGES: &[&str] = &["torch", "torchvision", "torchaudio"];
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LockEntry {
+    pub name: String,
+    pub version: String,
+    pub hashes: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionMismatch {
+    pub name: String,
+    pub locked: String,
+    pub installed: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LockDriftReport {
+    pub lockfile: String,
+    pub in_sync: bool,
+    pub missing: Vec<String>,                    // Locked but not installed
+    pub version_mismatches: Vec<VersionMismatch>,
+    pub unlocked: Vec<String>,                   // Installed but absent from the lock
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LockRegenerationReport {
+    pub lockfile: String,
+    pub locked_packages: usize,
+    pub skipped: Vec<String>, // Freeze lines we can't pin by hash (editable or direct URL installs)
+}
+
+/// Normalized project name per PEP 503, so `Pillow`/`pillow` and `typing_extensions`/`typing-extensions` compare equal.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Lockfile name for this machine. The Python version has its dot removed, so the Conda env
+/// (3.10) with a CUDA 12.4 build gives `windows-x86_64-py310-cu124.lock.txt` and the venv
+/// backend (3.12) gives `windows-x86_64-py312-cu124.lock.txt`.
+pub fn lockfile_name(app_handle: &AppHandle<Wry>) -> String {
+    let python_version = selected_backend(app_handle).expected_python_version().replace('.', "");
+    format!(
+        "{}-{}-py{}-{}.{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        python_version,
+        resolve_torch_for_host().variant.tag(),
+        LOCKFILE_EXTENSION
+    )
+}
+
+/// Directory the lockfiles are read from: the source tree in debug builds, the bundled
+/// resources in release builds.
+fn python_locks_dir(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        Ok(get_app_root_path()?.join(PYTHON_LOCKS_SUBDIR))
+    } else {
+        let resource_dir = app_handle.path().resource_dir().map_err(|e| format!("Tauri error getting resource directory: {}", e))?;
+        Ok(resource_dir.join(BUNDLED_PYTHON_LOCKS_SUBDIR))
+    }
+}
+
+pub fn get_lockfile_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    Ok(python_locks_dir(app_handle)?.join(lockfile_name(app_handle)))
+}
+
+/// The shipped lockfile for this machine, if there is one.
+pub fn active_lockfile(app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    get_lockfile_path(app_handle).ok().filter(|p| p.is_file())
+}
+
+/// `pip install` arguments for ComfyUI's dependencies: the lockfile with hash checking when
+/// one matches this machine, otherwise ComfyUI's unpinned requirements.txt.
+pub fn requirements_install_args(app_handle: &AppHandle<Wry>, requirements_path: &Path) -> Vec<String> {
+    match active_lockfile(app_handle) {
+        Some(lockfile) => {
+            info!("[PYTHON_LOCK] Installing from lockfile {} with hash checking.", lockfile.display());
+            let mut args = vec![
+                "-r".to_string(),
+                lockfile.to_string_lossy().to_string(),
+                "--require-hashes".to_string(),
+                "--no-deps".to_string(), // The lock is the complete dependency closure
+            ];
+            args.extend(resolve_torch_for_host().variant.pip_extra_index_args());
+            args
+        }
+        None => {
+            warn!("[PYTHON_LOCK] No lockfile for this platform ({}) in resources/python-locks. Installing unpinned requirements.", lockfile_name(app_handle));
+            vec!["-r".to_string(), requirements_path.to_string_lossy().to_string()]
+        }
+    }
+}
+
+/// Core custom nodes' requirements are part of the lock, so their per-node install is skipped
+/// when a lockfile is in use. Other nodes still install their own requirements.
+pub fn is_covered_by_lockfile(app_handle: &AppHandle<Wry>, node_name: &str) -> bool {
+    active_lockfile(app_handle).is_some() && find_custom_node(node_name).is_some()
+}
+
+/// Parses a pip requirements file with `--hash` options, joining `\` continuation lines.
+pub fn parse_lockfile(contents: &str) -> Vec<LockEntry> {
+    let joined = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+    joined
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or("").trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('-'))
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            let (name, version) = tokens.next()?.split_once("==")?;
+            let hashes = tokens.filter_map(|t| t.strip_prefix("--hash=")).map(|h| h.to_string()).collect();
+            Some(LockEntry { name: name.to_string(), version: version.to_string(), hashes })
+        })
+        .collect()
+}
+
+/// Splits `pip freeze` output into pinned packages and lines that can't be pinned by hash.
+pub(crate) fn parse_freeze(output: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let mut pinned = Vec::new();
+    let mut skipped = Vec::new();
+    for line in output.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        match line.split_once("==") {
+            Some((name, version)) if !line.starts_with('-') && !line.contains(" @ ") => {
+                pinned.push((name.to_string(), version.to_string()))
+            }
+            _ => skipped.push(line.to_string()),
+        }
+    }
+    (pinned, skipped)
+}
+
+/// Compares the installed env against the lockfile for this machine.
+pub async fn compute_lock_drift(app_handle: &AppHandle<Wry>) -> Result<LockDriftReport, String> {
+    let lockfile = active_lockfile(app_handle)
+        .ok_or_else(|| format!("No lockfile shipped for this platform ({}).", lockfile_name(app_handle)))?;
+    let contents = fs::read_to_string(&lockfile).map_err(|e| format!("Failed to read lockfile {}: {}", lockfile.display(), e))?;
+    let locked: HashMap<String, LockEntry> = parse_lockfile(&contents).into_iter().map(|e| (normalize_name(&e.name), e)).collect();
+
+    let (installed_list, _) = parse_freeze(&pip_freeze(app_handle).await?);
+    let installed: HashMap<String, String> = installed_list.into_iter().map(|(n, v)| (normalize_name(&n), v)).collect();
+
+    let mut missing = Vec::new();
+    let mut version_mismatches = Vec::new();
+    for (name, entry) in &locked {
+        match installed.get(name) {
+            None => missing.push(entry.name.clone()),
+            Some(version) if *version != entry.version => version_mismatches.push(VersionMismatch {
+                name: entry.name.clone(),
+                locked: entry.version.clone(),
+                installed: version.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    let mut unlocked: Vec<String> = installed.keys().filter(|n| !locked.contains_key(*n)).cloned().collect();
+    missing.sort();
+    version_mismatches.sort_by(|a, b| a.name.cmp(&b.name));
+    unlocked.sort();
+
+    let in_sync = missing.is_empty() && version_mismatches.is_empty() && unlocked.is_empty();
+    if !in_sync {
+        warn!(
+            "[PYTHON_LOCK] Env drifted from {}: {} missing, {} version mismatches, {} unlocked.",
+            lockfile.display(), missing.len(), version_mismatches.len(), unlocked.len()
+        );
+    }
+    Ok(LockDriftReport { lockfile: lockfile.display().to_string(), in_sync, missing, version_mismatches, unlocked })
+}
+
+/// Python with a working pip to download distributions with. A venv made by `uv venv` has no pip,
+/// but the bundled interpreter is the same version and platform.
+async fn downloader_python(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    match selected_backend(app_handle) {
+        EnvBackendKind::Conda => get_conda_env_python_executable_path(app_handle, COMFYUI_ENV_NAME).await,
+        EnvBackendKind::Venv => find_bundled_python(app_handle),
+    }
+}
+
+/// Builds a hash-pinned lockfile from the currently installed env, which should be a known-good
+/// setup. Each frozen package is downloaded for this platform and its distribution hashed.
+#[tauri::command]
+pub async fn regenerate_python_lockfile(app_handle: AppHandle<Wry>, output_path: Option<String>) -> Result<LockRegenerationReport, String> {
+    let lockfile = match output_path {
+        Some(p) if !p.trim().is_empty() => PathBuf::from(p),
+        _ => get_lockfile_path(&app_handle)?,
+    };
+    info!("[PYTHON_LOCK] Regenerating lockfile {} from the installed env...", lockfile.display());
+
+    let (pinned, skipped) = parse_freeze(&pip_freeze(&app_handle).await?);
+    for line in &skipped {
+        warn!("[PYTHON_LOCK] Cannot pin '{}' by hash; leaving it out of the lock.", line);
+    }
+
+    let python = downloader_python(&app_handle).await?;
+    let torch_index = resolve_torch_for_host().variant.pip_index_url();
+    let download_root = std::env::temp_dir().join(format!("metamorphosis-lock-{}", uuid::Uuid::new_v4()));
+
+    let mut lines = vec![
+        format!("# Generated by Metamorphosis {} on {}", app_handle.package_info().version, chrono::Utc::now().to_rfc3339()),
+        format!("# Platform lock: {}", lockfile_name(&app_handle)),
+    ];
+    let mut result: Result<(), String> = Ok(());
+    for (name, version) in &pinned {
+        let dest = download_root.join(normalize_name(name));
+        let dest_str = dest.to_string_lossy().to_string();
+        let requirement = format!("{}=={}", name, version);
+        let mut args = vec!["-m", "pip", "download", "--no-deps", "--quiet", "--dest", &dest_str, &requirement];
+        if let Some(torch_index) = torch_index.as_deref().filter(|_| TORCH_INDEX_PACKAGES.contains(&normalize_name(name).as_str())) {
+            args.push("--extra-index-url");
+            args.push(torch_index);
+        }
+        if let Err(e) = execute_command_to_string(&python, &args, None).await {
+            result = Err(format!("Failed to download {} for hashing: {}", requirement, e));
+            break;
+        }
+        let mut hashes = Vec::new();
+        for entry in fs::read_dir(&dest).map_err(|e| format!("Failed to read {}: {}", dest.display(), e))?.filter_map(|e| e.ok()) {
+            hashes.push(format!("--hash=sha256:{}", sha256_file(&entry.path())?));
+        }
+        hashes.sort();
+        lines.push(format!("{} \\\n    {}", requirement, hashes.join(" \\\n    ")));
+    }
+    let _ = fs::remove_dir_all(&download_root);
+    result?;
+
+    if let Some(parent) = lockfile.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&lockfile, lines.join("\n") + "\n").map_err(|e| format!("Failed to write lockfile {}: {}", lockfile.display(), e))?;
+    info!("[PYTHON_LOCK] Wrote {} packages to {}", pinned.len(), lockfile.display());
+
+    Ok(LockRegenerationReport { lockfile: lockfile.display().to_string(), locked_packages: pinned.len(), skipped })
+}
+
+#[tauri::command]
+pub async fn check_python_lock_drift(app_handle: AppHandle<Wry>) -> Result<LockDriftReport, String> {
+    compute_lock_drift(&app_handle).await
+}
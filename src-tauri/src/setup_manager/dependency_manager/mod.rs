@@ -1,7 +1,12 @@
 // metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/mod.rs
 pub mod command_runner;
+pub mod install_progress;
 pub mod disk_utils;
 pub mod python_env;
+pub mod venv_env;
+pub mod lockfile;
+pub mod torch_resolver;
+pub mod requirement_conflicts;
 
 // Re-export the public API that was previously in the old dependency_management.rs
 pub use self::python_env::{
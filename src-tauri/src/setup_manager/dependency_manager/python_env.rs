@@ -6,6 +6,7 @@ use fs2::available_space; // For disk space check
 use tokio::time::{sleep, Duration}; // Added for retry mechanism
 
 use crate::gpu_detection::{GpuType, get_gpu_info};
+use crate::setup_manager::setup_journal;
 use crate::setup; // For emit_setup_progress
 use crate::setup_manager::python_utils::{
     get_comfyui_directory_path,
@@ -17,6 +18,10 @@ use crate::setup_manager::python_utils::{
 use super::command_runner::run_command_for_setup_progress;
 use crate::setup_manager::python_utils::execute_command_to_string;
 use super::disk_utils::REQUIRED_DISK_SPACE;
+use super::venv_env::install_venv_dependencies_with_progress;
+use super::lockfile::requirements_install_args;
+use super::torch_resolver::resolve_torch_for_host;
+use crate::setup_manager::env_backend::{selected_backend, torch_install_command, EnvBackendKind};
 
 
 // New function for SetupScreen with detailed progress
@@ -104,6 +109,15 @@ pub async fn install_python_dependencies_with_progress(app_handle: &AppHandle<Wr
 
     info!("Python dependencies need installation or verification. Starting process...");
 
+    if selected_backend(app_handle) == EnvBackendKind::Venv {
+        info!("Using the venv environment backend with the bundled Python interpreter.");
+        install_venv_dependencies_with_progress(app_handle, phase_name, &comfyui_dir, current_phase_progress).await?;
+        setup::emit_setup_progress(app_handle, phase_name, "Python environment setup complete.", 100, None, None);
+        fs::write(&internal_deps_marker_path, "installed_via_setup_screen_flow").map_err(|e| format!("Failed to write internal dependency marker: {}", e))?;
+        info!("Created internal dependency marker: {}", internal_deps_marker_path.display());
+        return Ok(());
+    }
+
     // Determine the path to the conda executable
     let conda_executable = crate::setup_manager::python_utils::get_conda_executable_path(app_handle).await?;
 
@@ -160,55 +174,12 @@ pub async fn install_python_dependencies_with_progress(app_handle: &AppHandle<Wr
         return Err(err_msg);
     }
 
-    info!("Attempting to install PyTorch, Torchvision, and Torchaudio via Conda...");
-    let gpu_info_for_torch_step = get_gpu_info();
-    let mut conda_torch_args: Vec<String> = vec![
-        "install".to_string(),
-        "-n".to_string(),
-        env_name.to_string(),
-        "pytorch".to_string(),
-        "torchvision".to_string(),
-        "torchaudio".to_string(),
-    ];
-    info!("Executing command: {} {}", conda_executable.display(), conda_torch_args.join(" "));
-
-    if gpu_info_for_torch_step.gpu_type == GpuType::Nvidia {
-        if let Some(cuda_ver_str_ref) = gpu_info_for_torch_step.cuda_version.as_deref() {
-            if !cuda_ver_str_ref.is_empty() {
-                let cuda_ver_str = cuda_ver_str_ref.to_string();
-                info!("Detected NVIDIA GPU with CUDA version string: {}", cuda_ver_str);
-                let cuda_package_suffix = if cuda_ver_str.starts_with("12.") {
-                    "pytorch-cuda=12.4".to_string() // Align with the expected 12.4 DLL and 12.9 driver
-                } else if cuda_ver_str.starts_with("11.") {
-                    format!("pytorch-cuda={}", cuda_ver_str)
-                } else {
-                    error!("Unsupported NVIDIA CUDA version prefix: {}. Falling back to CPU PyTorch.", cuda_ver_str);
-                    "cpuonly".to_string()
-                };
-                conda_torch_args.push(cuda_package_suffix);
-                conda_torch_args.push("-c".to_string());
-                conda_torch_args.push("pytorch".to_string());
-                conda_torch_args.push("-c".to_string());
-                conda_torch_args.push("nvidia".to_string());
-            } else {
-                error!("NVIDIA GPU detected, but CUDA version string is empty. Falling back to CPU PyTorch.");
-                conda_torch_args.push("cpuonly".to_string());
-                conda_torch_args.push("-c".to_string());
-                conda_torch_args.push("pytorch".to_string());
-            }
-        } else {
-            error!("NVIDIA GPU detected, but no CUDA version found. Falling back to CPU PyTorch.");
-            conda_torch_args.push("cpuonly".to_string());
-            conda_torch_args.push("-c".to_string());
-            conda_torch_args.push("pytorch".to_string());
-        }
-    } else {
-        info!("Non-NVIDIA GPU detected ({:?}). Using CPU PyTorch.", gpu_info_for_torch_step.gpu_type);
-        conda_torch_args.push("cpuonly".to_string());
-        conda_torch_args.push("-c".to_string());
-        conda_torch_args.push("pytorch".to_string());
-    }
-    conda_torch_args.push("-y".to_string()); // Auto-approve
+    info!("Attempting to install PyTorch, Torchvision, and Torchaudio...");
+    let torch_resolution = resolve_torch_for_host();
+    setup_journal::record_note(app_handle, "torch_variant", torch_resolution.variant.tag());
+    setup_journal::record_note(app_handle, "torch_variant_reason", &torch_resolution.reason);
+    let (torch_installer, conda_torch_args) = torch_install_command(app_handle, torch_resolution.variant, false).await?;
+    info!("Executing command: {} {}", torch_installer.display(), conda_torch_args.join(" "));
 
     let conda_torch_args_refs: Vec<&str> = conda_torch_args.iter().map(|s| s.as_str()).collect();
     
@@ -223,7 +194,7 @@ pub async fn install_python_dependencies_with_progress(app_handle: &AppHandle<Wr
 
         match run_command_for_setup_progress(
             app_handle, phase_name, "Installing PyTorch, Torchvision, Torchaudio", current_phase_progress, 30,
-            &conda_executable, &conda_torch_args_refs,
+            &torch_installer, &conda_torch_args_refs,
             &comfyui_dir,
             "Starting PyTorch installation...", "PyTorch, Torchvision, Torchaudio installed."
         ).await {
@@ -326,9 +297,8 @@ pub async fn install_python_dependencies_with_progress(app_handle: &AppHandle<Wr
         "install".to_string(),
         "-vvv".to_string(),
         "--no-cache-dir".to_string(),
-        "-r".to_string(),
-        requirements_path.to_str().ok_or_else(|| "Failed to convert requirements_path to string".to_string())?.to_string(),
     ];
+    conda_run_args.extend(requirements_install_args(app_handle, &requirements_path));
 
     // Conditionally add --extra-index-url for onnxruntime-gpu if CUDA 12.x is detected
     let gpu_info_for_pip_step = get_gpu_info();
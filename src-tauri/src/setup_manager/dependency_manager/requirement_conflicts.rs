@@ -0,0 +1,536 @@
+// metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/requirement_conflicts.rs
+//
+// Detects incompatible Python requirements across ComfyUI and its custom nodes. Each node's
+// requirements.txt is installed on its own, so a later node can silently downgrade a package an
+// earlier one needs, or pull `opencv-python` next to `opencv-python-headless` (both ship the `cv2`
+// module and overwrite each other's files). Before a node's requirements are installed, all the
+// requirement files involved are parsed into specifiers and checked against each other.
+//
+// The team can ship `resources/python-constraints.txt` next to the python-locks. Its pins are
+// checked like any other requirement file, reported as constraint violations, and passed to pip
+// with `-c` so custom node installs cannot move those packages.
+//
+// Version handling is a PEP 440 subset: release segments, pre/dev suffixes ordering below the
+// release, post releases ordering above it (by post number), `.*` wildcards and `~=`. Pre-release
+// numbers, local versions and epochs are ignored, so `1.0a1` and `1.0rc2` compare equal.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::setup_manager::orchestration::get_app_root_path;
+use crate::setup_manager::python_utils::get_comfyui_directory_path;
+use super::lockfile::normalize_name;
+
+const CONSTRAINTS_FILE: &str = "resources/python-constraints.txt";
+// The bundler puts resources from outside src-tauri (`../resources/...`) under `_up_`.
+const BUNDLED_CONSTRAINTS_FILE: &str = "_up_/resources/python-constraints.txt";
+const COMFYUI_SOURCE: &str = "ComfyUI";
+const CONSTRAINTS_SOURCE: &str = "constraints";
+
+/// Distributions that install the same import package and must not be installed side by side.
+const EXCLUSIVE_PACKAGE_GROUPS: &[&[&str]] = &[
+    &["opencv-python", "opencv-python-headless", "opencv-contrib-python", "opencv-contrib-python-headless"],
+    &["onnxruntime", "onnxruntime-gpu", "onnxruntime-directml"],
+];
+
+/// Where a version sits relative to its release segments: `1.0rc1` < `1.0` < `1.0.post1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Phase {
+    Pre, // a/b/rc/dev suffix
+    Release,
+    Post(u64),
+}
+
+#[derive(Debug, Clone)]
+struct Version {
+    release: Vec<u64>,
+    phase: Phase,
+}
+
+impl Version {
+    fn parse(text: &str) -> Option<Version> {
+        let text = text.trim().trim_start_matches('v');
+        let text = text.split('+').next()?; // Drop local version
+        let mut release = Vec::new();
+        let mut phase = Phase::Release;
+        for part in text.split('.') {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            // "1.0.post1", "1.0.dev0" (own segment) or "1.0rc1", "1.0post1" (attached suffix)
+            let suffix = if digits.is_empty() { part } else { &part[digits.len()..] };
+            if !digits.is_empty() {
+                release.push(digits.parse().ok()?);
+            }
+            if !suffix.is_empty() {
+                phase = Self::parse_phase(suffix.trim_start_matches(['-', '_']));
+                break;
+            }
+        }
+        (!release.is_empty()).then_some(Version { release, phase })
+    }
+
+    fn parse_phase(suffix: &str) -> Phase {
+        match suffix.strip_prefix("post") {
+            Some(number) => Phase::Post(number.trim_start_matches(['-', '_', '.']).parse().unwrap_or(0)),
+            None => Phase::Pre,
+        }
+    }
+
+    fn release(release: Vec<u64>) -> Version {
+        Version { release, phase: Phase::Release }
+    }
+
+    fn segment(&self, i: usize) -> u64 {
+        self.release.get(i).copied().unwrap_or(0)
+    }
+
+    /// Smallest version after `self` that can be told apart from it.
+    fn just_above(&self) -> Version {
+        let mut release = self.release.clone();
+        release.extend([0, 0, 0, 1]);
+        Version::release(release)
+    }
+
+    /// A version just below `self`, if there is one.
+    fn just_below(&self) -> Option<Version> {
+        let last_non_zero = self.release.iter().rposition(|&s| s > 0)?;
+        let mut release = self.release[..=last_non_zero].to_vec();
+        release[last_non_zero] -= 1;
+        release.push(999_999);
+        Some(Version::release(release))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.release.len().max(other.release.len());
+        (0..len)
+            .map(|i| self.segment(i).cmp(&other.segment(i)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| self.phase.cmp(&other.phase))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Specifier {
+    Eq(Version),
+    NotEq(Version),
+    Lt(Version),
+    Le(Version),
+    Gt(Version),
+    Ge(Version),
+    /// `==1.2.*`: same leading segments.
+    Prefix(Vec<u64>),
+    NotPrefix(Vec<u64>),
+}
+
+impl Specifier {
+    fn matches(&self, v: &Version) -> bool {
+        let has_prefix = |prefix: &Vec<u64>| prefix.iter().enumerate().all(|(i, s)| v.segment(i) == *s);
+        match self {
+            Specifier::Eq(x) => v == x,
+            Specifier::NotEq(x) => v != x,
+            Specifier::Lt(x) => v < x,
+            Specifier::Le(x) => v <= x,
+            Specifier::Gt(x) => v > x,
+            Specifier::Ge(x) => v >= x,
+            Specifier::Prefix(p) => has_prefix(p),
+            Specifier::NotPrefix(p) => !has_prefix(p),
+        }
+    }
+
+    /// Versions on and around this specifier's boundary; if the specifiers of a package can all
+    /// be met, at least one of these candidates meets them.
+    fn boundary_candidates(&self) -> Vec<Version> {
+        let bound = match self {
+            Specifier::Eq(x) | Specifier::NotEq(x) | Specifier::Lt(x) | Specifier::Le(x) | Specifier::Gt(x) | Specifier::Ge(x) => x.clone(),
+            Specifier::Prefix(p) | Specifier::NotPrefix(p) => Version::release(p.clone()),
+        };
+        let mut candidates = vec![bound.just_above()];
+        candidates.extend(bound.just_below());
+        if let Specifier::Prefix(p) | Specifier::NotPrefix(p) = self {
+            // Just past the wildcard range: 1.2.* -> 1.3
+            let mut next = p.clone();
+            if let Some(last) = next.last_mut() {
+                *last += 1;
+            }
+            candidates.push(Version::release(next));
+        }
+        candidates.push(bound);
+        candidates
+    }
+}
+
+/// Parses `>=1.25,<2`, `==4.8.*`, `~=1.4.2`. Unparseable clauses are skipped.
+fn parse_specifiers(text: &str) -> Vec<Specifier> {
+    let mut specs = Vec::new();
+    for clause in text.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let op_len = clause.find(|c: char| !"<>=!~".contains(c)).unwrap_or(clause.len());
+        let (op, value) = (&clause[..op_len], clause[op_len..].trim());
+        if let Some(prefix) = value.strip_suffix(".*") {
+            let Some(v) = Version::parse(prefix) else { continue };
+            match op {
+                "==" => specs.push(Specifier::Prefix(v.release)),
+                "!=" => specs.push(Specifier::NotPrefix(v.release)),
+                _ => {}
+            }
+            continue;
+        }
+        let Some(v) = Version::parse(value) else { continue };
+        match op {
+            "==" | "===" => specs.push(Specifier::Eq(v)),
+            "!=" => specs.push(Specifier::NotEq(v)),
+            "<" => specs.push(Specifier::Lt(v)),
+            "<=" => specs.push(Specifier::Le(v)),
+            ">" => specs.push(Specifier::Gt(v)),
+            ">=" => specs.push(Specifier::Ge(v)),
+            "~=" if v.release.len() >= 2 => {
+                // ~=1.4.2 means >=1.4.2, ==1.4.*
+                specs.push(Specifier::Prefix(v.release[..v.release.len() - 1].to_vec()));
+                specs.push(Specifier::Ge(v));
+            }
+            _ => warn!("[REQUIREMENT_CONFLICTS] Ignoring unsupported version clause '{}'", clause),
+        }
+    }
+    specs
+}
+
+/// Evaluates the platform part of an environment marker (`sys_platform`, `platform_system`,
+/// `os_name` with `==`/`!=`, joined by `and`/`or`). Anything else counts as true, so a
+/// requirement is only dropped when it clearly targets another platform.
+fn marker_applies(marker: &str) -> bool {
+    let value_of = |variable: &str| -> Option<&'static str> {
+        match variable {
+            "sys_platform" => Some(match std::env::consts::OS { "windows" => "win32", "macos" => "darwin", other => other }),
+            "platform_system" => Some(match std::env::consts::OS { "windows" => "Windows", "macos" => "Darwin", "linux" => "Linux", other => other }),
+            "os_name" => Some(if cfg!(windows) { "nt" } else { "posix" }),
+            _ => None,
+        }
+    };
+    let comparison_applies = |comparison: &str| -> bool {
+        let (variable, expected, negate) = match (comparison.split_once("!="), comparison.split_once("==")) {
+            (Some((l, r)), _) => (l, r, true),
+            (None, Some((l, r))) => (l, r, false),
+            _ => return true,
+        };
+        let expected = expected.trim().trim_matches(|c: char| c == '"' || c == '\'');
+        match value_of(variable.trim()) {
+            Some(actual) => (actual == expected) != negate,
+            None => true,
+        }
+    };
+    marker
+        .split(" or ")
+        .any(|alternative| alternative.split(" and ").all(|c| comparison_applies(c.trim().trim_matches(|c: char| c == '(' || c == ')'))))
+}
+
+/// One requirement line, e.g. `numpy>=1.25,<2` from ComfyUI-Impact-Pack.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedRequirement {
+    pub name: String, // Normalized
+    pub requirement: String,
+    pub source: String,
+    #[serde(skip)]
+    specifiers: Vec<Specifier>,
+}
+
+/// Parses a requirements file. Options (`-r`, `--extra-index-url`), URL and VCS requirements,
+/// and lines whose marker excludes this platform are skipped.
+pub fn parse_requirements(contents: &str, source: &str) -> Vec<ParsedRequirement> {
+    let joined = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+    let mut requirements = Vec::new();
+    for line in joined.lines() {
+        let line = line.split(" #").next().unwrap_or("").trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') || line.contains("://") {
+            continue;
+        }
+        let (spec, marker) = match line.split_once(';') {
+            Some((spec, marker)) => (spec.trim(), Some(marker.trim())),
+            None => (line, None),
+        };
+        if marker.is_some_and(|m| !marker_applies(m)) {
+            continue;
+        }
+        let name_end = spec.find(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c))).unwrap_or(spec.len());
+        let name = &spec[..name_end];
+        if name.is_empty() {
+            continue;
+        }
+        // Drop extras: `package[extra]>=1`
+        let version_part = match spec[name_end..].trim_start().strip_prefix('[') {
+            Some(rest) => rest.split_once(']').map(|(_, v)| v).unwrap_or(""),
+            None => &spec[name_end..],
+        };
+        requirements.push(ParsedRequirement {
+            name: normalize_name(name),
+            requirement: spec.to_string(),
+            source: source.to_string(),
+            specifiers: parse_specifiers(version_part.trim().trim_matches(|c: char| c == '(' || c == ')')),
+        });
+    }
+    requirements
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    /// No version satisfies every requirement on the package.
+    IncompatibleVersions,
+    /// Packages that overwrite each other's files are requested by different sources.
+    MutuallyExclusivePackages,
+    /// A requirement is incompatible with the team constraints file.
+    ConstraintViolation,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementConflict {
+    pub kind: ConflictKind,
+    pub package: String,
+    pub requirements: Vec<ParsedRequirement>,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementConflictReport {
+    pub sources: Vec<String>,
+    pub constraints_file: Option<String>,
+    pub conflicts: Vec<RequirementConflict>,
+}
+
+fn satisfiable(requirements: &[&ParsedRequirement]) -> bool {
+    let specifiers: Vec<&Specifier> = requirements.iter().flat_map(|r| r.specifiers.iter()).collect();
+    if specifiers.is_empty() {
+        return true;
+    }
+    let mut candidates: Vec<Version> = specifiers.iter().flat_map(|s| s.boundary_candidates()).collect();
+    candidates.push(Version::release(vec![0]));
+    candidates.push(Version::release(vec![999_999]));
+    candidates.iter().any(|v| specifiers.iter().all(|s| s.matches(v)))
+}
+
+fn describe(requirements: &[&ParsedRequirement]) -> String {
+    requirements.iter().map(|r| format!("{} ({})", r.requirement, r.source)).collect::<Vec<_>>().join(", ")
+}
+
+/// Finds conflicts among `requirements`. When `focus_source` is set, only conflicts involving
+/// that source are reported (the others existed before and were reported then).
+pub fn detect_conflicts(requirements: &[ParsedRequirement], focus_source: Option<&str>) -> Vec<RequirementConflict> {
+    let involves_focus = |reqs: &[&ParsedRequirement]| focus_source.map_or(true, |f| reqs.iter().any(|r| r.source == f));
+    let mut by_package: BTreeMap<&str, Vec<&ParsedRequirement>> = BTreeMap::new();
+    for requirement in requirements {
+        by_package.entry(requirement.name.as_str()).or_default().push(requirement);
+    }
+
+    let mut conflicts = Vec::new();
+    for (package, reqs) in &by_package {
+        if reqs.len() < 2 || satisfiable(reqs) || !involves_focus(reqs) {
+            continue;
+        }
+        let (constraints, others): (Vec<&ParsedRequirement>, Vec<&ParsedRequirement>) = reqs.iter().partition(|r| r.source == CONSTRAINTS_SOURCE);
+        let violates_constraints = !constraints.is_empty() && satisfiable(&others);
+        conflicts.push(RequirementConflict {
+            kind: if violates_constraints { ConflictKind::ConstraintViolation } else { ConflictKind::IncompatibleVersions },
+            package: package.to_string(),
+            requirements: reqs.iter().map(|r| (*r).clone()).collect(),
+            message: if violates_constraints {
+                format!("{} is pinned by the team constraints ({}) but required as {}", package, describe(&constraints), describe(&others))
+            } else {
+                format!("No version of {} satisfies {}", package, describe(reqs))
+            },
+        });
+    }
+
+    for group in EXCLUSIVE_PACKAGE_GROUPS {
+        let present: Vec<&ParsedRequirement> = group.iter().filter_map(|p| by_package.get(p)).flatten().copied().collect();
+        let distinct = present.iter().map(|r| r.name.as_str()).collect::<BTreeSet<_>>();
+        if distinct.len() < 2 || !involves_focus(&present) {
+            continue;
+        }
+        conflicts.push(RequirementConflict {
+            kind: ConflictKind::MutuallyExclusivePackages,
+            package: distinct.iter().copied().collect::<Vec<_>>().join(" / "),
+            requirements: present.iter().map(|r| (*r).clone()).collect(),
+            message: format!("{} install the same module and overwrite each other: {}", distinct.iter().copied().collect::<Vec<_>>().join(" and "), describe(&present)),
+        });
+    }
+    conflicts
+}
+
+/// The team constraints file: the source tree in debug builds, the bundled resources in release
+/// builds. `None` when it isn't there.
+pub fn constraints_file_path(app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    let path = if cfg!(debug_assertions) {
+        get_app_root_path().ok()?.join(CONSTRAINTS_FILE)
+    } else {
+        app_handle.path().resource_dir().ok()?.join(BUNDLED_CONSTRAINTS_FILE)
+    };
+    path.is_file().then_some(path)
+}
+
+fn read_requirements_file(path: &Path, source: &str) -> Vec<ParsedRequirement> {
+    match fs::read_to_string(path) {
+        Ok(contents) => parse_requirements(&contents, source),
+        Err(e) => {
+            warn!("[REQUIREMENT_CONFLICTS] Could not read {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// Checks ComfyUI's requirements, every installed custom node's requirements.txt, the team
+/// constraints and, if given, `(name, requirements.txt)` of a node about to be installed.
+/// With a pending node, only conflicts that node would introduce are reported.
+pub fn check_requirement_conflicts_for(app_handle: &AppHandle<Wry>, pending: Option<(&str, &Path)>) -> Result<RequirementConflictReport, String> {
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+    let mut report = RequirementConflictReport::default();
+    let mut requirements = Vec::new();
+
+    let comfyui_requirements = comfyui_dir.join("requirements.txt");
+    if comfyui_requirements.is_file() {
+        requirements.extend(read_requirements_file(&comfyui_requirements, COMFYUI_SOURCE));
+        report.sources.push(COMFYUI_SOURCE.to_string());
+    }
+    if let Ok(entries) = fs::read_dir(comfyui_dir.join("custom_nodes")) {
+        let mut node_dirs: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect();
+        node_dirs.sort();
+        for node_dir in node_dirs {
+            let name = node_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let requirements_path = node_dir.join("requirements.txt");
+            if pending.is_some_and(|(pending_name, _)| pending_name == name) || !requirements_path.is_file() {
+                continue;
+            }
+            requirements.extend(read_requirements_file(&requirements_path, &name));
+            report.sources.push(name);
+        }
+    }
+    if let Some((name, path)) = pending {
+        requirements.extend(read_requirements_file(path, name));
+        report.sources.push(name.to_string());
+    }
+    if let Some(constraints) = constraints_file_path(app_handle) {
+        requirements.extend(read_requirements_file(&constraints, CONSTRAINTS_SOURCE));
+        report.constraints_file = Some(constraints.display().to_string());
+    }
+
+    report.conflicts = detect_conflicts(&requirements, pending.map(|(name, _)| name));
+    for conflict in &report.conflicts {
+        warn!("[REQUIREMENT_CONFLICTS] {:?}: {}", conflict.kind, conflict.message);
+    }
+    info!("[REQUIREMENT_CONFLICTS] Checked {} requirement sources, {} conflicts.", report.sources.len(), report.conflicts.len());
+    Ok(report)
+}
+
+/// Conflicts between the requirements of ComfyUI and all installed custom nodes.
+#[tauri::command]
+pub async fn check_requirement_conflicts(app_handle: AppHandle<Wry>) -> Result<RequirementConflictReport, String> {
+    check_requirement_conflicts_for(&app_handle, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap_or_else(|| panic!("unparseable version {}", text))
+    }
+
+    #[test]
+    fn versions_order_like_pep_440() {
+        let ordered = ["0.9.post1", "1.0rc1", "1.0", "1.0.post1", "1.0.post2", "1.0.1", "1.1", "2"];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} should be below {}", pair[0], pair[1]);
+        }
+        assert!(v("1.0.dev0") < v("1.0"));
+        // Documented limitation: pre-release kinds and numbers are not told apart.
+        assert_eq!(v("1.0.dev0"), v("1.0rc2"));
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert_eq!(v("v1.0+cu124"), v("1.0"));
+        assert_eq!(v("1.0-post1"), v("1.0.post1"));
+        assert!(Version::parse("latest").is_none());
+    }
+
+    #[test]
+    fn parses_specifier_clauses() {
+        let cases: &[(&str, Vec<Specifier>)] = &[
+            (">=1.25,<2", vec![Specifier::Ge(v("1.25")), Specifier::Lt(v("2"))]),
+            ("==4.8.*", vec![Specifier::Prefix(vec![4, 8])]),
+            ("!=1.5.*", vec![Specifier::NotPrefix(vec![1, 5])]),
+            ("~=1.4.2", vec![Specifier::Prefix(vec![1, 4]), Specifier::Ge(v("1.4.2"))]),
+            ("== 2.1.0 , != 2.1.1", vec![Specifier::Eq(v("2.1.0")), Specifier::NotEq(v("2.1.1"))]),
+            (">=1.0.post1", vec![Specifier::Ge(v("1.0.post1"))]),
+            ("", vec![]),
+            (">=abc", vec![]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(&parse_specifiers(text), expected, "specifiers of '{}'", text);
+        }
+    }
+
+    // (source, requirements.txt contents) pairs, and the (kind, package) of each expected conflict
+    type ConflictCase<'a> = (&'a [(&'a str, &'a str)], Vec<(ConflictKind, &'a str)>);
+
+    fn conflicts(files: &[(&str, &str)]) -> Vec<RequirementConflict> {
+        let requirements: Vec<ParsedRequirement> = files.iter().flat_map(|(source, contents)| parse_requirements(contents, source)).collect();
+        detect_conflicts(&requirements, None)
+    }
+
+    #[test]
+    fn detects_version_and_package_conflicts() {
+        let cases: &[ConflictCase] = &[
+            // Conflicting numpy ranges
+            (&[(COMFYUI_SOURCE, "numpy>=1.25.0"), ("ComfyUI-Impact-Pack", "numpy<1.24")], vec![(ConflictKind::IncompatibleVersions, "numpy")]),
+            (&[(COMFYUI_SOURCE, "numpy>=1.25.0"), ("ComfyUI_ControlNet_Aux", "numpy<2")], vec![]),
+            (&[(COMFYUI_SOURCE, "numpy==1.26.*"), ("ComfyUI-RMBG", "numpy>=2.0")], vec![(ConflictKind::IncompatibleVersions, "numpy")]),
+            // opencv-python next to opencv-python-headless
+            (
+                &[("ComfyUI-Impact-Pack", "opencv-python-headless>=4.7"), ("ComfyUI_ControlNet_Aux", "opencv-python")],
+                vec![(ConflictKind::MutuallyExclusivePackages, "opencv-python / opencv-python-headless")],
+            ),
+            (&[("ComfyUI-Impact-Pack", "opencv-python>=4.7"), ("ComfyUI_ControlNet_Aux", "opencv_python<5")], vec![]),
+            // Post releases sort above the release
+            (&[("a", "foo>=1.0.post1"), ("b", "foo<=1.0")], vec![(ConflictKind::IncompatibleVersions, "foo")]),
+            (&[("a", "foo>=1.0.post1"), ("b", "foo<1.1")], vec![]),
+            // Pre-releases sort below it
+            (&[("a", "foo>=1.0"), ("b", "foo<=1.0rc1")], vec![(ConflictKind::IncompatibleVersions, "foo")]),
+            (&[(CONSTRAINTS_SOURCE, "numpy<2"), ("ComfyUI-RMBG", "numpy>=2.0")], vec![(ConflictKind::ConstraintViolation, "numpy")]),
+            // Markers for another platform drop the requirement
+            (&[("a", "foo>=2"), ("b", "foo<1; sys_platform == 'no-such-platform'")], vec![]),
+        ];
+        for (files, expected) in cases {
+            let found: Vec<(ConflictKind, String)> = conflicts(files).into_iter().map(|c| (c.kind, c.package)).collect();
+            let expected: Vec<(ConflictKind, String)> = expected.iter().map(|(k, p)| (*k, p.to_string())).collect();
+            assert_eq!(found, expected, "conflicts in {:?}", files);
+        }
+    }
+
+    #[test]
+    fn focus_source_reports_only_its_own_conflicts() {
+        let requirements: Vec<ParsedRequirement> = [(COMFYUI_SOURCE, "numpy>=1.25"), ("old-node", "numpy<1.24"), ("new-node", "torch>=2")]
+            .iter()
+            .flat_map(|(source, contents)| parse_requirements(contents, source))
+            .collect();
+        assert!(detect_conflicts(&requirements, Some("new-node")).is_empty());
+        assert_eq!(detect_conflicts(&requirements, Some("old-node")).len(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+// metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/torch_resolver.rs
+//
+// Decides which PyTorch build to install for the detected GPU, driver and platform.
+// The decision is a first-match walk over `TORCH_RULES`, so supporting a new build means adding a
+// row rather than another branch. `resolve_torch_variant` is a pure function of its inputs; the
+// detection side lives in `resolve_torch_for_host`.
+
+use log::info;
+use serde::Serialize;
+
+use crate::gpu_detection::{get_gpu_info, GpuType};
+
+const PYTORCH_WHEEL_INDEX_BASE: &str = "https://download.pytorch.org/whl";
+const TORCH_PACKAGES: &[&str] = &["torch", "torchvision", "torchaudio"];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TorchVariant {
+    Cuda124,
+    Cuda121,
+    Cuda118,
+    Rocm62,
+    Xpu,
+    Mps,
+    Cpu,
+}
+
+impl TorchVariant {
+    /// Short name used in wheel index URLs, lockfile names and the setup journal.
+    pub fn tag(self) -> &'static str {
+        match self {
+            TorchVariant::Cuda124 => "cu124",
+            TorchVariant::Cuda121 => "cu121",
+            TorchVariant::Cuda118 => "cu118",
+            TorchVariant::Rocm62 => "rocm6.2",
+            TorchVariant::Xpu => "xpu",
+            TorchVariant::Mps => "mps",
+            TorchVariant::Cpu => "cpu",
+        }
+    }
+
+    /// Wheel index to install torch from with pip. MPS builds are the default wheels on PyPI.
+    pub fn pip_index_url(self) -> Option<String> {
+        match self {
+            TorchVariant::Mps => None,
+            other => Some(format!("{}/{}", PYTORCH_WHEEL_INDEX_BASE, other.tag())),
+        }
+    }
+
+    /// Packages and index arguments for `pip install`.
+    pub fn pip_install_args(self) -> Vec<String> {
+        let mut args: Vec<String> = TORCH_PACKAGES.iter().map(|s| s.to_string()).collect();
+        if let Some(index_url) = self.pip_index_url() {
+            args.push("--index-url".to_string());
+            args.push(index_url);
+        }
+        args
+    }
+
+    /// `--extra-index-url` arguments for installing a requirements list that pins this build.
+    /// Local-version pins such as torch==2.5.1+cu124 only resolve against the PyTorch index.
+    pub fn pip_extra_index_args(self) -> Vec<String> {
+        match self.pip_index_url() {
+            Some(index_url) => vec!["--extra-index-url".to_string(), index_url],
+            None => Vec::new(),
+        }
+    }
+
+    /// Extra package spec and channels for `conda install pytorch torchvision torchaudio`.
+    /// `None` means the pytorch conda channel has no such build and pip must be used instead.
+    pub fn conda_install_args(self) -> Option<Vec<&'static str>> {
+        match self {
+            TorchVariant::Cuda124 => Some(vec!["pytorch-cuda=12.4", "-c", "pytorch", "-c", "nvidia"]),
+            TorchVariant::Cuda121 => Some(vec!["pytorch-cuda=12.1", "-c", "pytorch", "-c", "nvidia"]),
+            TorchVariant::Cuda118 => Some(vec!["pytorch-cuda=11.8", "-c", "pytorch", "-c", "nvidia"]),
+            TorchVariant::Mps => Some(vec!["-c", "pytorch"]),
+            TorchVariant::Cpu => Some(vec!["cpuonly", "-c", "pytorch"]),
+            TorchVariant::Rocm62 | TorchVariant::Xpu => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TorchResolution {
+    pub variant: TorchVariant,
+    pub reason: String,
+}
+
+/// One row of the resolver table. `None` fields match anything.
+struct TorchRule {
+    gpu_type: Option<GpuType>,
+    os: Option<&'static str>,
+    arch: Option<&'static str>,
+    // Lowest CUDA version the driver must support (as reported by nvidia-smi) for this build.
+    min_driver_cuda: Option<(u32, u32)>,
+    variant: TorchVariant,
+}
+
+const TORCH_RULES: &[TorchRule] = &[
+    // Apple Silicon: Metal (MPS) whatever the reported GPU name.
+    TorchRule { gpu_type: None, os: Some("macos"), arch: Some("aarch64"), min_driver_cuda: None, variant: TorchVariant::Mps },
+    // NVIDIA: newest CUDA build the driver can run.
+    TorchRule { gpu_type: Some(GpuType::Nvidia), os: None, arch: None, min_driver_cuda: Some((12, 4)), variant: TorchVariant::Cuda124 },
+    TorchRule { gpu_type: Some(GpuType::Nvidia), os: None, arch: None, min_driver_cuda: Some((12, 1)), variant: TorchVariant::Cuda121 },
+    TorchRule { gpu_type: Some(GpuType::Nvidia), os: None, arch: None, min_driver_cuda: Some((11, 8)), variant: TorchVariant::Cuda118 },
+    // AMD: ROCm wheels only exist for Linux.
+    TorchRule { gpu_type: Some(GpuType::Amd), os: Some("linux"), arch: Some("x86_64"), min_driver_cuda: None, variant: TorchVariant::Rocm62 },
+    // Intel: XPU wheels for Windows and Linux.
+    TorchRule { gpu_type: Some(GpuType::Intel), os: Some("windows"), arch: Some("x86_64"), min_driver_cuda: None, variant: TorchVariant::Xpu },
+    TorchRule { gpu_type: Some(GpuType::Intel), os: Some("linux"), arch: Some("x86_64"), min_driver_cuda: None, variant: TorchVariant::Xpu },
+];
+
+/// Parses a CUDA version such as `12.4` or `11.8.89` into (major, minor).
+fn parse_cuda_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().unwrap_or("0").parse().ok()?;
+    Some((major, minor))
+}
+
+/// Picks the torch build for the given GPU, driver CUDA version, OS and architecture
+/// (`std::env::consts::OS`/`ARCH` values). Falls back to CPU when no rule matches.
+pub fn resolve_torch_variant(gpu_type: &GpuType, driver_cuda_version: Option<&str>, os: &str, arch: &str) -> TorchResolution {
+    let driver_cuda = driver_cuda_version.and_then(parse_cuda_version);
+
+    for rule in TORCH_RULES {
+        if rule.gpu_type.as_ref().is_some_and(|g| g != gpu_type) {
+            continue;
+        }
+        if rule.os.is_some_and(|o| o != os) || rule.arch.is_some_and(|a| a != arch) {
+            continue;
+        }
+        if let Some(min) = rule.min_driver_cuda {
+            match driver_cuda {
+                Some(driver) if driver >= min => {}
+                _ => continue,
+            }
+        }
+        let reason = match (rule.min_driver_cuda, driver_cuda_version) {
+            (Some((major, minor)), Some(driver)) => format!("{:?} GPU, driver supports CUDA {} (>= {}.{})", gpu_type, driver, major, minor),
+            _ => format!("{:?} GPU on {}/{}", gpu_type, os, arch),
+        };
+        return TorchResolution { variant: rule.variant, reason };
+    }
+
+    let reason = match (gpu_type, driver_cuda_version) {
+        (GpuType::Nvidia, Some(driver)) => format!("NVIDIA driver only supports CUDA {}, older than any supported build", driver),
+        (GpuType::Nvidia, None) => "NVIDIA GPU detected but the driver's CUDA version is unknown".to_string(),
+        _ => format!("No accelerated torch build for {:?} GPU on {}/{}", gpu_type, os, arch),
+    };
+    TorchResolution { variant: TorchVariant::Cpu, reason }
+}
+
+/// Detects this machine's GPU and resolves the torch build for it.
+pub fn resolve_torch_for_host() -> TorchResolution {
+    let gpu_info = get_gpu_info();
+    let resolution = resolve_torch_variant(&gpu_info.gpu_type, gpu_info.cuda_version.as_deref(), std::env::consts::OS, std::env::consts::ARCH);
+    info!("[TORCH_RESOLVER] Resolved torch variant {} ({})", resolution.variant.tag(), resolution.reason);
+    resolution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_expected_variant_for_each_platform() {
+        // (gpu, driver CUDA version, os, arch, expected variant)
+        let cases: &[(GpuType, Option<&str>, &str, &str, TorchVariant)] = &[
+            // NVIDIA: newest build the driver supports.
+            (GpuType::Nvidia, Some("12.6"), "windows", "x86_64", TorchVariant::Cuda124),
+            (GpuType::Nvidia, Some("12.4"), "linux", "x86_64", TorchVariant::Cuda124),
+            (GpuType::Nvidia, Some("12.2"), "windows", "x86_64", TorchVariant::Cuda121),
+            (GpuType::Nvidia, Some("12.1"), "linux", "x86_64", TorchVariant::Cuda121),
+            (GpuType::Nvidia, Some("11.8.89"), "windows", "x86_64", TorchVariant::Cuda118),
+            (GpuType::Nvidia, Some("11.4"), "windows", "x86_64", TorchVariant::Cpu),
+            (GpuType::Nvidia, None, "windows", "x86_64", TorchVariant::Cpu),
+            (GpuType::Nvidia, Some("not a version"), "linux", "x86_64", TorchVariant::Cpu),
+            // AMD: ROCm on Linux only.
+            (GpuType::Amd, None, "linux", "x86_64", TorchVariant::Rocm62),
+            (GpuType::Amd, None, "windows", "x86_64", TorchVariant::Cpu),
+            // Intel: XPU on Windows and Linux.
+            (GpuType::Intel, None, "windows", "x86_64", TorchVariant::Xpu),
+            (GpuType::Intel, None, "linux", "x86_64", TorchVariant::Xpu),
+            (GpuType::Intel, None, "macos", "x86_64", TorchVariant::Cpu),
+            // Apple Silicon: MPS whatever the GPU is reported as.
+            (GpuType::Unknown, None, "macos", "aarch64", TorchVariant::Mps),
+            (GpuType::Amd, None, "macos", "aarch64", TorchVariant::Mps),
+            // Nothing usable: CPU.
+            (GpuType::Unknown, None, "windows", "x86_64", TorchVariant::Cpu),
+            (GpuType::Unknown, None, "linux", "aarch64", TorchVariant::Cpu),
+        ];
+
+        for (gpu_type, driver, os, arch, expected) in cases {
+            let resolution = resolve_torch_variant(gpu_type, *driver, os, arch);
+            assert_eq!(resolution.variant, *expected, "{:?} driver {:?} on {}/{}: {}", gpu_type, driver, os, arch, resolution.reason);
+        }
+    }
+
+    #[test]
+    fn parses_cuda_versions() {
+        assert_eq!(parse_cuda_version("12.4"), Some((12, 4)));
+        assert_eq!(parse_cuda_version(" 11.8.89\n"), Some((11, 8)));
+        assert_eq!(parse_cuda_version("12"), Some((12, 0)));
+        assert_eq!(parse_cuda_version(""), None);
+    }
+
+    #[test]
+    fn mps_installs_from_pypi() {
+        assert_eq!(TorchVariant::Mps.pip_index_url(), None);
+        assert_eq!(TorchVariant::Cuda124.pip_index_url().as_deref(), Some("https://download.pytorch.org/whl/cu124"));
+    }
+}
@@ -0,0 +1,155 @@
+// metamorphosis-app/src-tauri/src/setup_manager/dependency_manager/venv_env.rs
+//
+// Python environment setup for the Venv backend: a virtual environment created from the bundled
+// python-build-standalone interpreter, with dependencies installed by `uv` when available and
+// pip otherwise. Mirrors the Conda flow in python_env.rs step by step.
+
+use std::path::PathBuf;
+use log::{error, info, warn};
+use tauri::{AppHandle, Wry};
+use tokio::time::{sleep, Duration};
+
+use crate::gpu_detection::{get_gpu_info, GpuType};
+use crate::setup;
+use crate::setup_manager::env_backend::{find_bundled_python, find_uv, get_venv_path, pip_install_command, venv_python_path};
+use crate::setup_manager::python_utils::wait_for_file_to_exist;
+use super::command_runner::run_command_for_setup_progress;
+use super::lockfile::requirements_install_args;
+use super::torch_resolver::resolve_torch_for_host;
+use crate::setup_manager::setup_journal;
+
+const ONNXRUNTIME_CUDA12_INDEX: &str = "https://aiinfra.pkgs.visualstudio.com/PublicPackages/_packaging/onnxruntime-cuda-12/pypi/simple/";
+
+/// Runs `pip install <args>` (or `uv pip install`) inside the venv.
+async fn pip_install(
+    app_handle: &AppHandle<Wry>,
+    phase_name: &str,
+    step: &str,
+    progress: u8,
+    weight: u8,
+    packages_and_flags: &[String],
+    cwd: &PathBuf,
+) -> Result<u8, String> {
+    let (installer, mut args) = pip_install_command(app_handle).await?;
+    args.extend(packages_and_flags.iter().cloned());
+    let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    info!("Executing command: {} {}", installer.display(), args.join(" "));
+    run_command_for_setup_progress(
+        app_handle, phase_name, step, progress, weight,
+        &installer, &args_refs,
+        cwd,
+        &format!("Starting: {}...", step), &format!("{} failed", step),
+    ).await
+}
+
+/// Creates the venv (if needed) and installs ComfyUI's dependencies into it.
+/// Returns the phase progress reached.
+pub async fn install_venv_dependencies_with_progress(
+    app_handle: &AppHandle<Wry>,
+    phase_name: &str,
+    comfyui_dir: &PathBuf,
+    mut current_phase_progress: u8,
+) -> Result<u8, String> {
+    let venv_dir = get_venv_path()?;
+    let venv_python = venv_python_path(&venv_dir);
+    let uv = find_uv(app_handle).await;
+
+    if !venv_python.exists() {
+        let bundled_python = find_bundled_python(app_handle).map_err(|e| {
+            error!("{}", e);
+            setup::emit_setup_progress(app_handle, "error", "Bundled Python Missing", current_phase_progress, Some(e.clone()), Some(e.clone()));
+            e
+        })?;
+        info!("Creating venv at {} from bundled Python {}", venv_dir.display(), bundled_python.display());
+        let venv_dir_str = venv_dir.to_string_lossy().to_string();
+        let bundled_python_str = bundled_python.to_string_lossy().to_string();
+        let (command, args): (PathBuf, Vec<&str>) = match &uv {
+            Some(uv) => (uv.clone(), vec!["venv", "--python", &bundled_python_str, &venv_dir_str]),
+            None => (bundled_python.clone(), vec!["-m", "venv", &venv_dir_str]),
+        };
+        current_phase_progress = run_command_for_setup_progress(
+            app_handle, phase_name, "Creating Python virtual environment", current_phase_progress, 15,
+            &command, &args,
+            comfyui_dir,
+            "Creating virtual environment from bundled Python...", "Failed to create virtual environment",
+        ).await?;
+    } else {
+        info!("Venv already exists at {}. Skipping creation.", venv_dir.display());
+        current_phase_progress = current_phase_progress.max(30);
+        setup::emit_setup_progress(app_handle, phase_name, "Python virtual environment already exists.", current_phase_progress, None, None);
+    }
+
+    wait_for_file_to_exist(app_handle, &venv_python, 120, 1000, "venv Python executable").await?;
+
+    if uv.is_none() {
+        // pip from the bundled interpreter can be old enough to mis-resolve torch wheels.
+        let upgrade_args = vec!["--upgrade".to_string(), "pip".to_string()];
+        current_phase_progress = pip_install(app_handle, phase_name, "Upgrading pip", current_phase_progress, 5, &upgrade_args, comfyui_dir).await?;
+    }
+
+    let requirements_path = comfyui_dir.join("requirements.txt");
+    if !requirements_path.exists() {
+        let err_msg = format!("ComfyUI requirements.txt not found at {}", requirements_path.display());
+        error!("{}", err_msg);
+        setup::emit_setup_progress(app_handle, "error", "Requirements File Error", current_phase_progress, Some(err_msg.clone()), Some(err_msg.clone()));
+        return Err(err_msg);
+    }
+
+    let gpu_info = get_gpu_info();
+    let torch_resolution = resolve_torch_for_host();
+    setup_journal::record_note(app_handle, "torch_variant", torch_resolution.variant.tag());
+    setup_journal::record_note(app_handle, "torch_variant_reason", &torch_resolution.reason);
+    let torch_args = torch_resolution.variant.pip_install_args();
+
+    let max_retries = 3;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        setup::emit_setup_progress(app_handle, phase_name, &format!("Attempt {} of {} to install PyTorch...", attempt, max_retries), current_phase_progress, None, None);
+        match pip_install(app_handle, phase_name, "Installing PyTorch, Torchvision, Torchaudio", current_phase_progress, 30, &torch_args, comfyui_dir).await {
+            Ok(progress) => {
+                current_phase_progress = progress;
+                info!("PyTorch installed into venv on attempt {}.", attempt);
+                break;
+            }
+            Err(e) if attempt < max_retries => {
+                let retry_msg = format!("Retrying PyTorch installation in 5 seconds (attempt {}/{})", attempt, max_retries);
+                warn!("{}", retry_msg);
+                setup::emit_setup_progress(app_handle, phase_name, &retry_msg, current_phase_progress, Some(e), None);
+                sleep(Duration::from_secs(5)).await;
+            }
+            Err(e) => {
+                let final_err_msg = format!("Failed to install PyTorch after {} attempts. Last error: {}", max_retries, e);
+                error!("{}", final_err_msg);
+                setup::emit_setup_progress(app_handle, "error", "PyTorch Installation Failed", current_phase_progress, Some(final_err_msg.clone()), Some(final_err_msg.clone()));
+                return Err(final_err_msg);
+            }
+        }
+    }
+
+    let base_packages = vec!["numpy".to_string(), "onnxruntime".to_string(), "cffi".to_string()];
+    current_phase_progress = pip_install(app_handle, phase_name, "Installing NumPy, onnxruntime and cffi", current_phase_progress, 10, &base_packages, comfyui_dir).await?;
+
+    let mut requirements_args = requirements_install_args(app_handle, &requirements_path);
+    if gpu_info.gpu_type == GpuType::Nvidia && gpu_info.cuda_version.as_deref().map_or(false, |v| v.starts_with("12.")) {
+        info!("Detected NVIDIA CUDA 12.x. Adding --extra-index-url for onnxruntime-gpu to pip install command.");
+        requirements_args.push("--extra-index-url".to_string());
+        requirements_args.push(ONNXRUNTIME_CUDA12_INDEX.to_string());
+    }
+    current_phase_progress = pip_install(app_handle, phase_name, "Installing remaining dependencies from requirements.txt", current_phase_progress, 10, &requirements_args, comfyui_dir).await?;
+
+    let check_torch_py_path = comfyui_dir.join("check_torch.py");
+    if check_torch_py_path.exists() {
+        let check_torch_path_str = check_torch_py_path.to_string_lossy().to_string();
+        current_phase_progress = run_command_for_setup_progress(
+            app_handle, phase_name, "Verifying PyTorch CUDA Setup", current_phase_progress, 5,
+            &venv_python, &[check_torch_path_str.as_str()],
+            comfyui_dir,
+            "Running PyTorch verification script...", "PyTorch verification script failed",
+        ).await?;
+    } else {
+        warn!("check_torch.py not found at {}. Skipping PyTorch verification.", check_torch_py_path.display());
+    }
+
+    Ok(current_phase_progress)
+}
@@ -0,0 +1,225 @@
+// metamorphosis-app/src-tauri/src/setup_manager/env_backend.rs
+//
+// The Python environment ComfyUI runs in can come from one of two backends:
+//  - Conda: Miniconda from `resources/installers` plus a `comfyui_env` conda env (python=3.10).
+//  - Venv: a virtual environment created from the python-build-standalone interpreter that
+//    build_logic bundles into `vendor/python`, with dependencies installed by `uv` (if found) or pip.
+// The backend is chosen in settings. Everything that needs "the env's python" or has to run
+// pip/launch ComfyUI inside the env goes through this module.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Wry};
+use tauri_plugin_shell::ShellExt;
+
+use crate::settings::load_settings;
+use super::dependency_manager::torch_resolver::TorchVariant;
+use super::orchestration::get_app_root_path;
+use super::python_utils::{
+    execute_command_to_string, get_bundled_python_executable_path, get_conda_env_python_executable_path, get_conda_executable_path,
+    get_vendor_path,
+};
+
+pub const COMFYUI_ENV_NAME: &str = "comfyui_env";
+// Directory (under the app root, next to miniconda3) holding the venv for the Venv backend.
+pub const VENV_DIR_NAME: &str = "comfyui_venv";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum EnvBackendKind {
+    #[default]
+    Conda,
+    Venv,
+}
+
+impl EnvBackendKind {
+    /// Python minor version the env is created with.
+    pub fn expected_python_version(self) -> &'static str {
+        match self {
+            EnvBackendKind::Conda => "3.10",
+            EnvBackendKind::Venv => "3.12", // python-build-standalone 3.12.10 from build_logic
+        }
+    }
+}
+
+/// The backend selected in settings.
+pub fn selected_backend(app_handle: &AppHandle<Wry>) -> EnvBackendKind {
+    load_settings(app_handle).env_backend
+}
+
+pub fn get_venv_path() -> Result<PathBuf, String> {
+    Ok(get_app_root_path()?.join(VENV_DIR_NAME))
+}
+
+fn venv_bin_dir(venv_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv_dir.join("Scripts")
+    } else {
+        venv_dir.join("bin")
+    }
+}
+
+pub fn venv_python_path(venv_dir: &Path) -> PathBuf {
+    venv_bin_dir(venv_dir).join(if cfg!(windows) { "python.exe" } else { "python" })
+}
+
+/// Locates the bundled python-build-standalone interpreter. The Windows build has `python.exe`
+/// at the install root, Unix builds keep it in `bin/`.
+pub fn find_bundled_python(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    if let Ok(path) = get_bundled_python_executable_path(app_handle) {
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+    let python_dir = get_vendor_path(app_handle)?.join("python");
+    let candidates = [
+        python_dir.join("bin").join("python3"),
+        python_dir.join("python").join(if cfg!(windows) { "python.exe" } else { "bin/python3" }),
+    ];
+    candidates
+        .into_iter()
+        .find(|p| p.exists())
+        .ok_or_else(|| format!("Bundled Python interpreter not found under {}", python_dir.display()))
+}
+
+/// Returns `uv` if it is bundled in `vendor/uv` or available on PATH.
+pub async fn find_uv(app_handle: &AppHandle<Wry>) -> Option<PathBuf> {
+    if let Ok(vendor_path) = get_vendor_path(app_handle) {
+        let bundled_uv = vendor_path.join("uv").join(if cfg!(windows) { "uv.exe" } else { "uv" });
+        if bundled_uv.exists() {
+            return Some(bundled_uv);
+        }
+    }
+    let uv_on_path = PathBuf::from("uv");
+    match execute_command_to_string(&uv_on_path, &["--version"], None).await {
+        Ok(version) => {
+            info!("[ENV_BACKEND] Using uv from PATH: {}", version);
+            Some(uv_on_path)
+        }
+        Err(_) => None,
+    }
+}
+
+/// Returns the executable and leading arguments for `pip install` inside the selected env.
+/// Callers append the packages or `-r requirements.txt`.
+pub async fn pip_install_command(app_handle: &AppHandle<Wry>) -> Result<(PathBuf, Vec<String>), String> {
+    match selected_backend(app_handle) {
+        EnvBackendKind::Conda => {
+            let conda_executable = get_conda_executable_path(app_handle).await?;
+            let args = ["run", "-n", COMFYUI_ENV_NAME, "python", "-m", "pip", "install"];
+            Ok((conda_executable, args.iter().map(|s| s.to_string()).collect()))
+        }
+        EnvBackendKind::Venv => {
+            let venv_python = venv_python_path(&get_venv_path()?);
+            match find_uv(app_handle).await {
+                Some(uv) => Ok((uv, vec![
+                    "pip".to_string(),
+                    "install".to_string(),
+                    "--python".to_string(),
+                    venv_python.to_string_lossy().to_string(),
+                ])),
+                None => Ok((venv_python, vec!["-m".to_string(), "pip".to_string(), "install".to_string()])),
+            }
+        }
+    }
+}
+
+/// Returns the executable and leading arguments for uninstalling packages from the selected env
+/// without prompting. Callers append the package names.
+pub async fn pip_uninstall_command(app_handle: &AppHandle<Wry>) -> Result<(PathBuf, Vec<String>), String> {
+    let (installer, mut args) = pip_install_command(app_handle).await?;
+    if let Some(pos) = args.iter().position(|a| a == "install") {
+        args[pos] = "uninstall".to_string();
+    }
+    // `uv pip uninstall` never prompts and has no -y flag.
+    if args.first().map(|a| a.as_str()) != Some("pip") {
+        args.push("-y".to_string());
+    }
+    Ok((installer, args))
+}
+
+/// Returns the executable and arguments that install the given torch build into the selected env.
+/// Conda uses the pytorch channel where it publishes the build and falls back to pip inside the
+/// env for wheel-only builds (ROCm, XPU).
+pub async fn torch_install_command(app_handle: &AppHandle<Wry>, variant: TorchVariant, force_reinstall: bool) -> Result<(PathBuf, Vec<String>), String> {
+    if selected_backend(app_handle) == EnvBackendKind::Conda {
+        if let Some(variant_args) = variant.conda_install_args() {
+            let conda_executable = get_conda_executable_path(app_handle).await?;
+            let mut args: Vec<String> = ["install", "-n", COMFYUI_ENV_NAME, "pytorch", "torchvision", "torchaudio"].iter().map(|s| s.to_string()).collect();
+            args.extend(variant_args.iter().map(|s| s.to_string()));
+            if force_reinstall {
+                args.push("--force-reinstall".to_string());
+            }
+            args.push("-y".to_string()); // Auto-approve
+            return Ok((conda_executable, args));
+        }
+    }
+    let (installer, mut args) = pip_install_command(app_handle).await?;
+    if force_reinstall {
+        args.push("--force-reinstall".to_string());
+    }
+    args.extend(variant.pip_install_args());
+    Ok((installer, args))
+}
+
+/// Runs `pip freeze` against the selected env. A venv created by `uv venv` has no pip of its own,
+/// so `uv pip freeze` is used for it when uv is available.
+pub async fn pip_freeze(app_handle: &AppHandle<Wry>) -> Result<String, String> {
+    let python = get_conda_env_python_executable_path(app_handle, COMFYUI_ENV_NAME).await?;
+    if selected_backend(app_handle) == EnvBackendKind::Venv {
+        if let Some(uv) = find_uv(app_handle).await {
+            let python_str = python.to_string_lossy().to_string();
+            return execute_command_to_string(&uv, &["pip", "freeze", "--python", &python_str], None).await;
+        }
+    }
+    execute_command_to_string(&python, &["-m", "pip", "freeze"], None).await
+}
+
+/// Environment variables to launch ComfyUI with so it behaves as if the env were activated.
+pub async fn get_launch_env_vars(app_handle: &AppHandle<Wry>) -> Result<HashMap<String, String>, String> {
+    match selected_backend(app_handle) {
+        EnvBackendKind::Conda => {
+            let conda_exe_path = get_conda_executable_path(app_handle).await?;
+            info!("[ENV_BACKEND] Capturing environment variables from conda env '{}'...", COMFYUI_ENV_NAME);
+            let dump_args: &[&str] = if cfg!(windows) { &["cmd", "/c", "set"] } else { &["env"] };
+            let mut args = vec!["run", "-n", COMFYUI_ENV_NAME];
+            args.extend_from_slice(dump_args);
+            let env_capture_output = app_handle.shell()
+                .command(&conda_exe_path)
+                .args(&args)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to capture conda environment: {}", e))?;
+
+            if !env_capture_output.status.success() {
+                return Err(format!("Failed to capture conda environment. Stderr: {}", String::from_utf8_lossy(&env_capture_output.stderr)));
+            }
+
+            let env_vars: HashMap<String, String> = String::from_utf8_lossy(&env_capture_output.stdout)
+                .lines()
+                .filter_map(|line| line.split_once('=').map(|(key, value)| (key.to_string(), value.to_string())))
+                .collect();
+            info!("[ENV_BACKEND] Successfully captured {} environment variables.", env_vars.len());
+            Ok(env_vars)
+        }
+        EnvBackendKind::Venv => {
+            // A venv only needs VIRTUAL_ENV and its bin dir first on PATH.
+            let venv_dir = get_venv_path()?;
+            let bin_dir = venv_bin_dir(&venv_dir);
+            let existing_path = std::env::var_os("PATH").unwrap_or_default();
+            let mut paths = vec![bin_dir];
+            paths.extend(std::env::split_paths(&existing_path));
+            let joined_path = std::env::join_paths(paths).map_err(|e| format!("Failed to build PATH for venv: {}", e))?;
+
+            let mut env_vars = HashMap::new();
+            env_vars.insert("VIRTUAL_ENV".to_string(), venv_dir.to_string_lossy().to_string());
+            env_vars.insert("PATH".to_string(), joined_path.to_string_lossy().to_string());
+            if std::env::var_os("PYTHONHOME").is_some() {
+                warn!("[ENV_BACKEND] PYTHONHOME is set in the app environment; it may interfere with the venv.");
+            }
+            Ok(env_vars)
+        }
+    }
+}
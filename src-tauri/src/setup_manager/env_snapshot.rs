@@ -0,0 +1,282 @@
+// metamorphosis-app/src-tauri/src/setup_manager/env_snapshot.rs
+//
+// Snapshots of the Python environment, taken before anything changes its packages (custom node
+// requirements, torch reinstalls) and after a setup run completes. Installing custom node
+// requirements can break working packages (Impact Pack and ControlNet Aux pull heavy
+// dependencies), so a snapshot lets the user roll back to the last known-good state.
+//
+// Each snapshot is a directory under `<app data>/env_snapshots/<id>/` holding `pip freeze`
+// output, `conda list --explicit` output (Conda backend only) and a `snapshot.json` with metadata.
+// A snapshot is "known good" when it was taken while the master marker existed, i.e. after a
+// setup run had completed.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+use uuid::Uuid;
+
+use super::component_reset::ensure_backend_idle;
+use super::dependency_manager::command_runner::run_command_for_setup_progress;
+use super::dependency_manager::lockfile::{normalize_name, parse_freeze};
+use super::dependency_manager::torch_resolver::resolve_torch_for_host;
+use super::env_backend::{pip_freeze, pip_install_command, pip_uninstall_command, selected_backend, EnvBackendKind, COMFYUI_ENV_NAME};
+use super::event_utils::emit_setup_progress;
+use super::install_manifest::get_master_marker_path;
+use super::python_utils::{execute_command_to_string, get_comfyui_directory_path, get_conda_env_python_executable_path, get_conda_executable_path};
+use super::setup_journal;
+
+const ENV_SNAPSHOTS_DIR: &str = "env_snapshots";
+const SNAPSHOT_METADATA_FILENAME: &str = "snapshot.json";
+const PIP_FREEZE_FILENAME: &str = "pip-freeze.txt";
+const CONDA_EXPLICIT_FILENAME: &str = "conda-explicit.txt";
+const PIP_RESTORE_FILENAME: &str = "pip-restore.txt";
+// The conda env is rebuilt under this name and only swapped in once it was created successfully.
+const RESTORE_ENV_NAME: &str = "comfyui_env_restore";
+// Older snapshots are pruned; each one is only a few KB, but there is no reason to keep them forever.
+const MAX_ENV_SNAPSHOTS: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvSnapshot {
+    pub id: String,
+    pub created_at: String,
+    pub step: String,   // Setup journal step running when the snapshot was taken, or "manual"
+    pub reason: String, // What was about to change, e.g. "installing dependencies for ComfyUI-Impact-Pack"
+    pub backend: EnvBackendKind,
+    pub known_good: bool,
+    pub has_conda_list: bool,
+}
+
+fn get_snapshots_dir(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(ENV_SNAPSHOTS_DIR))
+}
+
+/// All snapshots, newest first. Unreadable snapshot directories are skipped.
+pub fn list_snapshots(app_handle: &AppHandle<Wry>) -> Vec<EnvSnapshot> {
+    let dir = match get_snapshots_dir(app_handle) {
+        Ok(d) => d,
+        Err(_) => return Vec::new(),
+    };
+    let mut snapshots: Vec<EnvSnapshot> = fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join(SNAPSHOT_METADATA_FILENAME)).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    snapshots
+}
+
+/// Ids of the snapshots to delete: everything past the newest `MAX_ENV_SNAPSHOTS`, except the
+/// newest known-good one, which is kept however old it is so a rollback target always exists.
+fn snapshots_to_prune(snapshots: &[EnvSnapshot]) -> Vec<String> {
+    let newest_known_good = snapshots.iter().find(|s| s.known_good).map(|s| s.id.as_str());
+    snapshots
+        .iter()
+        .skip(MAX_ENV_SNAPSHOTS)
+        .filter(|s| Some(s.id.as_str()) != newest_known_good)
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+fn prune_snapshots(app_handle: &AppHandle<Wry>) {
+    let Ok(dir) = get_snapshots_dir(app_handle) else { return };
+    for old in snapshots_to_prune(&list_snapshots(app_handle)) {
+        if let Err(e) = fs::remove_dir_all(dir.join(&old)) {
+            warn!("[ENV_SNAPSHOT] Failed to prune snapshot {}: {}", old, e);
+        }
+    }
+}
+
+/// Records the env's packages. Returns `Ok(None)` when there is no env yet.
+pub async fn take_env_snapshot(app_handle: &AppHandle<Wry>, reason: &str) -> Result<Option<EnvSnapshot>, String> {
+    let python_exe = get_conda_env_python_executable_path(app_handle, COMFYUI_ENV_NAME).await?;
+    if !python_exe.exists() {
+        info!("[ENV_SNAPSHOT] No Python environment yet; nothing to snapshot before {}.", reason);
+        return Ok(None);
+    }
+    let backend = selected_backend(app_handle);
+    let freeze = pip_freeze(app_handle).await?;
+    let conda_list = if backend == EnvBackendKind::Conda {
+        let conda_exe = get_conda_executable_path(app_handle).await?;
+        Some(execute_command_to_string(&conda_exe, &["list", "-n", COMFYUI_ENV_NAME, "--explicit"], None).await?)
+    } else {
+        None
+    };
+
+    let now = chrono::Utc::now();
+    let snapshot = EnvSnapshot {
+        id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), &Uuid::new_v4().simple().to_string()[..8]),
+        created_at: now.to_rfc3339(),
+        step: setup_journal::current_step_name(app_handle).unwrap_or_else(|| "manual".to_string()),
+        reason: reason.to_string(),
+        backend,
+        known_good: get_master_marker_path(app_handle).map_or(false, |p| p.exists()),
+        has_conda_list: conda_list.is_some(),
+    };
+
+    let snapshot_dir = get_snapshots_dir(app_handle)?.join(&snapshot.id);
+    fs::create_dir_all(&snapshot_dir).map_err(|e| format!("Failed to create snapshot dir {}: {}", snapshot_dir.display(), e))?;
+    fs::write(snapshot_dir.join(PIP_FREEZE_FILENAME), &freeze).map_err(|e| format!("Failed to write pip freeze snapshot: {}", e))?;
+    if let Some(list) = &conda_list {
+        fs::write(snapshot_dir.join(CONDA_EXPLICIT_FILENAME), list).map_err(|e| format!("Failed to write conda list snapshot: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|e| format!("Failed to serialize snapshot metadata: {}", e))?;
+    fs::write(snapshot_dir.join(SNAPSHOT_METADATA_FILENAME), json).map_err(|e| format!("Failed to write snapshot metadata: {}", e))?;
+
+    info!("[ENV_SNAPSHOT] Took snapshot {} ({}, known good: {}).", snapshot.id, reason, snapshot.known_good);
+    prune_snapshots(app_handle);
+    Ok(Some(snapshot))
+}
+
+/// Takes a snapshot before a dependency-changing step. A failed snapshot is logged but never
+/// blocks the step itself.
+pub async fn snapshot_before_change(app_handle: &AppHandle<Wry>, reason: &str) {
+    if let Err(e) = take_env_snapshot(app_handle, reason).await {
+        warn!("[ENV_SNAPSHOT] Could not snapshot the environment before {}: {}", reason, e);
+    }
+}
+
+/// Lines of a `pip freeze` snapshot that pip can reinstall. Packages conda installed show up as
+/// `name @ file:///...` pointing at conda's build cache and are restored by conda instead.
+fn restorable_requirements(freeze: &str) -> String {
+    freeze
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.contains(" @ file:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn run_restore_step(app_handle: &AppHandle<Wry>, step: &str, progress: u8, weight: u8, command: &Path, args: &[String], cwd: &PathBuf) -> Result<u8, String> {
+    let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    run_command_for_setup_progress(
+        app_handle, "restoring_env_snapshot", step, progress, weight,
+        &command.to_path_buf(), &args_refs,
+        cwd,
+        &format!("{}...", step), &format!("{} failed", step),
+    ).await
+}
+
+/// Restores the env to `snapshot`. Conda envs are recreated from the explicit package list; pip
+/// packages are then reinstalled at their recorded versions and anything added since is removed.
+/// The conda env is built under a temporary name first, so a failed create (e.g. offline, with
+/// packages missing from the cache) leaves the current env in place.
+async fn restore_snapshot(app_handle: &AppHandle<Wry>, snapshot: &EnvSnapshot) -> Result<(), String> {
+    let snapshot_dir = get_snapshots_dir(app_handle)?.join(&snapshot.id);
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+    let mut progress = 0;
+
+    if snapshot.has_conda_list {
+        let conda_exe = get_conda_executable_path(app_handle).await?;
+        let explicit_path = snapshot_dir.join(CONDA_EXPLICIT_FILENAME).to_string_lossy().to_string();
+        let to_args = |args: &[&str]| -> Vec<String> { args.iter().map(|s| s.to_string()).collect() };
+        // Leftover from an earlier failed restore; conda refuses to create over it.
+        let _ = execute_command_to_string(&conda_exe, &["remove", "-n", RESTORE_ENV_NAME, "--all", "-y"], None).await;
+
+        let create_args = to_args(&["create", "-n", RESTORE_ENV_NAME, "--file", explicit_path.as_str(), "-y"]);
+        progress = match run_restore_step(app_handle, "Recreating conda environment from snapshot", progress, 40, &conda_exe, &create_args, &comfyui_dir).await {
+            Ok(p) => p,
+            Err(e) => {
+                let _ = execute_command_to_string(&conda_exe, &["remove", "-n", RESTORE_ENV_NAME, "--all", "-y"], None).await;
+                return Err(format!("{} The current environment was left unchanged.", e));
+            }
+        };
+        let remove_args = to_args(&["remove", "-n", COMFYUI_ENV_NAME, "--all", "-y"]);
+        progress = run_restore_step(app_handle, "Removing current conda environment", progress, 5, &conda_exe, &remove_args, &comfyui_dir).await?;
+        let rename_args = to_args(&["rename", "-n", RESTORE_ENV_NAME, COMFYUI_ENV_NAME]);
+        progress = run_restore_step(app_handle, "Swapping in the restored conda environment", progress, 5, &conda_exe, &rename_args, &comfyui_dir)
+            .await
+            .map_err(|e| format!("{} The restored environment is still available as '{}'.", e, RESTORE_ENV_NAME))?;
+    } else {
+        // Remove packages installed after the snapshot so they can't shadow the restored ones.
+        let (recorded, _) = parse_freeze(&fs::read_to_string(snapshot_dir.join(PIP_FREEZE_FILENAME)).map_err(|e| format!("Failed to read snapshot: {}", e))?);
+        let recorded_names: HashSet<String> = recorded.iter().map(|(name, _)| normalize_name(name)).collect();
+        let (current, _) = parse_freeze(&pip_freeze(app_handle).await?);
+        let added: Vec<String> = current.into_iter().map(|(name, _)| name).filter(|name| !recorded_names.contains(&normalize_name(name))).collect();
+        if !added.is_empty() {
+            info!("[ENV_SNAPSHOT] Removing packages added since the snapshot: {}", added.join(", "));
+            let (uninstaller, mut args) = pip_uninstall_command(app_handle).await?;
+            args.extend(added);
+            progress = run_restore_step(app_handle, "Removing packages added since the snapshot", progress, 20, &uninstaller, &args, &comfyui_dir).await?;
+        }
+    }
+
+    let freeze = fs::read_to_string(snapshot_dir.join(PIP_FREEZE_FILENAME)).map_err(|e| format!("Failed to read snapshot: {}", e))?;
+    let restore_path = snapshot_dir.join(PIP_RESTORE_FILENAME);
+    fs::write(&restore_path, restorable_requirements(&freeze)).map_err(|e| format!("Failed to write restore requirements: {}", e))?;
+    let (installer, mut args) = pip_install_command(app_handle).await?;
+    args.push("-r".to_string());
+    args.push(restore_path.to_string_lossy().to_string());
+    args.extend(resolve_torch_for_host().variant.pip_extra_index_args());
+    run_restore_step(app_handle, "Reinstalling pip packages from snapshot", progress, 100 - progress, &installer, &args, &comfyui_dir).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_env_snapshots(app_handle: AppHandle<Wry>) -> Result<Vec<EnvSnapshot>, String> {
+    Ok(list_snapshots(&app_handle))
+}
+
+/// Restores the given snapshot, or the most recent known-good one when no id is given.
+#[tauri::command]
+pub async fn rollback_env_snapshot(app_handle: AppHandle<Wry>, snapshot_id: Option<String>) -> Result<EnvSnapshot, String> {
+    ensure_backend_idle(&app_handle)?;
+    let snapshots = list_snapshots(&app_handle);
+    let snapshot = match &snapshot_id {
+        Some(id) => snapshots.into_iter().find(|s| &s.id == id).ok_or_else(|| format!("Environment snapshot '{}' not found.", id))?,
+        None => snapshots.into_iter().find(|s| s.known_good).ok_or_else(|| "No known-good environment snapshot to roll back to.".to_string())?,
+    };
+    if snapshot.backend != selected_backend(&app_handle) {
+        return Err(format!("Snapshot {} was taken with the {:?} backend, but {:?} is selected.", snapshot.id, snapshot.backend, selected_backend(&app_handle)));
+    }
+
+    info!("[ENV_SNAPSHOT] Rolling back to snapshot {} (taken {} before {}).", snapshot.id, snapshot.created_at, snapshot.reason);
+    match restore_snapshot(&app_handle, &snapshot).await {
+        Ok(()) => {
+            emit_setup_progress(&app_handle, "restoring_env_snapshot", "Environment restored", 100, Some(format!("Restored snapshot from {}.", snapshot.created_at)), None);
+            Ok(snapshot)
+        }
+        Err(e) => {
+            error!("[ENV_SNAPSHOT] Rollback to {} failed: {}", snapshot.id, e);
+            emit_setup_progress(&app_handle, "error", "Environment Rollback Failed", 0, Some(e.clone()), Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(id: &str, known_good: bool) -> EnvSnapshot {
+        EnvSnapshot {
+            id: id.to_string(),
+            created_at: String::new(),
+            step: "manual".to_string(),
+            reason: "test".to_string(),
+            backend: EnvBackendKind::Conda,
+            known_good,
+            has_conda_list: true,
+        }
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_known_good_snapshot() {
+        // Newest first, like list_snapshots: a run of failed installs after the last good state.
+        let mut snapshots: Vec<EnvSnapshot> = (0..MAX_ENV_SNAPSHOTS + 2).map(|i| snapshot(&format!("bad-{}", i), false)).collect();
+        snapshots.push(snapshot("good-new", true));
+        snapshots.push(snapshot("good-old", true));
+
+        let pruned = snapshots_to_prune(&snapshots);
+        assert!(!pruned.contains(&"good-new".to_string()));
+        assert!(pruned.contains(&"good-old".to_string()));
+        assert_eq!(pruned.len(), 3); // bad-10, bad-11 and good-old
+        assert!(snapshots_to_prune(&snapshots[..MAX_ENV_SNAPSHOTS]).is_empty());
+    }
+}
@@ -2,7 +2,8 @@
 use tauri::{AppHandle, Wry, Emitter}; // Import Wry, Emitter
 use log::error;
 
-use super::types::{CustomNodeCloneFailedPayload, CustomNodePayload, SetupProgressPayload}; // Import from the new types module
+use super::dependency_manager::install_progress::InstallProgressSnapshot;
+use super::types::{CustomNodeCloneFailedPayload, CustomNodePayload, InstallPackageProgressPayload, SetupProgressPayload}; // Import from the new types module
 
 // Generic event emitter
 pub fn emit_event<S: serde::Serialize + Clone>(
@@ -35,6 +36,23 @@ pub fn emit_setup_progress(
     }
 }
 
+// Helper to emit package-level progress of an install command
+pub fn emit_install_package_progress(
+    app_handle: &AppHandle<Wry>,
+    phase: &str,
+    step: &str,
+    snapshot: InstallProgressSnapshot,
+) {
+    let payload = InstallPackageProgressPayload {
+        phase: phase.to_string(),
+        step: step.to_string(),
+        snapshot,
+    };
+    if let Err(e) = app_handle.emit("install-package-progress", payload) {
+        error!("Failed to emit install-package-progress event: {}", e);
+    }
+}
+
 // Helper functions for Custom Node Cloning Events
 
 pub fn emit_custom_node_clone_start(app_handle: &AppHandle<Wry>, node_name: &str) {
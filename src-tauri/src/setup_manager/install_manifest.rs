@@ -0,0 +1,583 @@
+// metamorphosis-app/src-tauri/src/setup_manager/install_manifest.rs
+//
+// The master installation marker used to contain only the literal text
+// `setup_completed_successfully`, which told us nothing about *what* was installed.
+// It now holds a JSON manifest describing the installed components so that an app
+// upgrade which adds a model or custom node only installs the difference.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, Wry};
+
+use super::custom_node_manager::archive_install::read_archive_source;
+use super::custom_node_manager::git_ops::{head_commit, resolve_revision};
+use super::custom_node_manager::node_definitions::CUSTOM_NODE_MANIFEST;
+use super::deep_verification::node_import_target;
+use super::dependency_manager::lockfile::active_lockfile;
+use super::dependency_manager::torch_resolver::resolve_torch_for_host;
+use super::env_backend::{selected_backend, EnvBackendKind};
+use super::model_config::{get_core_models_list, ModelConfig};
+use super::python_utils::get_comfyui_directory_path;
+
+pub const MASTER_MARKER_FILENAME: &str = "metamorphosis_setup_complete.marker";
+// Contents written by app versions that predate the manifest.
+const LEGACY_MARKER_CONTENTS: &str = "setup_completed_successfully";
+// `app_version` of a manifest reconstructed from disk for a legacy marker.
+const LEGACY_APP_VERSION: &str = "legacy";
+const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelManifestEntry {
+    pub id: String,
+    pub fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeManifestEntry {
+    pub name: String,
+    pub repo_url: String,
+    #[serde(default)]
+    pub revision: Option<String>, // Pinned commit or tag from the custom node manifest, None for the default branch
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub model_manifest_hash: String,
+    pub models: Vec<ModelManifestEntry>,
+    pub custom_nodes: Vec<CustomNodeManifestEntry>,
+    #[serde(default)]
+    pub python_requirements_hash: Option<String>,
+    #[serde(default)]
+    pub torch_variant: Option<String>,
+    #[serde(default)]
+    pub env_backend: Option<EnvBackendKind>, // None in manifests written before the venv backend existed, which were all conda
+    #[serde(default)]
+    pub python_version: Option<String>, // e.g. "3.10"; None in manifests written before it was recorded
+    pub completed_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ComponentKind {
+    Model,
+    CustomNode,
+    PythonRequirements,
+}
+
+/// A component that is expected by this app version but absent or outdated in the installed manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingComponent {
+    pub kind: ComponentKind,
+    pub name: String,
+    pub reason: String,
+}
+
+/// What we found in the master marker file.
+#[derive(Debug)]
+pub enum MarkerState {
+    Missing,
+    Legacy,
+    Manifest(InstallManifest),
+    Unreadable(String),
+}
+
+/// Which parts of setup need to run. `Full` (with the reason) is used for new installs, unreadable
+/// markers and a changed Python environment.
+#[derive(Debug, Clone)]
+pub enum SetupPlan {
+    Full(String),
+    Incremental(Vec<MissingComponent>),
+}
+
+impl SetupPlan {
+    fn includes(&self, kind: ComponentKind, name: &str) -> bool {
+        match self {
+            SetupPlan::Full(_) => true,
+            SetupPlan::Incremental(missing) => missing.iter().any(|m| m.kind == kind && m.name == name),
+        }
+    }
+
+    pub fn includes_model(&self, model_id: &str) -> bool {
+        self.includes(ComponentKind::Model, model_id)
+    }
+
+    pub fn includes_custom_node(&self, node_name: &str) -> bool {
+        self.includes(ComponentKind::CustomNode, node_name)
+    }
+
+    pub fn includes_python_requirements(&self) -> bool {
+        match self {
+            SetupPlan::Full(_) => true,
+            SetupPlan::Incremental(missing) => missing.iter().any(|m| m.kind == ComponentKind::PythonRequirements),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(self, SetupPlan::Full(_))
+    }
+}
+
+pub fn get_master_marker_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(MASTER_MARKER_FILENAME))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hex::encode(hasher.finalize())
+}
+
+/// Fingerprint of the fields that decide which file ends up on disk.
+/// Renaming a model's display name should not trigger a re-download.
+fn model_fingerprint(model: &ModelConfig) -> String {
+    let size = model.expected_size_bytes.map(|s| s.to_string()).unwrap_or_default();
+    sha256_hex(format!("{}|{}|{}|{}|{}", model.id, model.url, model.target_subdir, model.target_filename, size).as_bytes())
+}
+
+/// Hash of what the Python env is installed from: ComfyUI's requirements.txt plus the Python
+/// lockfile when one is in use, so shipping a new lock also triggers a reinstall.
+fn python_requirements_hash(comfyui_dir: &Path, lockfile: Option<&Path>) -> Option<String> {
+    let requirements_path = comfyui_dir.join("requirements.txt");
+    let mut bytes = match fs::read(&requirements_path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("[INSTALL_MANIFEST] Could not read {} for hashing: {}", requirements_path.display(), e);
+            return None;
+        }
+    };
+    if let Some(lockfile) = lockfile {
+        match fs::read(lockfile) {
+            Ok(lock_bytes) => bytes.extend(lock_bytes),
+            Err(e) => warn!("[INSTALL_MANIFEST] Could not read lockfile {} for hashing: {}", lockfile.display(), e),
+        }
+    }
+    Some(sha256_hex(&bytes))
+}
+
+fn hash_model_entries(models: &[ModelManifestEntry]) -> String {
+    sha256_hex(models.iter().map(|m| format!("{}={}", m.id, m.fingerprint)).collect::<Vec<_>>().join("\n").as_bytes())
+}
+
+/// Builds the manifest describing what this app version expects to be installed.
+pub fn build_expected_manifest(app_handle: &AppHandle<Wry>) -> Result<InstallManifest, String> {
+    let models: Vec<ModelManifestEntry> = get_core_models_list()
+        .iter()
+        .map(|m| ModelManifestEntry { id: m.id.clone(), fingerprint: model_fingerprint(m) })
+        .collect();
+    let model_manifest_hash = hash_model_entries(&models);
+
+    let custom_nodes = CUSTOM_NODE_MANIFEST
+        .iter()
+        .map(|node| CustomNodeManifestEntry {
+            name: node.name.to_string(),
+            repo_url: node.repo_url.to_string(),
+            revision: node.revision.map(|r| r.to_string()),
+        })
+        .collect();
+
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+
+    Ok(InstallManifest {
+        format_version: MANIFEST_FORMAT_VERSION,
+        app_version: app_handle.package_info().version.to_string(),
+        model_manifest_hash,
+        models,
+        custom_nodes,
+        python_requirements_hash: python_requirements_hash(&comfyui_dir, active_lockfile(app_handle).as_deref()),
+        torch_variant: Some(resolve_torch_for_host().variant.tag().to_string()),
+        env_backend: Some(selected_backend(app_handle)),
+        python_version: Some(selected_backend(app_handle).expected_python_version().to_string()),
+        completed_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+pub fn read_master_marker(marker_path: &Path) -> MarkerState {
+    if !marker_path.exists() {
+        return MarkerState::Missing;
+    }
+    match fs::read_to_string(marker_path) {
+        Ok(contents) if contents.trim() == LEGACY_MARKER_CONTENTS => MarkerState::Legacy,
+        Ok(contents) => match serde_json::from_str::<InstallManifest>(&contents) {
+            Ok(manifest) => MarkerState::Manifest(manifest),
+            Err(e) => MarkerState::Unreadable(format!("Failed to parse master marker as manifest: {}", e)),
+        },
+        Err(e) => MarkerState::Unreadable(format!("Failed to read master marker at {}: {}", marker_path.display(), e)),
+    }
+}
+
+/// Model entries for the core models whose files are on disk with the expected size. A legacy
+/// install doesn't say where its files came from, so a present file is taken to be the current one.
+fn models_on_disk(models_dir: &Path, models: &[ModelConfig]) -> Vec<ModelManifestEntry> {
+    models
+        .iter()
+        .filter(|m| {
+            let path = models_dir.join(&m.target_subdir).join(&m.target_filename);
+            fs::metadata(&path).is_ok_and(|meta| meta.is_file() && m.expected_size_bytes.map_or(true, |size| meta.len() == size))
+        })
+        .map(|m| ModelManifestEntry { id: m.id.clone(), fingerprint: model_fingerprint(m) })
+        .collect()
+}
+
+/// Revision a core node is installed at, in the form the manifest records it: the pinned revision
+/// when the checkout (or archive) matches it, otherwise whatever is actually there.
+async fn installed_node_revision(app_handle: &AppHandle<Wry>, node_dir: &Path, pinned: Option<&str>) -> Option<String> {
+    let pinned = pinned?;
+    if let Some(source) = read_archive_source(node_dir) {
+        return source.revision;
+    }
+    if !node_dir.join(".git").exists() {
+        return None;
+    }
+    match (resolve_revision(app_handle, node_dir, pinned).await, head_commit(app_handle, node_dir).await) {
+        (Ok(expected), Ok(head)) if expected == head => Some(pinned.to_string()),
+        (_, Ok(head)) => Some(head),
+        (_, Err(e)) => {
+            warn!("[INSTALL_MANIFEST] Could not read the checked out revision in {}: {}", node_dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Reconstructs the manifest of an install made before manifests existed from the model files,
+/// custom node directories and node revisions on disk, so an upgrade only installs the difference.
+/// The Python requirements hash is left unset, which reinstalls the requirements once and then
+/// replaces the legacy marker with a real manifest.
+async fn manifest_from_disk(app_handle: &AppHandle<Wry>) -> Result<InstallManifest, String> {
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+    let models = models_on_disk(&comfyui_dir.join("models"), &get_core_models_list());
+
+    let mut custom_nodes = Vec::new();
+    for node in CUSTOM_NODE_MANIFEST {
+        let node_dir = comfyui_dir.join("custom_nodes").join(node_import_target(node.name));
+        if !node_dir.exists() {
+            continue;
+        }
+        custom_nodes.push(CustomNodeManifestEntry {
+            name: node.name.to_string(),
+            repo_url: node.repo_url.to_string(),
+            revision: installed_node_revision(app_handle, &node_dir, node.revision).await,
+        });
+    }
+
+    Ok(InstallManifest {
+        format_version: MANIFEST_FORMAT_VERSION,
+        app_version: LEGACY_APP_VERSION.to_string(),
+        model_manifest_hash: hash_model_entries(&models),
+        models,
+        custom_nodes,
+        python_requirements_hash: None,
+        torch_variant: None,
+        env_backend: None, // Legacy installs were all conda
+        python_version: None,
+        completed_at: String::new(),
+    })
+}
+
+/// Describes a switch of env backend or Python version. Every component lives in the Python env,
+/// so that needs a full setup rather than a diff.
+fn environment_change(installed: &InstallManifest, expected: &InstallManifest) -> Option<String> {
+    let installed_backend = installed.env_backend.unwrap_or_default();
+    if expected.env_backend.is_some_and(|backend| backend != installed_backend) {
+        return Some(format!("Python environment backend changed ({:?} -> {:?})", installed_backend, expected.env_backend.unwrap_or_default()));
+    }
+    match (&installed.python_version, &expected.python_version) {
+        (Some(from), Some(to)) if from != to => Some(format!("Python version changed ({} -> {})", from, to)),
+        _ => None,
+    }
+}
+
+/// Lists every component in `expected` that is absent or different in `installed`.
+pub fn diff_manifests(installed: &InstallManifest, expected: &InstallManifest) -> Vec<MissingComponent> {
+    let mut missing = Vec::new();
+
+    if installed.model_manifest_hash != expected.model_manifest_hash {
+        for model in &expected.models {
+            match installed.models.iter().find(|m| m.id == model.id) {
+                None => missing.push(MissingComponent {
+                    kind: ComponentKind::Model,
+                    name: model.id.clone(),
+                    reason: "Not installed".to_string(),
+                }),
+                Some(m) if m.fingerprint != model.fingerprint => missing.push(MissingComponent {
+                    kind: ComponentKind::Model,
+                    name: model.id.clone(),
+                    reason: "Model source or target changed".to_string(),
+                }),
+                Some(_) => {}
+            }
+        }
+    }
+
+    for node in &expected.custom_nodes {
+        match installed.custom_nodes.iter().find(|n| n.name == node.name) {
+            None => missing.push(MissingComponent {
+                kind: ComponentKind::CustomNode,
+                name: node.name.clone(),
+                reason: "Not installed".to_string(),
+            }),
+            Some(n) if n.repo_url != node.repo_url || n.revision != node.revision => missing.push(MissingComponent {
+                kind: ComponentKind::CustomNode,
+                name: node.name.clone(),
+                reason: format!(
+                    "Revision changed ({} -> {})",
+                    n.revision.as_deref().unwrap_or("default branch"),
+                    node.revision.as_deref().unwrap_or("default branch")
+                ),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    // A missing expected hash means requirements.txt could not be read; don't force a reinstall for that.
+    let requirements_changed = expected.python_requirements_hash.is_some() && installed.python_requirements_hash != expected.python_requirements_hash;
+    // A new GPU or driver can call for a different torch build. Older manifests don't say which
+    // build is installed, so only a known, different variant counts.
+    let torch_change = match (&installed.torch_variant, &expected.torch_variant) {
+        (Some(installed_variant), Some(expected_variant)) if installed_variant != expected_variant => Some((installed_variant, expected_variant)),
+        _ => None,
+    };
+    if requirements_changed || torch_change.is_some() {
+        missing.push(MissingComponent {
+            kind: ComponentKind::PythonRequirements,
+            name: "comfyui_requirements".to_string(),
+            reason: match torch_change {
+                Some((from, to)) => format!("Torch build changed ({} -> {})", from, to),
+                None => "ComfyUI requirements.txt or Python lockfile changed".to_string(),
+            },
+        });
+    }
+
+    missing
+}
+
+/// Compares the master marker with what this app version expects.
+/// A legacy marker is compared using a manifest rebuilt from disk. A missing or unreadable marker
+/// (or a legacy one whose install can't be inspected) means we can't tell what is installed, so a
+/// full setup is planned.
+pub async fn compute_setup_plan(app_handle: &AppHandle<Wry>) -> Result<SetupPlan, String> {
+    let marker_path = get_master_marker_path(app_handle)?;
+    let marker = match read_master_marker(&marker_path) {
+        MarkerState::Legacy => match manifest_from_disk(app_handle).await {
+            Ok(installed) => {
+                info!("[INSTALL_MANIFEST] Legacy master marker found; comparing against the installed files.");
+                MarkerState::Manifest(installed)
+            }
+            Err(e) => {
+                warn!("[INSTALL_MANIFEST] Could not inspect the legacy install: {}", e);
+                MarkerState::Legacy
+            }
+        },
+        other => other,
+    };
+    plan_for_marker(marker, || build_expected_manifest(app_handle))
+}
+
+/// The setup plan for a marker state. `expected` is only built when there is a manifest to diff.
+fn plan_for_marker(marker: MarkerState, expected: impl FnOnce() -> Result<InstallManifest, String>) -> Result<SetupPlan, String> {
+    match marker {
+        MarkerState::Manifest(installed) => {
+            let expected = expected()?;
+            if installed.app_version != expected.app_version {
+                info!("[INSTALL_MANIFEST] Installed by app version {}, running {}.", installed.app_version, expected.app_version);
+            }
+            if let Some(reason) = environment_change(&installed, &expected) {
+                info!("[INSTALL_MANIFEST] {}; planning full setup.", reason);
+                return Ok(SetupPlan::Full(reason));
+            }
+            Ok(SetupPlan::Incremental(diff_manifests(&installed, &expected)))
+        }
+        MarkerState::Legacy => {
+            info!("[INSTALL_MANIFEST] Legacy master marker found; planning full setup.");
+            Ok(SetupPlan::Full("Installation was made by an older app version without an install manifest.".to_string()))
+        }
+        MarkerState::Missing => Ok(SetupPlan::Full("No installation found.".to_string())),
+        MarkerState::Unreadable(e) => {
+            warn!("[INSTALL_MANIFEST] {}. Planning full setup.", e);
+            Ok(SetupPlan::Full(e))
+        }
+    }
+}
+
+/// Writes the manifest for the current app version as the master marker.
+pub fn write_master_marker(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let marker_path = get_master_marker_path(app_handle)?;
+    if let Some(parent) = marker_path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data dir for master marker at {:?}: {}", parent, e))?;
+        }
+    }
+    let manifest = build_expected_manifest(app_handle)?;
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize install manifest: {}", e))?;
+    fs::write(&marker_path, json)
+        .map_err(|e| format!("Failed to write master installation marker at {:?}: {}", marker_path, e))?;
+    Ok(marker_path)
+}
+
+/// Drops a component from the installed manifest after it has been removed from disk,
+/// so the next startup reports it through `IncrementalSetupRequired`.
+/// Does nothing when the marker is missing or predates the manifest.
+pub fn forget_installed_component(app_handle: &AppHandle<Wry>, kind: ComponentKind, name: &str) -> Result<(), String> {
+    let marker_path = get_master_marker_path(app_handle)?;
+    let mut manifest = match read_master_marker(&marker_path) {
+        MarkerState::Manifest(m) => m,
+        _ => return Ok(()),
+    };
+    match kind {
+        ComponentKind::Model => {
+            manifest.models.retain(|m| m.id != name);
+            manifest.model_manifest_hash = hash_model_entries(&manifest.models);
+        }
+        ComponentKind::CustomNode => manifest.custom_nodes.retain(|n| n.name != name),
+        ComponentKind::PythonRequirements => manifest.python_requirements_hash = None,
+    }
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize install manifest: {}", e))?;
+    fs::write(&marker_path, json)
+        .map_err(|e| format!("Failed to update master installation marker at {:?}: {}", marker_path, e))?;
+    info!("[INSTALL_MANIFEST] Removed {:?} '{}' from the install manifest.", kind, name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> InstallManifest {
+        let models = vec![ModelManifestEntry { id: "sdxl_base".to_string(), fingerprint: "f1".to_string() }];
+        InstallManifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            app_version: "1.0.0".to_string(),
+            model_manifest_hash: hash_model_entries(&models),
+            models,
+            custom_nodes: vec![CustomNodeManifestEntry {
+                name: "ComfyUI-Impact-Pack".to_string(),
+                repo_url: "https://github.com/ltdrdata/ComfyUI-Impact-Pack".to_string(),
+                revision: Some("abc123".to_string()),
+            }],
+            python_requirements_hash: Some("req".to_string()),
+            torch_variant: Some("cu121".to_string()),
+            env_backend: Some(EnvBackendKind::Conda),
+            python_version: Some("3.10".to_string()),
+            completed_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn identical_manifests_have_no_differences() {
+        assert!(diff_manifests(&manifest(), &manifest()).is_empty());
+    }
+
+    #[test]
+    fn changed_node_revision_reinstalls_only_that_node() {
+        let installed = manifest();
+        let mut expected = manifest();
+        expected.custom_nodes[0].revision = Some("def456".to_string());
+
+        let missing = diff_manifests(&installed, &expected);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].kind, ComponentKind::CustomNode);
+        assert_eq!(missing[0].name, "ComfyUI-Impact-Pack");
+        assert_eq!(missing[0].reason, "Revision changed (abc123 -> def456)");
+    }
+
+    #[test]
+    fn changed_torch_variant_reinstalls_python_requirements() {
+        let installed = manifest();
+        let mut expected = manifest();
+        expected.torch_variant = Some("cu124".to_string());
+
+        let plan = SetupPlan::Incremental(diff_manifests(&installed, &expected));
+        assert!(plan.includes_python_requirements());
+        assert!(!plan.includes_custom_node("ComfyUI-Impact-Pack"));
+        match plan {
+            SetupPlan::Incremental(missing) => assert_eq!(missing[0].reason, "Torch build changed (cu121 -> cu124)"),
+            SetupPlan::Full(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn switched_env_backend_plans_full_setup() {
+        let mut expected = manifest();
+        expected.env_backend = Some(EnvBackendKind::Venv);
+        expected.python_version = Some("3.12".to_string());
+
+        let plan = plan_for_marker(MarkerState::Manifest(manifest()), || Ok(expected.clone())).unwrap();
+        assert!(matches!(plan, SetupPlan::Full(reason) if reason == "Python environment backend changed (Conda -> Venv)"));
+
+        // Manifests from before the venv backend don't record one; those envs are conda.
+        let mut installed = manifest();
+        installed.env_backend = None;
+        installed.python_version = None;
+        let plan = plan_for_marker(MarkerState::Manifest(installed), || Ok(expected)).unwrap();
+        assert!(plan.is_full());
+    }
+
+    #[test]
+    fn manifest_without_torch_variant_is_not_reinstalled() {
+        // Written before the torch build was recorded: the field deserializes to None.
+        let mut json = serde_json::to_value(manifest()).unwrap();
+        json.as_object_mut().unwrap().remove("torchVariant");
+        let installed: InstallManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(installed.torch_variant, None);
+        assert!(diff_manifests(&installed, &manifest()).is_empty());
+    }
+
+    #[test]
+    fn legacy_install_counts_only_complete_model_files() {
+        let dir = std::env::temp_dir().join(format!("install_manifest_models_test_{}", std::process::id()));
+        let model = |id: &str, size: Option<u64>| -> ModelConfig {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": id,
+                "url": format!("https://example.com/{}.safetensors", id),
+                "target_subdir": "checkpoints",
+                "target_filename": format!("{}.safetensors", id),
+                "expected_size_bytes": size,
+            }))
+            .unwrap()
+        };
+        let models = vec![model("present", Some(4)), model("truncated", Some(100)), model("absent", None)];
+        fs::create_dir_all(dir.join("checkpoints")).unwrap();
+        fs::write(dir.join("checkpoints").join("present.safetensors"), b"data").unwrap();
+        fs::write(dir.join("checkpoints").join("truncated.safetensors"), b"data").unwrap();
+
+        let installed = models_on_disk(&dir, &models);
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0], ModelManifestEntry { id: "present".to_string(), fingerprint: model_fingerprint(&models[0]) });
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn legacy_marker_without_manifest_plans_full_setup() {
+        let dir = std::env::temp_dir().join(format!("install_manifest_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join(MASTER_MARKER_FILENAME);
+
+        fs::write(&marker, format!("{}\n", LEGACY_MARKER_CONTENTS)).unwrap();
+        let state = read_master_marker(&marker);
+        assert!(matches!(state, MarkerState::Legacy));
+        // Only reached when the legacy install couldn't be inspected; there is nothing to diff
+        // against, so the expected manifest is never built.
+        let plan = plan_for_marker(state, || panic!("expected manifest built for a legacy marker")).unwrap();
+        assert!(plan.is_full());
+
+        fs::write(&marker, serde_json::to_string(&manifest()).unwrap()).unwrap();
+        let plan = plan_for_marker(read_master_marker(&marker), || Ok(manifest())).unwrap();
+        assert!(matches!(plan, SetupPlan::Incremental(missing) if missing.is_empty()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -12,12 +12,21 @@ pub mod model_orchestrator;
 pub mod custom_node_manager;
 pub mod python_utils;
 pub mod dependency_manager; // Added dependency_manager module
+pub mod install_manifest;
+pub mod deep_verification;
+pub mod component_reset;
+pub mod setup_journal;
+pub mod env_backend;
+pub mod env_snapshot;
+pub mod torch_probe;
 
 // Re-export key public functions and commands
 pub use orchestration::{
     get_setup_status_and_initialize,
     start_application_setup,
     retry_application_setup,
+    cancel_application_setup,
+    SetupTaskState,
 };
 
 pub use verification::{
@@ -70,9 +79,6 @@ pub use model_config::{
 
 // Re-export from custom_node_manager
 pub use custom_node_manager::{
-    clone_comfyui_impact_pack,
-    clone_comfyui_impact_subpack,
-    clone_comfyui_smz_nodes,
     clone_repository_to_custom_nodes,
     install_custom_node_dependencies,
 };
@@ -17,6 +17,8 @@ pub struct ModelConfig {
     #[serde(default)]
     pub expected_size_bytes: Option<u64>, // Optional: for a more robust check
     #[serde(default)]
+    pub expected_sha256: Option<String>, // Optional: checked by deep verification only, hashing large files is slow
+    #[serde(default)]
     pub model_type: ModelType, // Type of the model, used for special handling like extraction
     #[serde(default = "default_is_essential")]
     pub is_essential: bool, // Whether the model is essential for core functionality
@@ -39,6 +41,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "Metamorphosis_v3.safetensors".to_string(),
             downloaded_filename: None,
             expected_size_bytes: Some(6_938_374_086),
+            expected_sha256: None,
             model_type: ModelType::Checkpoint,
             is_essential: true,
         },
@@ -50,6 +53,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "clipseg_weights.pth".to_string(), // Renamed for convention
             downloaded_filename: Some("pytorch_model.bin".to_string()),
             expected_size_bytes: None, // Add if known
+            expected_sha256: None,
             model_type: ModelType::Generic, // Or a more specific type if applicable
             is_essential: true, // Assuming it's essential for the user's workflow
         },
@@ -61,6 +65,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "upernet_global_small.pth".to_string(),
             downloaded_filename: None,
             expected_size_bytes: None,
+            expected_sha256: None,
             model_type: ModelType::Generic,
             is_essential: true,
         },
@@ -72,6 +77,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "control-lora-depth-rank128.safetensors".to_string(),
             downloaded_filename: None,
             expected_size_bytes: None,
+            expected_sha256: None,
             model_type: ModelType::LoRA,
             is_essential: true,
         },
@@ -83,6 +89,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "control-lora-openposeXL2-rank256.safetensors".to_string(),
             downloaded_filename: None,
             expected_size_bytes: None,
+            expected_sha256: None,
             model_type: ModelType::LoRA,
             is_essential: true,
         },
@@ -94,6 +101,7 @@ pub fn get_core_models_list() -> Vec<ModelConfig> {
             target_filename: "control-lora-canny-rank128.safetensors".to_string(),
             downloaded_filename: None,
             expected_size_bytes: None,
+            expected_sha256: None,
             model_type: ModelType::LoRA,
             is_essential: true,
         },
@@ -3,6 +3,8 @@ use tauri::{AppHandle, Manager, Wry}; // Emitter might not be directly used here
 use log::{error, info, warn}; // Added warn
 use std::fs;
 use std::path::PathBuf; // Added this import
+use std::sync::Mutex;
+use uuid::Uuid;
 use tauri::Emitter; // Added Emitter
 
 /// Determines the application's root directory based on whether it's a debug or release build.
@@ -68,10 +70,19 @@ const MINICONDA_INSTALLER_MACOS_FILENAME: &str = "Miniconda3-latest-MacOSX-x86_6
 const MINICONDA_INSTALLER_MACOS_ARM64_FILENAME: &str = "Miniconda3-latest-MacOSX-arm64.pkg";
 const INSTALLERS_SUBDIR: &str = "resources/installers";
 pub(crate) const MINICONDA_INSTALL_DIR_NAME: &str = "miniconda3";
-const MINICONDA_INSTALLED_MARKER: &str = ".miniconda_installed.marker";
+pub(crate) const MINICONDA_INSTALLED_MARKER: &str = ".miniconda_installed.marker";
 
 use super::event_utils::emit_setup_progress;
 use super::types::SetupStatusEvent;
+use super::install_manifest::{compute_setup_plan, get_master_marker_path, write_master_marker, SetupPlan};
+use super::deep_verification::perform_deep_verification;
+use super::setup_journal::{self, JournalStatus};
+use super::custom_node_manager::workflow_requirements::ensure_workflow_runnable;
+use crate::character::character_generator::UNIFIED_WORKFLOW_JSON;
+use super::env_backend::{selected_backend, EnvBackendKind};
+use super::env_snapshot::take_env_snapshot;
+use super::torch_probe::{evaluate_torch_probe, run_torch_probe, TorchDeviceReport};
+use crate::settings::load_settings;
 // Updated verification imports
 use super::verification::{
     check_initialization_status, run_quick_verification, check_python_package_import
@@ -84,30 +95,81 @@ use crate::setup_manager::python_utils::{
     // get_script_path, // Not directly used here
     // get_vendor_path, // No longer directly used here, comfyui_directory_path is used
 };
-use crate::setup_manager::{get_core_models_list, download_and_place_models}; // Uncommented and added functions
+use crate::setup_manager::{get_core_models_list, download_and_place_models, get_final_model_path, ModelConfig}; // Uncommented and added functions
 use crate::setup_manager::custom_node_manager;
+use crate::setup_manager::custom_node_manager::node_definitions;
 use crate::setup_manager::dependency_manager; // Changed from crate::dependency_management
  
 // Note: comfyui_sidecar is kept as crate level for now.
 // If it is also refactored into managers, these paths would change.
 // use crate::comfyui_sidecar; // Removed direct import
 use crate::sidecar_manager::spawn_and_health_check_comfyui; // Imported function directly
+use crate::sidecar_manager::external_backend::is_external_mode;
 use crate::process_manager::ProcessManager;
 
 
 /// The main entry point command to determine setup status and initialize if necessary.
 #[tauri::command]
 pub async fn get_setup_status_and_initialize(app_handle: AppHandle<Wry>) -> Result<(), String> {
-    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
-    let master_marker_path = app_data_dir.join("metamorphosis_setup_complete.marker");
+    if is_external_mode(&app_handle) {
+        // Nothing is installed locally; readiness is decided by probing the external ComfyUI.
+        info!("[SETUP_ORCHESTRATION] External backend mode. Skipping installation checks.");
+        app_handle.emit("setup_status", SetupStatusEvent::BackendFullyVerifiedAndReady).map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let master_marker_path = get_master_marker_path(&app_handle)?;
 
     if master_marker_path.exists() {
         info!("[SETUP_ORCHESTRATION] Master Installation Marker found at {}. Performing quick verification.", master_marker_path.display());
         match run_quick_verification(&app_handle).await {
             Ok(true) => {
-                info!("[SETUP_ORCHESTRATION] Quick verification PASSED.");
-                app_handle.emit("setup_status", SetupStatusEvent::BackendFullyVerifiedAndReady).map_err(|e| e.to_string())?;
-                info!("[SETUP_ORCHESTRATION] Emitted BackendFullyVerifiedAndReady.");
+                info!("[SETUP_ORCHESTRATION] Quick verification PASSED. Comparing install manifest with this app version.");
+                let plan = match compute_setup_plan(&app_handle).await {
+                    Ok(plan) => plan,
+                    Err(e) => {
+                        // Without a plan we can't tell what is installed; the setup run will check everything.
+                        error!("[SETUP_ORCHESTRATION] Could not compare the install manifest: {}", e);
+                        app_handle.emit("setup_status", SetupStatusEvent::FullSetupRequired { reason: format!("Could not compare the installation with this app version: {}", e) }).map_err(|e| e.to_string())?;
+                        info!("[SETUP_ORCHESTRATION] Emitted FullSetupRequired (reason: setup plan error).");
+                        return Ok(());
+                    }
+                };
+                match plan {
+                    SetupPlan::Incremental(missing) if missing.is_empty() => {
+                        if load_settings(&app_handle).deep_verification_on_startup {
+                            info!("[SETUP_ORCHESTRATION] Deep verification on startup is enabled. Running it now.");
+                            // An error running the checks counts as a failed report: the backend can't be trusted.
+                            let failure = match perform_deep_verification(&app_handle).await {
+                                Ok(report) if report.passed => None,
+                                Ok(report) => Some(format!("Deep verification failed: {}", report.failed_checks().iter().map(|c| c.name.clone()).collect::<Vec<_>>().join(", "))),
+                                Err(e) => {
+                                    error!("[SETUP_ORCHESTRATION] Deep verification could not run: {}", e);
+                                    Some(format!("Deep verification could not run: {}", e))
+                                }
+                            };
+                            if let Some(reason) = failure {
+                                // Deleting the marker makes the next setup run check every component, not just the manifest diff.
+                                if let Err(e) = fs::remove_file(&master_marker_path) {
+                                    error!("[SETUP_ORCHESTRATION] Failed to delete master marker file at {}: {}", master_marker_path.display(), e);
+                                }
+                                app_handle.emit("setup_status", SetupStatusEvent::FullSetupRequired { reason }).map_err(|e| e.to_string())?;
+                                info!("[SETUP_ORCHESTRATION] Emitted FullSetupRequired (reason: deep verification failed).");
+                                return Ok(());
+                            }
+                        }
+                        app_handle.emit("setup_status", SetupStatusEvent::BackendFullyVerifiedAndReady).map_err(|e| e.to_string())?;
+                        info!("[SETUP_ORCHESTRATION] Emitted BackendFullyVerifiedAndReady.");
+                    }
+                    SetupPlan::Incremental(missing) => {
+                        info!("[SETUP_ORCHESTRATION] Install manifest is outdated. Missing components: {:?}", missing);
+                        app_handle.emit("setup_status", SetupStatusEvent::IncrementalSetupRequired { missing }).map_err(|e| e.to_string())?;
+                        info!("[SETUP_ORCHESTRATION] Emitted IncrementalSetupRequired.");
+                    }
+                    SetupPlan::Full(reason) => {
+                        info!("[SETUP_ORCHESTRATION] Emitting FullSetupRequired (reason: {}).", reason);
+                        app_handle.emit("setup_status", SetupStatusEvent::FullSetupRequired { reason }).map_err(|e| e.to_string())?;
+                    }
+                }
             }
             Ok(false) => {
                 info!("[SETUP_ORCHESTRATION] Quick verification FAILED. Invalidating master marker.");
@@ -187,26 +249,110 @@ pub async fn get_setup_status_and_initialize(app_handle: AppHandle<Wry>) -> Resu
 }
 
 
+/// Process group tag for every child process spawned by a setup run, so cancellation can kill them.
+const SETUP_PROCESS_GROUP: &str = "setup";
+
+/// Tracks the setup run in progress, if any. Managed by Tauri.
+pub struct SetupTaskState {
+    current: Mutex<Option<ActiveSetup>>,
+}
+
+struct ActiveSetup {
+    run_id: String,
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl SetupTaskState {
+    pub fn new() -> Self {
+        Self { current: Mutex::new(None) }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current.lock().unwrap().is_some()
+    }
+}
+
 /// Start the application setup process
 #[tauri::command]
 pub async fn start_application_setup(app_handle: AppHandle<Wry>) -> Result<(), String> {
+    if is_external_mode(&app_handle) {
+        return Err("Setup is not needed in external ComfyUI mode.".to_string());
+    }
+    let setup_state = app_handle.state::<SetupTaskState>();
+    let mut current = setup_state.current.lock().unwrap();
+    if let Some(active) = current.as_ref() {
+        warn!("[SETUP_ORCHESTRATION] Rejecting setup start: run {} is still in progress.", active.run_id);
+        return Err("Setup is already running.".to_string());
+    }
+
+    let run_id = Uuid::new_v4().to_string();
+    info!("[SETUP_ORCHESTRATION] Starting setup run {}", run_id);
+    setup_journal::begin_run(&app_handle, &run_id);
+
     // Spawn the setup process in the background
     let handle_clone = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
-        if let Err(e) = orchestrate_full_setup(handle_clone.clone()).await { // Clone handle_clone for orchestrate_full_setup
-            error!("Full setup orchestration failed: {}", e);
-            // Notify the frontend of the error using the new helper
-             emit_setup_progress(
-                &handle_clone, // Use the cloned handle for emitting error
-                "error",
-                "Critical Setup Error",
-                0,
-                Some("The application setup encountered a critical error and could not complete.".to_string()),
-                Some(e.clone()), // Send the error message
-            );
+    let run_id_for_task = run_id.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        let result = ProcessManager::run_in_group(SETUP_PROCESS_GROUP, orchestrate_full_setup(handle_clone.clone())).await;
+        match result {
+            Ok(_) => {
+                setup_journal::finish_run(&handle_clone, &run_id_for_task, JournalStatus::Completed, None);
+                // The env just passed verification; keep it as a known-good rollback point.
+                if let Err(e) = take_env_snapshot(&handle_clone, "setup completed").await {
+                    warn!("[SETUP_ORCHESTRATION] Failed to snapshot the environment after setup: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Full setup orchestration failed: {}", e);
+                setup_journal::finish_run(&handle_clone, &run_id_for_task, JournalStatus::Failed, Some(e.clone()));
+                // Notify the frontend of the error using the new helper
+                emit_setup_progress(
+                    &handle_clone, // Use the cloned handle for emitting error
+                    "error",
+                    "Critical Setup Error",
+                    0,
+                    Some("The application setup encountered a critical error and could not complete.".to_string()),
+                    Some(e.clone()), // Send the error message
+                );
+            }
+        }
+
+        // Clear the active run, unless it was already taken by a cancellation.
+        let setup_state = handle_clone.state::<SetupTaskState>();
+        let mut current = setup_state.current.lock().unwrap();
+        if current.as_ref().map_or(false, |a| a.run_id == run_id_for_task) {
+            *current = None;
         }
     });
-    
+
+    *current = Some(ActiveSetup { run_id, handle });
+    Ok(())
+}
+
+/// Cancel the running setup: aborts the orchestration task, kills the child processes it started
+/// (conda, pip, git, ...) and closes the journal as cancelled.
+#[tauri::command]
+pub async fn cancel_application_setup(app_handle: AppHandle<Wry>) -> Result<(), String> {
+    let active = app_handle.state::<SetupTaskState>().current.lock().unwrap().take();
+    let active = active.ok_or_else(|| "No setup is running.".to_string())?;
+
+    let step = setup_journal::current_step_name(&app_handle).unwrap_or_else(|| "unknown".to_string());
+    info!("[SETUP_ORCHESTRATION] Cancelling setup run {} during step '{}'", active.run_id, step);
+
+    // Abort first so the orchestration can't move on to spawn the next process once we kill the current one.
+    active.handle.abort();
+    let killed = app_handle.state::<ProcessManager>().stop_process_group(SETUP_PROCESS_GROUP);
+    info!("[SETUP_ORCHESTRATION] Killed {} setup process(es): {:?}", killed.len(), killed);
+
+    setup_journal::finish_run(&app_handle, &active.run_id, JournalStatus::Cancelled, Some(format!("Cancelled by user during step '{}'", step)));
+    emit_setup_progress(
+        &app_handle,
+        "error",
+        "Setup Cancelled",
+        0,
+        Some("Setup was cancelled. You can retry at any time.".to_string()),
+        Some("Setup cancelled by user.".to_string()),
+    );
     Ok(())
 }
 
@@ -228,8 +374,17 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
         info!("[SETUP_ORCHESTRATION] Pre-existing ComfyUI sidecar stop attempt complete.");
     }
 
+    // Work out whether this is a fresh install or an upgrade that only needs the components
+    // added or changed since the install manifest was written.
+    let plan = compute_setup_plan(&app_handle).await?;
+    match &plan {
+        SetupPlan::Full(reason) => info!("[SETUP_ORCHESTRATION] Running full setup: {}", reason),
+        SetupPlan::Incremental(missing) => info!("[SETUP_ORCHESTRATION] Running incremental setup for: {:?}", missing),
+    }
+
     // Phase: Miniconda Setup (0-20%)
     emit_setup_progress(&app_handle, "installing_miniconda", "Checking Miniconda installation", 0, Some("Verifying Miniconda environment...".to_string()), None);
+    setup_journal::begin_step(&app_handle, "installing_miniconda");
 
     let app_root_path = get_app_root_path()?;
     let miniconda_install_path = app_root_path.join(MINICONDA_INSTALL_DIR_NAME);
@@ -241,7 +396,12 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
         miniconda_install_path.join("bin").join("conda")
     };
 
-    if miniconda_marker_path.exists() {
+    let env_backend = selected_backend(&app_handle);
+    if env_backend == EnvBackendKind::Venv {
+        // The venv is created from the bundled interpreter during python_setup; Miniconda is not needed.
+        info!("[SETUP_ORCHESTRATION] Venv environment backend selected. Skipping Miniconda installation.");
+        emit_setup_progress(&app_handle, "installing_miniconda", "Miniconda not required", 20, Some("Using the bundled Python interpreter.".to_string()), None);
+    } else if miniconda_marker_path.exists() {
         if conda_exe_path.exists() {
             info!("[SETUP_ORCHESTRATION] Miniconda marker found at {} and conda executable exists. Skipping installation.", miniconda_marker_path.display());
             emit_setup_progress(&app_handle, "installing_miniconda", "Miniconda already installed", 20, Some("Miniconda is already set up.".to_string()), None);
@@ -376,6 +536,7 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
 
     // Phase 1: Checking (Initial system checks, disk space etc.) (20-30%)
     emit_setup_progress(&app_handle, "checking", "Running system checks", 20, Some("Checking system requirements and environment...".to_string()), None);
+    setup_journal::begin_step(&app_handle, "checking");
     
     let main_window = app_handle.get_webview_window("main").ok_or_else(|| {
         let msg = "Failed to get main window for initial checks".to_string();
@@ -394,10 +555,22 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
  
      // Phase 2 & 3: Python Environment & ComfyUI Dependencies (30-60%)
      emit_setup_progress(&app_handle, "python_setup", "Setting up Python environment", 30, Some("Initializing Python virtual environment and dependencies...".to_string()), None);
+     setup_journal::begin_step(&app_handle, "python_setup");
  
  
      // Check if Python environment is already set up and dependencies are installed
-     match super::verification::check_python_environment_integrity(&app_handle).await {
+     // An incremental run with changed requirements must reinstall even if the current env imports fine.
+     let python_env_check = if !plan.is_full() && plan.includes_python_requirements() {
+         info!("[SETUP_ORCHESTRATION] ComfyUI requirements changed since last install. Forcing dependency re-installation.");
+         let deps_marker_path = get_comfyui_directory_path(&app_handle)?.join(".conda_env_deps_installed.marker");
+         if deps_marker_path.exists() {
+             fs::remove_file(&deps_marker_path).map_err(|e| format!("Failed to remove dependency marker {}: {}", deps_marker_path.display(), e))?;
+         }
+         Ok(false)
+     } else {
+         super::verification::check_python_environment_integrity(&app_handle).await
+     };
+     match python_env_check {
          Ok(true) => {
              info!("[SETUP_ORCHESTRATION] Python environment and dependencies already verified. Skipping installation.");
              emit_setup_progress(&app_handle, "python_setup", "Python environment ready", 60, Some("Python environment and dependencies are already set up.".to_string()), None);
@@ -427,95 +600,31 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
       // Phase: Installing Custom Nodes (60-80%)
      // This phase is added before model downloading, as custom nodes might define model locations or types.
      emit_setup_progress(&app_handle, "installing_custom_nodes", "Setting up custom nodes", 60, Some("Cloning and installing required custom nodes...".to_string()), None);
+     setup_journal::begin_step(&app_handle, "installing_custom_nodes");
      
  
-       // Install ComfyUI-Impact-Pack
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI-Impact-Pack...");
-       match custom_node_manager::clone_comfyui_impact_pack(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI-Impact-Pack cloned successfully or already exists and dependencies checked/installed.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "Impact Pack Setup Complete", 68, Some("ComfyUI-Impact-Pack processed.".to_string()), None);
-           }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI-Impact-Pack: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "Impact Pack Setup Failed", 64, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI-Impact-Pack failing: {}", e);
-           }
-       }
-   
-       // Install ComfyUI-Impact-Subpack
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI-Impact-Subpack...");
-       match custom_node_manager::clone_comfyui_impact_subpack(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI-Impact-Subpack cloned successfully or already exists and dependencies checked/installed.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "Impact Subpack Setup Complete", 70, Some("ComfyUI-Impact-Subpack processed.".to_string()), None);
-           }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI-Impact-Subpack: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "Impact Subpack Setup Failed", 68, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI-Impact-Subpack failing: {}", e);
-           }
-       }
-   
-       // Install ComfyUI_smZNodes
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI_smZNodes...");
-       match custom_node_manager::clone_comfyui_smz_nodes(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI_smZNodes cloned successfully or already exists.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "smZNodes Setup Complete", 72, Some("ComfyUI_smZNodes processed.".to_string()), None);
-           }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI_smZNodes: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "smZNodes Setup Failed", 70, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI_smZNodes failing: {}", e);
-           }
-       }
-   
-       // Install ComfyUI_ControlNet_Aux
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI_ControlNet_Aux...");
-       match custom_node_manager::clone_comfyui_controlnet_aux(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI_ControlNet_Aux cloned successfully or already exists and dependencies checked/installed.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "ControlNet Aux Setup Complete", 74, Some("ComfyUI_ControlNet_Aux processed.".to_string()), None);
-           }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI_ControlNet_Aux: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "ControlNet Aux Setup Failed", 72, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI_ControlNet_Aux failing: {}", e);
-           }
-       }
-   
-       // Install ComfyUI-CLIPSeg
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI-CLIPSeg...");
-       match custom_node_manager::clone_comfyui_clipseg(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI-CLIPSeg cloned and clipseg.py moved successfully or already exists.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "CLIPSeg Setup Complete", 76, Some("ComfyUI-CLIPSeg processed.".to_string()), None);
-           }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI-CLIPSeg: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "CLIPSeg Setup Failed", 74, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI-CLIPSeg failing: {}", e);
-           }
-       }
-   
-       // Install ComfyUI-RMBG
-       info!("[SETUP_ORCHESTRATION] Attempting to clone ComfyUI-RMBG...");
-       match custom_node_manager::clone_comfyui_rmbg(&app_handle).await {
-           Ok(_) => {
-               info!("ComfyUI-RMBG cloned successfully or already exists and dependencies checked/installed.");
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "RMBG Setup Complete", 78, Some("ComfyUI-RMBG processed.".to_string()), None);
+       // Install each core custom node the install manifest says is missing or outdated.
+       // Progress advances from 62% to 78% across the manifest; a failed node is reported but does
+       // not stop setup.
+       let node_count = node_definitions::CUSTOM_NODE_MANIFEST.len() as u64;
+       for (index, node) in node_definitions::CUSTOM_NODE_MANIFEST.iter().enumerate() {
+           let node_progress = (62 + (index as u64 + 1) * 16 / node_count) as u8;
+           if !plan.includes_custom_node(node.name) {
+               info!("[SETUP_ORCHESTRATION] {} is up to date with the install manifest. Skipping.", node.name);
+               continue;
            }
-           Err(e) => {
-               let err_msg = format!("Failed to setup ComfyUI-RMBG: {}", e);
-               error!("{}", err_msg);
-               emit_setup_progress(&app_handle, "installing_custom_nodes", "RMBG Setup Failed", 76, Some(err_msg.clone()), Some(e.to_string()));
-               warn!("Continuing setup despite ComfyUI-RMBG failing: {}", e);
+           info!("[SETUP_ORCHESTRATION] Attempting to install {}...", node.name);
+           match custom_node_manager::install_custom_node(&app_handle, node).await {
+               Ok(_) => {
+                   info!("{} installed successfully or already exists and dependencies checked/installed.", node.name);
+                   emit_setup_progress(&app_handle, "installing_custom_nodes", &format!("{} Setup Complete", node.name), node_progress, Some(format!("{} processed.", node.name)), None);
+               }
+               Err(e) => {
+                   let err_msg = format!("Failed to setup {}: {}", node.name, e);
+                   error!("{}", err_msg);
+                   emit_setup_progress(&app_handle, "installing_custom_nodes", &format!("{} Setup Failed", node.name), node_progress, Some(err_msg.clone()), Some(e.to_string()));
+                   warn!("Continuing setup despite {} failing: {}", node.name, e);
+               }
            }
        }
    
@@ -525,6 +634,7 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
    
          // Phase 3.5: Verification of Custom Nodes and Dependencies (80-85%)
         emit_setup_progress(&app_handle, "verifying_dependencies", "Verifying installations", 80, Some("Verifying custom node and Python package installations...".to_string()), None);
+        setup_journal::begin_step(&app_handle, "verifying_dependencies");
      
         let comfyui_dir_for_verify = get_comfyui_directory_path(&app_handle).map_err(|e| {
             let err_msg = format!("Failed to get ComfyUI directory for verification: {}", e);
@@ -553,11 +663,39 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
                 return Err(err_msg); // Halting setup
             }
         }
+
+        // Check that torch can actually use the detected GPU. A mismatch is not fatal (ComfyUI still
+        // runs on CPU), so warn and let the frontend offer a reinstall via the mismatch event.
+        match run_torch_probe(&app_handle, &venv_python_exe_for_verify, &comfyui_dir_for_verify).await {
+            Ok(probe) => match evaluate_torch_probe(&app_handle, probe).await {
+                Ok(TorchDeviceReport { mismatch: Some(mismatch), .. }) => {
+                    setup_journal::record_note(&app_handle, "torch_device_mismatch", &mismatch.message);
+                    emit_setup_progress(&app_handle, "verifying_dependencies", "PyTorch cannot use the GPU", 85, Some(mismatch.message.clone()), None);
+                }
+                Ok(report) => {
+                    emit_setup_progress(&app_handle, "verifying_dependencies", "PyTorch device check passed", 85, Some(report.probe.summary()), None);
+                }
+                Err(e) => warn!("[SETUP_ORCHESTRATION] Could not compare torch probe with detected GPU: {}", e),
+            },
+            Err(e) => warn!("[SETUP_ORCHESTRATION] Torch device probe failed: {}", e),
+        }
+
+        // Custom node failures above are only warnings, so check here that every node pack the
+        // character workflow uses actually made it, rather than failing on the first generation.
+        let unified_workflow: serde_json::Value = serde_json::from_str(UNIFIED_WORKFLOW_JSON)
+            .map_err(|e| format!("Failed to parse bundled workflow: {}", e))?;
+        if let Err(e) = ensure_workflow_runnable(&app_handle, &unified_workflow).await {
+            error!("[SETUP_ORCHESTRATION] {}", e);
+            emit_setup_progress(&app_handle, "error", "Required Node Packs Missing", 0, Some(e.clone()), Some(e.clone()));
+            return Err(e);
+        }
+        emit_setup_progress(&app_handle, "verifying_dependencies", "Workflow node packs present", 85, Some("All node packs used by the character workflow are installed.".to_string()), None);
    
         // End of Verification Phase
    
         // Phase 4: Downloading Models (85-95%)
         emit_setup_progress(&app_handle, "downloading_models", "Downloading AI models", 85, Some("Starting download of core AI models...".to_string()), None);
+        setup_journal::begin_step(&app_handle, "downloading_models");
    
         // Determine ComfyUI models base path
         let comfyui_dir_for_models = get_comfyui_directory_path(&app_handle)?;
@@ -572,9 +710,32 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
         }
         
         let core_models = get_core_models_list();
+        // On an incremental run, only models that are new or changed since the last install are fetched.
+        let changed_models: Vec<ModelConfig> = if plan.is_full() {
+            Vec::new()
+        } else {
+            core_models.iter().filter(|m| plan.includes_model(&m.id)).cloned().collect()
+        };
         if core_models.is_empty() {
             info!("[SETUP_ORCHESTRATION] No core models configured for download.");
             emit_setup_progress(&app_handle, "downloading_models", "No models to download", 95, Some("No core AI models configured for download.".to_string()), None);
+        } else if !changed_models.is_empty() {
+            info!("[SETUP_ORCHESTRATION] Downloading {} new or changed model(s).", changed_models.len());
+            for model in &changed_models {
+                // The downloader skips files that already exist, so remove stale copies of changed models first.
+                let final_path = get_final_model_path(&comfyui_models_base_path, model)?;
+                if final_path.exists() {
+                    if let Err(e) = fs::remove_file(&final_path) {
+                        warn!("[SETUP_ORCHESTRATION] Failed to remove outdated model file {}: {}", final_path.display(), e);
+                    }
+                }
+            }
+            if let Err(e) = download_and_place_models(app_handle.clone(), &changed_models, &comfyui_models_base_path).await {
+                let err_msg = format!("Failed to download one or more changed models: {}", e);
+                error!("{}", err_msg);
+                emit_setup_progress(&app_handle, "error", "Model Download Failed", 0, Some(err_msg.clone()), Some(e.to_string()));
+                return Err(err_msg);
+            }
         } else {
             // Check if core models already exist and are verified
             match super::verification::check_core_models_exist(&app_handle).await {
@@ -604,10 +765,18 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
         }
    
         // Phase 5: Finalizing (Starting ComfyUI Sidecar and Health Check) (95-100%)
+        setup_journal::begin_step(&app_handle, "finalizing");
         if !comfyui_was_already_running_and_assumed_healthy {
             info!("[SETUP_ORCHESTRATION] ComfyUI was not already running or assumed healthy. Starting ComfyUI services...");
             emit_setup_progress(&app_handle, "finalizing", "Starting ComfyUI services", 95, Some("Launching and verifying ComfyUI backend...".to_string()), None);
-            match spawn_and_health_check_comfyui(&app_handle).await { // Changed call to use direct import
+            // Start the sidecar on its own task. This one runs inside the "setup" process group, and a
+            // sidecar tagged with it would be killed along with the installers when setup is cancelled.
+            let sidecar_app_handle = app_handle.clone();
+            let sidecar_result = tauri::async_runtime::spawn(async move { spawn_and_health_check_comfyui(&sidecar_app_handle).await })
+                .await
+                .map_err(|e| format!("ComfyUI start task failed: {}", e))
+                .and_then(|result| result);
+            match sidecar_result {
                 Ok(_) => {
                     info!("ComfyUI services started and healthy.");
                     emit_setup_progress(&app_handle, "finalizing", "ComfyUI services ready", 100, Some("ComfyUI backend is running and responsive.".to_string()), None);
@@ -625,24 +794,10 @@ async fn orchestrate_full_setup(app_handle: AppHandle<Wry>) -> Result<(), String
         }
    
         // Phase 6: Complete
-        // Create Master Installation Marker File
-        let app_data_dir = app_handle.path().app_data_dir().map_err(|e| {
-            let err_msg = format!("Failed to get app data dir for master marker: {}", e);
-            error!("{}", err_msg);
-            err_msg
-        })?;
-        if !app_data_dir.exists() {
-            fs::create_dir_all(&app_data_dir).map_err(|e| {
-               let err_msg = format!("Failed to create app data dir for master marker at {:?}: {}", app_data_dir, e);
-               error!("{}", err_msg);
-               err_msg
-           })?;
-       }
-       let master_marker_path = app_data_dir.join("metamorphosis_setup_complete.marker");
-       fs::write(&master_marker_path, "setup_completed_successfully").map_err(|e| {
-           let err_msg = format!("Failed to write master installation marker at {:?}: {}", master_marker_path, e);
-           error!("{}", err_msg);
-           err_msg
+        // Create Master Installation Marker File, recording the installed components as a manifest
+       let master_marker_path = write_master_marker(&app_handle).map_err(|e| {
+           error!("{}", e);
+           e
        })?;
        info!("Master Installation Marker File created at {}", master_marker_path.display());
     
@@ -5,6 +5,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 use log::{info, error, debug};
 use crate::setup_manager::event_utils::emit_event;
+use crate::setup_manager::env_backend::{get_venv_path, selected_backend, venv_python_path, EnvBackendKind};
 use tauri::{AppHandle, Wry, Manager};
 use serde_json::json;
 use std::env; // Added for env! macro
@@ -70,6 +71,23 @@ pub fn get_comfyui_directory_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf
     }
 }
 
+/// Version of the ComfyUI frontend the build script bundles (see build_logic/frontend_installer.rs).
+pub const BUNDLED_FRONTEND_VERSION: &str = env!("COMFYUI_FRONTEND_VERSION");
+// Holds the version of the frontend in the bundled directory; written by the build script.
+const BUNDLED_FRONTEND_VERSION_MARKER: &str = ".frontend_version";
+
+/// Returns the path to the bundled ComfyUI frontend (`vendor/comfyui_frontend`), passed to ComfyUI
+/// as `--front-end-root` so it doesn't download the frontend at startup.
+pub fn get_bundled_frontend_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    Ok(get_vendor_path(app_handle)?.join("comfyui_frontend"))
+}
+
+/// Whether the bundled frontend is present and is the version this build expects.
+pub fn bundled_frontend_is_valid(frontend_path: &Path) -> bool {
+    let version = std::fs::read_to_string(frontend_path.join(BUNDLED_FRONTEND_VERSION_MARKER)).unwrap_or_default();
+    frontend_path.join("index.html").is_file() && version.trim() == BUNDLED_FRONTEND_VERSION
+}
+
 /// Returns the absolute path to the bundled Python executable.
 pub fn get_bundled_python_executable_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
     let vendor_path = get_vendor_path(app_handle)?; // Will be non-canonicalized in debug
@@ -123,10 +141,17 @@ pub async fn get_conda_executable_path(app_handle: &AppHandle<Wry>) -> Result<Pa
     }
 }
 
-/// Returns the absolute path to the Python executable within the specified Conda environment.
-/// This function assumes that the `conda` executable is found and uses its location
-/// to infer the Miniconda installation root and then the environment's Python path.
+/// Returns the absolute path to the Python executable of ComfyUI's environment.
+/// With the Conda backend this is the python of the named Conda environment, inferred from the
+/// Miniconda installation root. With the Venv backend `env_name` is ignored and the venv's
+/// python is returned.
 pub async fn get_conda_env_python_executable_path(app_handle: &AppHandle<Wry>, env_name: &str) -> Result<PathBuf, String> {
+    if selected_backend(app_handle) == EnvBackendKind::Venv {
+        let venv_python = venv_python_path(&get_venv_path()?);
+        info!("Determined venv Python executable path: {}", venv_python.display());
+        return Ok(venv_python);
+    }
+
     let app_root_path = crate::setup_manager::orchestration::get_app_root_path()?;
     let miniconda_install_path = app_root_path.join(crate::setup_manager::orchestration::MINICONDA_INSTALL_DIR_NAME);
 
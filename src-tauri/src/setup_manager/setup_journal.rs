@@ -0,0 +1,176 @@
+// metamorphosis-app/src-tauri/src/setup_manager/setup_journal.rs
+//
+// A small on-disk record of the most recent setup run: which steps started, finished, failed or
+// were cancelled, plus notes such as the torch variant chosen. It survives crashes, so after an
+// interrupted run we can tell which step was in progress.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+const SETUP_JOURNAL_FILENAME: &str = "setup_journal.json";
+
+// Serializes read-modify-write cycles on the journal file.
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JournalStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStep {
+    pub name: String,
+    pub status: JournalStatus,
+    pub started_at: String,
+    #[serde(default)]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupJournal {
+    pub run_id: String,
+    pub status: JournalStatus,
+    pub started_at: String,
+    #[serde(default)]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub detail: Option<String>,
+    pub steps: Vec<JournalStep>,
+    // Free-form key/value facts recorded during the run, e.g. the resolved torch variant.
+    #[serde(default)]
+    pub notes: Vec<(String, String)>,
+}
+
+impl SetupJournal {
+    pub fn current_step(&self) -> Option<&JournalStep> {
+        self.steps.iter().rev().find(|s| s.status == JournalStatus::Running)
+    }
+
+    fn close_running_steps(&mut self, status: JournalStatus, detail: Option<String>) {
+        let now = chrono::Utc::now().to_rfc3339();
+        for step in self.steps.iter_mut().filter(|s| s.status == JournalStatus::Running) {
+            step.status = status;
+            step.finished_at = Some(now.clone());
+            if detail.is_some() {
+                step.detail = detail.clone();
+            }
+        }
+    }
+}
+
+fn get_journal_path(app_handle: &AppHandle<Wry>) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(SETUP_JOURNAL_FILENAME))
+}
+
+pub fn read_journal(app_handle: &AppHandle<Wry>) -> Option<SetupJournal> {
+    let path = get_journal_path(app_handle).ok()?;
+    let contents = fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_journal(app_handle: &AppHandle<Wry>, journal: &SetupJournal) -> Result<(), String> {
+    let path = get_journal_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create app data dir {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(journal).map_err(|e| format!("Failed to serialize setup journal: {}", e))?;
+    // Write to a temp file and rename so a crash never leaves a half-written journal.
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write setup journal {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to replace setup journal {}: {}", path.display(), e))
+}
+
+/// Applies `update` to the journal of the current run, if any. Journal errors are logged, never
+/// propagated: a setup run must not fail because its bookkeeping could not be written.
+fn update_journal<F: FnOnce(&mut SetupJournal)>(app_handle: &AppHandle<Wry>, update: F) {
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut journal = match read_journal(app_handle) {
+        Some(j) => j,
+        None => return,
+    };
+    update(&mut journal);
+    if let Err(e) = write_journal(app_handle, &journal) {
+        error!("[SETUP_JOURNAL] {}", e);
+    }
+}
+
+/// Starts a new journal for a setup run, replacing the previous one.
+pub fn begin_run(app_handle: &AppHandle<Wry>, run_id: &str) {
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(previous) = read_journal(app_handle) {
+        if previous.status == JournalStatus::Running {
+            info!("[SETUP_JOURNAL] Previous run {} was interrupted during step {:?}.", previous.run_id, previous.current_step().map(|s| s.name.clone()));
+        }
+    }
+    let journal = SetupJournal {
+        run_id: run_id.to_string(),
+        status: JournalStatus::Running,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        detail: None,
+        steps: Vec::new(),
+        notes: Vec::new(),
+    };
+    if let Err(e) = write_journal(app_handle, &journal) {
+        error!("[SETUP_JOURNAL] {}", e);
+    }
+}
+
+/// Marks `step` as started. Any step still running is considered completed.
+pub fn begin_step(app_handle: &AppHandle<Wry>, step: &str) {
+    update_journal(app_handle, |journal| {
+        journal.close_running_steps(JournalStatus::Completed, None);
+        journal.steps.push(JournalStep {
+            name: step.to_string(),
+            status: JournalStatus::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            detail: None,
+        });
+    });
+}
+
+/// Records a fact about the current run, replacing an earlier note with the same key.
+pub fn record_note(app_handle: &AppHandle<Wry>, key: &str, value: &str) {
+    update_journal(app_handle, |journal| {
+        journal.notes.retain(|(k, _)| k != key);
+        journal.notes.push((key.to_string(), value.to_string()));
+    });
+}
+
+/// Name of the step currently running, if a run is in progress.
+pub fn current_step_name(app_handle: &AppHandle<Wry>) -> Option<String> {
+    read_journal(app_handle).and_then(|j| j.current_step().map(|s| s.name.clone()))
+}
+
+/// Closes the run. Only a running journal is updated, so whichever of completion, failure or
+/// cancellation happens first wins.
+pub fn finish_run(app_handle: &AppHandle<Wry>, run_id: &str, status: JournalStatus, detail: Option<String>) {
+    update_journal(app_handle, |journal| {
+        if journal.run_id != run_id || journal.status != JournalStatus::Running {
+            return;
+        }
+        journal.close_running_steps(status, detail.clone());
+        journal.status = status;
+        journal.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        journal.detail = detail;
+    });
+}
+
+#[tauri::command]
+pub async fn get_setup_journal(app_handle: AppHandle<Wry>) -> Result<Option<SetupJournal>, String> {
+    Ok(read_journal(&app_handle))
+}
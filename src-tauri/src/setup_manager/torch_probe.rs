@@ -0,0 +1,184 @@
+// metamorphosis-app/src-tauri/src/setup_manager/torch_probe.rs
+//
+// Checks that the installed torch can actually use the GPU we detected. A machine with an NVIDIA
+// card but a CPU-only torch otherwise runs in slow mode without anyone noticing. The probe runs
+// in the env after it is built (and as part of deep verification); on a mismatch we emit a
+// structured warning that the frontend can turn into a "reinstall torch" offer.
+
+use std::path::Path;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Wry};
+
+use crate::gpu_detection::{get_gpu_info, GpuInfo};
+use super::component_reset::ensure_backend_idle;
+use super::deep_verification::run_probe_script;
+use super::dependency_manager::command_runner::run_command_for_setup_progress;
+use super::dependency_manager::torch_resolver::{resolve_torch_for_host, TorchResolution, TorchVariant};
+use super::env_backend::{torch_install_command, COMFYUI_ENV_NAME};
+use super::env_snapshot::snapshot_before_change;
+use super::python_utils::{get_comfyui_directory_path, get_conda_env_python_executable_path};
+use super::setup_journal;
+
+pub const EVT_TORCH_DEVICE_MISMATCH: &str = "torch_device_mismatch";
+const TORCH_PROBE_RESULT_PREFIX: &str = "TORCH_PROBE_RESULT ";
+
+const TORCH_DEVICE_PROBE_SCRIPT: &str = r#"
+import json, torch
+result = {
+    "version": torch.__version__,
+    "cudaAvailable": torch.cuda.is_available(),
+    "cudaBuild": torch.version.cuda,
+    "hipBuild": getattr(torch.version, "hip", None),
+    "mpsAvailable": bool(hasattr(torch.backends, "mps") and torch.backends.mps.is_available()),
+    "xpuAvailable": bool(hasattr(torch, "xpu") and torch.xpu.is_available()),
+    "deviceName": None,
+    "vramBytes": None,
+}
+if result["cudaAvailable"]:
+    props = torch.cuda.get_device_properties(0)
+    result["deviceName"] = props.name
+    result["vramBytes"] = props.total_memory
+elif result["xpuAvailable"]:
+    props = torch.xpu.get_device_properties(0)
+    result["deviceName"] = props.name
+    result["vramBytes"] = getattr(props, "total_memory", None)
+elif result["mpsAvailable"]:
+    result["deviceName"] = "Apple MPS"
+print("TORCH_PROBE_RESULT " + json.dumps(result))
+"#;
+
+/// What torch reports about itself inside the env.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TorchProbeResult {
+    pub version: String,
+    pub cuda_available: bool,
+    pub cuda_build: Option<String>,
+    pub hip_build: Option<String>, // Set on ROCm builds, which also report cuda_available
+    pub mps_available: bool,
+    pub xpu_available: bool,
+    pub device_name: Option<String>,
+    pub vram_bytes: Option<u64>,
+}
+
+impl TorchProbeResult {
+    /// Whether torch can use the accelerator the given build targets.
+    fn supports(&self, variant: TorchVariant) -> bool {
+        match variant {
+            TorchVariant::Cuda124 | TorchVariant::Cuda121 | TorchVariant::Cuda118 => self.cuda_available && self.hip_build.is_none(),
+            TorchVariant::Rocm62 => self.cuda_available && self.hip_build.is_some(),
+            TorchVariant::Xpu => self.xpu_available,
+            TorchVariant::Mps => self.mps_available,
+            TorchVariant::Cpu => true,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "torch {}, cuda={}, rocm={}, mps={}, xpu={}, device={}",
+            self.version,
+            self.cuda_available,
+            self.hip_build.is_some(),
+            self.mps_available,
+            self.xpu_available,
+            self.device_name.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+/// Payload of `torch_device_mismatch`: the GPU we detected, the build we expected, and what torch reports.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TorchDeviceMismatch {
+    pub gpu: GpuInfo,
+    pub expected: TorchResolution,
+    pub probe: TorchProbeResult,
+    pub message: String,
+    pub can_reinstall: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TorchDeviceReport {
+    pub probe: TorchProbeResult,
+    pub mismatch: Option<TorchDeviceMismatch>,
+}
+
+/// Runs the probe script with the given python.
+pub async fn run_torch_probe(app_handle: &AppHandle<Wry>, python_exe: &Path, cwd: &Path) -> Result<TorchProbeResult, String> {
+    let json = run_probe_script(app_handle, python_exe, cwd, "torch_device_probe.py", TORCH_DEVICE_PROBE_SCRIPT, &[], TORCH_PROBE_RESULT_PREFIX).await?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse torch probe result: {}", e))
+}
+
+/// Compares the probe with the build the resolver picks for the detected GPU.
+pub fn compare_with_gpu(probe: &TorchProbeResult, gpu: &GpuInfo, expected: &TorchResolution) -> Option<TorchDeviceMismatch> {
+    if probe.supports(expected.variant) {
+        return None;
+    }
+    Some(TorchDeviceMismatch {
+        gpu: gpu.clone(),
+        expected: expected.clone(),
+        probe: probe.clone(),
+        message: format!(
+            "{:?} GPU detected ({}), but the installed torch cannot use it ({}). Generation will run on CPU.",
+            gpu.gpu_type,
+            expected.variant.tag(),
+            probe.summary()
+        ),
+        can_reinstall: true,
+    })
+}
+
+/// Compares a probe with the detected GPU and emits `torch_device_mismatch` on a mismatch.
+pub async fn evaluate_torch_probe(app_handle: &AppHandle<Wry>, probe: TorchProbeResult) -> Result<TorchDeviceReport, String> {
+    let gpu = tokio::task::spawn_blocking(get_gpu_info).await.map_err(|e| e.to_string())?;
+    let expected = tokio::task::spawn_blocking(resolve_torch_for_host).await.map_err(|e| e.to_string())?;
+    info!("[TORCH_PROBE] {}", probe.summary());
+
+    let mismatch = compare_with_gpu(&probe, &gpu, &expected);
+    if let Some(mismatch) = &mismatch {
+        warn!("[TORCH_PROBE] {}", mismatch.message);
+        if let Err(e) = app_handle.emit(EVT_TORCH_DEVICE_MISMATCH, mismatch) {
+            error!("[TORCH_PROBE] Failed to emit {}: {}", EVT_TORCH_DEVICE_MISMATCH, e);
+        }
+    }
+    Ok(TorchDeviceReport { probe, mismatch })
+}
+
+/// Probes torch in the ComfyUI env and compares the result with the detected GPU.
+pub async fn check_torch_device(app_handle: &AppHandle<Wry>) -> Result<TorchDeviceReport, String> {
+    let python_exe = get_conda_env_python_executable_path(app_handle, COMFYUI_ENV_NAME).await?;
+    let comfyui_dir = get_comfyui_directory_path(app_handle)?;
+    let probe = run_torch_probe(app_handle, &python_exe, &comfyui_dir).await?;
+    evaluate_torch_probe(app_handle, probe).await
+}
+
+#[tauri::command]
+pub async fn run_torch_device_probe(app_handle: AppHandle<Wry>) -> Result<TorchDeviceReport, String> {
+    check_torch_device(&app_handle).await
+}
+
+/// Force-reinstalls the torch build the resolver picks for this machine, then probes again.
+#[tauri::command]
+pub async fn reinstall_torch_variant(app_handle: AppHandle<Wry>) -> Result<TorchDeviceReport, String> {
+    ensure_backend_idle(&app_handle)?;
+    let phase = "reinstalling_torch";
+    let resolution = tokio::task::spawn_blocking(resolve_torch_for_host).await.map_err(|e| e.to_string())?;
+    info!("[TORCH_PROBE] Reinstalling torch variant {} ({})", resolution.variant.tag(), resolution.reason);
+
+    snapshot_before_change(&app_handle, &format!("reinstalling torch ({})", resolution.variant.tag())).await;
+    let (installer, args) = torch_install_command(&app_handle, resolution.variant, true).await?;
+    let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let comfyui_dir = get_comfyui_directory_path(&app_handle)?;
+    run_command_for_setup_progress(
+        &app_handle, phase, "Reinstalling PyTorch", 0, 100,
+        &installer, &args_refs,
+        &comfyui_dir,
+        &format!("Installing the {} build of PyTorch...", resolution.variant.tag()), "Failed to reinstall PyTorch",
+    ).await?;
+
+    setup_journal::record_note(&app_handle, "torch_variant", resolution.variant.tag());
+    check_torch_device(&app_handle).await
+}
+
@@ -2,6 +2,9 @@
 use serde::Serialize;
 use serde::Deserialize;
 
+use super::dependency_manager::install_progress::InstallProgressSnapshot;
+use super::install_manifest::MissingComponent;
+
 // Unified Setup Progress Payload
 #[derive(Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -15,6 +18,16 @@ pub struct SetupProgressPayload {
     pub error: Option<String>,
 }
 
+// Package-level progress of a pip/uv/conda command, emitted alongside setup-progress
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPackageProgressPayload {
+    pub phase: String,
+    pub step: String,
+    #[serde(flatten)]
+    pub snapshot: InstallProgressSnapshot,
+}
+
 // Setup phases (kept for reference, but string literals will be used in emit_setup_progress)
 #[derive(Debug, Clone, serde::Serialize)]
 pub enum SetupPhase {
@@ -77,6 +90,8 @@ impl Default for ModelType {
 pub enum SetupStatusEvent {
     BackendFullyVerifiedAndReady,
     FullSetupRequired { reason: String },
+    // Installed from an older manifest; only the listed components need installing.
+    IncrementalSetupRequired { missing: Vec<MissingComponent> },
 }
 
 // Payloads for Custom Node Cloning Events
@@ -11,10 +11,14 @@ use crate::process_manager::ProcessManager;
 
 // Import new python_utils functions
 use crate::setup_manager::python_utils::{
+    bundled_frontend_is_valid,
+    get_bundled_frontend_path,
     get_comfyui_directory_path,
     get_conda_env_python_executable_path,
+    BUNDLED_FRONTEND_VERSION,
 };
 use crate::setup_manager::orchestration::get_app_root_path; // Import get_app_root_path
+use crate::setup_manager::env_backend::{selected_backend, EnvBackendKind};
 
 // use super::types::SetupStatusEvent;
 
@@ -118,7 +122,10 @@ pub async fn check_initialization_status(window: WebviewWindow) -> Result<(), St
     let miniconda_install_path = app_root_path.join("miniconda3");
     let miniconda_marker_path = app_root_path.join(".miniconda_installed.marker");
 
-    if miniconda_install_path.exists() && miniconda_install_path.is_dir() && miniconda_marker_path.exists() && miniconda_marker_path.is_file() {
+    if selected_backend(&app_handle) == EnvBackendKind::Venv {
+        info!("[SETUP_VERIFICATION] Venv environment backend selected; Miniconda is not required.");
+        window.emit("initialization-status", json!({ "status": "progress", "stage": "CheckingMiniconda", "progress": 50, "message": "Using the bundled Python interpreter." })).map_err(|e| e.to_string())?;
+    } else if miniconda_install_path.exists() && miniconda_install_path.is_dir() && miniconda_marker_path.exists() && miniconda_marker_path.is_file() {
         info!("[SETUP_VERIFICATION] Miniconda installation verified at {:?}", miniconda_install_path);
         window.emit("initialization-status", json!({ "status": "progress", "stage": "CheckingMiniconda", "progress": 50, "message": "Miniconda installation found." })).map_err(|e| e.to_string())?;
     } else {
@@ -214,6 +221,21 @@ pub async fn run_quick_verification(app_handle: &AppHandle<Wry>) -> Result<bool,
     }
     info!("[QUICK VERIFY] PASSED: main.py exists.");
 
+    // 5. Check for the bundled ComfyUI frontend (vendor/comfyui_frontend), used for offline launches.
+    // Not fatal: it ships with the app, so setup can't restore it, and the launch falls back to
+    // ComfyUI's default frontend without it.
+    let frontend_path = get_bundled_frontend_path(app_handle)?;
+    info!("[QUICK VERIFY] Checking for bundled ComfyUI frontend v{} at {}", BUNDLED_FRONTEND_VERSION, frontend_path.display());
+    if bundled_frontend_is_valid(&frontend_path) {
+        info!("[QUICK VERIFY] PASSED: bundled ComfyUI frontend found.");
+    } else {
+        warn!(
+            "[QUICK VERIFY] WARNING: bundled ComfyUI frontend v{} missing or outdated at {}; ComfyUI will use its default frontend.",
+            BUNDLED_FRONTEND_VERSION,
+            frontend_path.display()
+        );
+    }
+
     // ComfyUI sidecar start and health check will be handled by a subsequent command
     // after SplashScreen receives BackendFullyVerifiedAndReady.
     info!("[QUICK VERIFY] All essential file existence checks passed.");
@@ -0,0 +1,163 @@
+// metamorphosis-app/src-tauri/src/sidecar_manager/endpoint.rs
+//
+// Where the ComfyUI sidecar listens. The port comes from settings (8188 by default); when that
+// port is already taken at launch, e.g. by a ComfyUI the user started themselves, a free port is
+// picked instead so we never end up queueing prompts on someone else's server.
+// The port actually in use is kept in ACTIVE_PORT. Every Rust caller builds its URLs through
+// `comfyui_url`, and the frontend gets the same values from `get_comfyui_endpoint`.
+// In external backend mode those URLs point at the user's own ComfyUI instead.
+
+use std::net::TcpListener;
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, Url, Wry};
+use tauri_plugin_http::reqwest;
+use tokio::time::Duration;
+
+use super::event_utils::emit_backend_status;
+use super::external_backend::BackendMode;
+use crate::process_manager::ProcessManager;
+use crate::settings::load_settings;
+
+pub const DEFAULT_COMFYUI_PORT: u16 = 8188;
+// Address the sidecar is told to listen on with `--listen`; ports are checked against it.
+// Loopback only: other machines reach ComfyUI through the token-protected LAN proxy, if enabled.
+pub const COMFYUI_LISTEN_HOST: &str = "127.0.0.1";
+
+// Port of the current (or last) sidecar launch. None until the first launch resolved one.
+static ACTIVE_PORT: Lazy<Mutex<Option<u16>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ComfyUiEndpoint {
+    pub port: u16,
+    pub base_url: String,
+    pub ws_url: String,
+}
+
+/// The port the sidecar is (or will be) reachable on: the launched port, else the configured one.
+pub fn comfyui_port(app_handle: &AppHandle<Wry>) -> u16 {
+    ACTIVE_PORT.lock().unwrap().unwrap_or_else(|| load_settings(app_handle).comfyui_port)
+}
+
+/// The user's ComfyUI URL without a trailing slash, when external backend mode is selected.
+pub fn external_base_url(app_handle: &AppHandle<Wry>) -> Option<String> {
+    let settings = load_settings(app_handle);
+    if settings.backend_mode != BackendMode::External {
+        return None;
+    }
+    settings.external_comfyui_url.map(|url| url.trim().trim_end_matches('/').to_string()).filter(|url| !url.is_empty())
+}
+
+pub fn comfyui_base_url(app_handle: &AppHandle<Wry>) -> String {
+    external_base_url(app_handle).unwrap_or_else(|| format!("http://127.0.0.1:{}", comfyui_port(app_handle)))
+}
+
+/// Full URL for an API path, e.g. `comfyui_url(app, "/prompt")`.
+pub fn comfyui_url(app_handle: &AppHandle<Wry>, path: &str) -> String {
+    format!("{}{}", comfyui_base_url(app_handle), path)
+}
+
+pub fn comfyui_endpoint(app_handle: &AppHandle<Wry>) -> ComfyUiEndpoint {
+    if let Some(base_url) = external_base_url(app_handle) {
+        let port = Url::parse(&base_url).ok().and_then(|url| url.port_or_known_default()).unwrap_or_default();
+        // http -> ws, https -> wss
+        let ws_url = format!("{}/ws", base_url.replacen("http", "ws", 1));
+        return ComfyUiEndpoint { port, base_url, ws_url };
+    }
+    let port = comfyui_port(app_handle);
+    ComfyUiEndpoint {
+        port,
+        base_url: format!("http://127.0.0.1:{}", port),
+        ws_url: format!("ws://127.0.0.1:{}/ws", port),
+    }
+}
+
+/// Origin the webview loads the app from, passed to ComfyUI as its only CORS origin.
+/// ComfyUI rejects requests whose Origin differs from a loopback Host unless a CORS origin is set,
+/// which would block the app's own WebSocket.
+pub fn webview_origin(app_handle: &AppHandle<Wry>) -> String {
+    if cfg!(dev) {
+        if let Some(dev_url) = app_handle.config().build.dev_url.as_ref() {
+            return dev_url.origin().ascii_serialization();
+        }
+    }
+    if cfg!(windows) {
+        "http://tauri.localhost".to_string()
+    } else {
+        "tauri://localhost".to_string()
+    }
+}
+
+pub(super) fn port_is_free(port: u16) -> bool {
+    TcpListener::bind((COMFYUI_LISTEN_HOST, port)).is_ok()
+}
+
+/// A port the OS reports as unused right now.
+fn find_free_port() -> Result<u16, String> {
+    let listener = TcpListener::bind((COMFYUI_LISTEN_HOST, 0)).map_err(|e| format!("Failed to find a free port: {}", e))?;
+    listener.local_addr().map(|addr| addr.port()).map_err(|e| format!("Failed to read the free port: {}", e))
+}
+
+/// Picks the port for the next sidecar launch and makes it the active one: the configured port
+/// when it is free, otherwise any free port.
+pub fn resolve_launch_port(app_handle: &AppHandle<Wry>) -> Result<u16, String> {
+    let configured = load_settings(app_handle).comfyui_port;
+    let port = if port_is_free(configured) {
+        info!("[PORT CHECK] Configured port {} is available.", configured);
+        configured
+    } else {
+        let free = find_free_port()?;
+        warn!("[PORT CHECK] Configured port {} is already in use. Starting ComfyUI on port {} instead.", configured, free);
+        emit_backend_status(app_handle, "port_reassigned", format!("Port {} is in use; starting ComfyUI on port {}.", configured, free), false);
+        free
+    };
+    set_active_port(app_handle, port);
+    Ok(port)
+}
+
+/// Makes `port` the one URLs are built with and tells the frontend.
+pub(crate) fn set_active_port(app_handle: &AppHandle<Wry>, port: u16) {
+    *ACTIVE_PORT.lock().unwrap() = Some(port);
+    if let Err(e) = app_handle.emit("comfyui-endpoint", comfyui_endpoint(app_handle)) {
+        warn!("Failed to emit comfyui-endpoint event: {}", e);
+    }
+}
+
+/// Checks that the server answering on the active port is the sidecar we launched: our child must
+/// still be alive (if it had failed to bind, it would have exited), and `/system_stats` must
+/// report a ComfyUI started with `--port <our port>`.
+pub async fn verify_sidecar_identity(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    let port = comfyui_port(app_handle);
+    if !app_handle.state::<ProcessManager>().is_process_running("comfyui_sidecar") {
+        return Err(format!("Something is answering on port {}, but the ComfyUI sidecar is not running.", port));
+    }
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let stats: Value = client
+        .get(comfyui_url(app_handle, "/system_stats"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to query /system_stats: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid /system_stats response: {}", e))?;
+    let Some(argv) = stats.pointer("/system/argv").and_then(|a| a.as_array()) else {
+        warn!("[PORT CHECK] /system_stats has no argv (older ComfyUI); relying on the child process check.");
+        return Ok(());
+    };
+    let args: Vec<&str> = argv.iter().filter_map(|a| a.as_str()).collect();
+    let port_arg = port.to_string();
+    if args.windows(2).any(|pair| pair[0] == "--port" && pair[1] == port_arg) {
+        Ok(())
+    } else {
+        Err(format!("The server on port {} is not the ComfyUI sidecar we launched (argv: {}).", port, args.join(" ")))
+    }
+}
+
+#[tauri::command]
+pub async fn get_comfyui_endpoint(app_handle: AppHandle<Wry>) -> Result<ComfyUiEndpoint, String> {
+    Ok(comfyui_endpoint(&app_handle))
+}
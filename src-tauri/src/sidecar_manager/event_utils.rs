@@ -4,8 +4,6 @@ use tauri::{AppHandle, Emitter, Wry};
 use log::error;
 use serde_json::json;
 
-pub const COMFYUI_PORT: u16 = 8188; // TODO: Make this configurable
-
 // Helper to emit backend status
 pub fn emit_backend_status(app_handle: &AppHandle<Wry>, status: &str, message: String, is_error: bool) {
     if let Err(e) = app_handle.emit("backend-status", json!({
@@ -0,0 +1,252 @@
+// metamorphosis-app/src-tauri/src/sidecar_manager/external_backend.rs
+//
+// External backend mode: instead of installing and spawning the ComfyUI sidecar, the app talks to
+// a ComfyUI the user already runs, on this machine or on another workstation. Setup (Miniconda,
+// the Python env, custom nodes, models) is skipped entirely, so before the app reports ready a
+// capability probe asks the server's `/object_info` whether the node packs and model files the
+// workflow needs are there.
+// All request URLs go through `endpoint::comfyui_url`, which returns the external base URL in
+// this mode, so generation, workflow checks and the frontend need no mode-specific code.
+// The webview's HTTP client is only allowed to reach loopback by the static capabilities; the
+// configured external URL, and nothing else, is added at runtime by `allow_external_backend_url`.
+// (The CSP's `ws:` source, which also matches `wss:`, already covers the WebSocket.)
+
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::ipc::CapabilityBuilder;
+use tauri::{AppHandle, Emitter, Manager, Url, Wry};
+use tauri_plugin_http::reqwest;
+use tokio::time::Duration;
+
+use super::endpoint::{comfyui_endpoint, external_base_url};
+use super::event_utils::emit_backend_status;
+use crate::character::character_generator::UNIFIED_WORKFLOW_JSON;
+use crate::settings::load_settings;
+use crate::setup_manager::custom_node_manager::workflow_requirements::{resolve_class_types, workflow_class_types, WorkflowRequirements};
+
+// Input values with these extensions name model files, which loaders offer as a list of choices.
+const MODEL_FILE_EXTENSIONS: &[&str] = &[".safetensors", ".ckpt", ".pt", ".pth", ".bin", ".onnx", ".gguf", ".sft"];
+
+// Set while the external health monitor runs, so repeated readiness checks don't start more.
+static EXTERNAL_MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+// External URLs the webview's HTTP client was allowed to reach this session.
+static ALLOWED_EXTERNAL_URLS: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
+/// Where ComfyUI comes from. Changing it takes effect the next time the backend is started.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum BackendMode {
+    #[default]
+    Managed,  // Installed by setup and run as a sidecar
+    External, // A ComfyUI at `external_comfyui_url`, not managed by the app
+}
+
+pub fn is_external_mode(app_handle: &AppHandle<Wry>) -> bool {
+    load_settings(app_handle).backend_mode == BackendMode::External
+}
+
+/// A model file the workflow references that the server doesn't offer.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingModelFile {
+    pub class_type: String,
+    pub input: String,
+    pub file: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalCapabilityReport {
+    pub base_url: String,
+    pub node_packs: WorkflowRequirements,
+    pub missing_models: Vec<MissingModelFile>,
+    pub ready: bool,
+}
+
+impl ExternalCapabilityReport {
+    fn describe_problems(&self) -> String {
+        let mut problems = Vec::new();
+        if !self.node_packs.is_satisfied() {
+            problems.push(self.node_packs.describe_problems());
+        }
+        if !self.missing_models.is_empty() {
+            let files: Vec<&str> = self.missing_models.iter().map(|m| m.file.as_str()).collect();
+            problems.push(format!("missing models: {}", files.join(", ")));
+        }
+        problems.join("; ")
+    }
+}
+
+pub(crate) fn validate_base_url(base_url: &str) -> Result<(), String> {
+    let url = Url::parse(base_url).map_err(|e| format!("Invalid external ComfyUI URL '{}': {}", base_url, e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!("External ComfyUI URL must use http or https, not '{}'.", scheme)),
+    }
+}
+
+/// Lets the frontend's HTTP client reach the configured external ComfyUI by adding a runtime
+/// capability scoped to that URL. Does nothing in managed mode. Runtime capabilities can't be
+/// revoked, so a URL allowed earlier stays allowed until the app restarts.
+pub fn allow_external_backend_url(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    let Some(base_url) = external_base_url(app_handle) else { return Ok(()) };
+    validate_base_url(&base_url)?;
+    let mut allowed = ALLOWED_EXTERNAL_URLS.lock().unwrap();
+    if allowed.contains(&base_url) {
+        return Ok(());
+    }
+    let capability = CapabilityBuilder::new(format!("external-comfyui-{}", allowed.len()))
+        .window("main")
+        .permission_scoped("http:default", vec![json!({ "url": format!("{}/*", base_url) })], Vec::<Value>::new());
+    app_handle
+        .add_capability(capability)
+        .map_err(|e| format!("Failed to allow requests to external ComfyUI at {}: {}", base_url, e))?;
+    info!("[EXTERNAL_BACKEND] Allowed frontend requests to {}", base_url);
+    allowed.insert(base_url);
+    Ok(())
+}
+
+/// Model files referenced by an API-format workflow that `/object_info` doesn't list as choices
+/// for the input they are set on.
+fn missing_model_files(workflow: &Value, object_info: &Value) -> Vec<MissingModelFile> {
+    let mut missing = Vec::new();
+    let Some(nodes) = workflow.as_object() else { return missing };
+    for node in nodes.values() {
+        let (Some(class_type), Some(inputs)) = (node.get("class_type").and_then(|c| c.as_str()), node.get("inputs").and_then(|i| i.as_object())) else {
+            continue;
+        };
+        for (input, value) in inputs {
+            let Some(file) = value.as_str() else { continue };
+            if !MODEL_FILE_EXTENSIONS.iter().any(|ext| file.to_ascii_lowercase().ends_with(ext)) {
+                continue;
+            }
+            // Combo inputs are described as `[[choice, ...], {options}]`.
+            let choices = ["required", "optional"].iter().find_map(|section| {
+                object_info.get(class_type)?.get("input")?.get(section)?.get(input)?.get(0)?.as_array()
+            });
+            let Some(choices) = choices else { continue };
+            if !choices.iter().any(|c| c.as_str() == Some(file)) {
+                let entry = MissingModelFile { class_type: class_type.to_string(), input: input.clone(), file: file.to_string() };
+                if !missing.contains(&entry) {
+                    missing.push(entry);
+                }
+            }
+        }
+    }
+    missing
+}
+
+/// Checks that the ComfyUI at `base_url` answers and has what the app's workflow needs.
+pub async fn probe_external_comfyui(base_url: &str) -> Result<ExternalCapabilityReport, String> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    validate_base_url(base_url)?;
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(15)).build().map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let queue = client.get(format!("{}/queue", base_url)).send().await.map_err(|e| format!("External ComfyUI at {} is not reachable: {}", base_url, e))?;
+    if !queue.status().is_success() {
+        return Err(format!("External ComfyUI at {} answered /queue with HTTP {}.", base_url, queue.status()));
+    }
+    let object_info: Value = client
+        .get(format!("{}/object_info", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to query /object_info on {}: {}", base_url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid /object_info response from {}: {}", base_url, e))?;
+    let loaded_classes: BTreeSet<String> = object_info.as_object().map(|o| o.keys().cloned().collect()).unwrap_or_default();
+
+    let workflow: Value = serde_json::from_str(UNIFIED_WORKFLOW_JSON).map_err(|e| format!("Failed to parse the bundled workflow: {}", e))?;
+    // Nothing is installed locally in this mode, so only the server's classes count.
+    let node_packs = resolve_class_types(&workflow_class_types(&workflow), None, Some(&loaded_classes));
+    let missing_models = missing_model_files(&workflow, &object_info);
+    let ready = node_packs.is_satisfied() && missing_models.is_empty();
+    info!(
+        "[EXTERNAL_BACKEND] Probed {}: {} node classes, missing packs: {:?}, missing models: {:?}",
+        base_url,
+        loaded_classes.len(),
+        node_packs.missing_packs.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        missing_models.iter().map(|m| m.file.as_str()).collect::<Vec<_>>()
+    );
+    Ok(ExternalCapabilityReport { base_url: base_url.to_string(), node_packs, missing_models, ready })
+}
+
+/// The external-mode counterpart of spawning the sidecar: probes the configured server and, if it
+/// has everything, reports the backend ready and starts monitoring it.
+pub async fn connect_external_backend(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    let Some(base_url) = external_base_url(app_handle) else {
+        let err_msg = "External ComfyUI mode is selected, but no ComfyUI URL is set.".to_string();
+        emit_backend_status(app_handle, "backend_error", err_msg.clone(), true);
+        return Err(err_msg);
+    };
+    emit_backend_status(app_handle, "checking_external", format!("Connecting to ComfyUI at {}...", base_url), false);
+    if let Err(e) = allow_external_backend_url(app_handle) {
+        error!("[EXTERNAL_BACKEND] {}", e);
+        emit_backend_status(app_handle, "backend_error", e.clone(), true);
+        return Err(e);
+    }
+
+    let report = probe_external_comfyui(&base_url).await.map_err(|e| {
+        error!("[EXTERNAL_BACKEND] {}", e);
+        emit_backend_status(app_handle, "backend_error", e.clone(), true);
+        e
+    })?;
+    if !report.ready {
+        let err_msg = format!("ComfyUI at {} cannot run the app's workflow: {}.", base_url, report.describe_problems());
+        error!("[EXTERNAL_BACKEND] {}", err_msg);
+        emit_backend_status(app_handle, "backend_error", err_msg.clone(), true);
+        return Err(err_msg);
+    }
+
+    if let Err(e) = app_handle.emit("comfyui-endpoint", comfyui_endpoint(app_handle)) {
+        warn!("Failed to emit comfyui-endpoint event: {}", e);
+    }
+    if let Err(e) = app_handle.emit("comfyui-fully-healthy", ()) {
+        error!("[EXTERNAL_BACKEND] Failed to emit comfyui-fully-healthy event: {}", e);
+    }
+    emit_backend_status(app_handle, "backend_ready", format!("Connected to ComfyUI at {}.", base_url), false);
+
+    if !EXTERNAL_MONITOR_RUNNING.swap(true, Ordering::SeqCst) {
+        tauri::async_runtime::spawn(monitor_external_comfyui(app_handle.clone()));
+    }
+    Ok(())
+}
+
+/// Polls the external server. There is no process to restart, so this only reports the server
+/// going away and coming back. Stops once the app is switched back to managed mode.
+async fn monitor_external_comfyui(app_handle: AppHandle<Wry>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(30));
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().unwrap_or_default();
+    let mut healthy = true;
+    info!("[EXTERNAL_BACKEND] Starting health monitoring.");
+    loop {
+        interval.tick().await;
+        let Some(base_url) = external_base_url(&app_handle) else { break };
+        let ok = matches!(client.get(format!("{}/queue", base_url)).send().await, Ok(response) if response.status().is_success());
+        if ok && !healthy {
+            info!("[EXTERNAL_BACKEND] {} is reachable again.", base_url);
+            emit_backend_status(&app_handle, "backend_ready", format!("Reconnected to ComfyUI at {}.", base_url), false);
+        } else if !ok && healthy {
+            error!("[EXTERNAL_BACKEND] {} stopped responding.", base_url);
+            emit_backend_status(&app_handle, "backend_error", format!("ComfyUI at {} is not responding.", base_url), true);
+        }
+        healthy = ok;
+    }
+    info!("[EXTERNAL_BACKEND] No longer in external mode; health monitoring stopped.");
+    EXTERNAL_MONITOR_RUNNING.store(false, Ordering::SeqCst);
+}
+
+/// Probes a ComfyUI server without connecting to it, e.g. to test a URL before saving it.
+/// Without `base_url`, the configured external URL is used.
+#[tauri::command]
+pub async fn check_external_comfyui(app_handle: AppHandle<Wry>, base_url: Option<String>) -> Result<ExternalCapabilityReport, String> {
+    let base_url = base_url
+        .or_else(|| load_settings(&app_handle).external_comfyui_url)
+        .ok_or_else(|| "No external ComfyUI URL given or configured.".to_string())?;
+    probe_external_comfyui(&base_url).await
+}
@@ -10,10 +10,11 @@ use std::future::Future;
 use std::error::Error as StdError; // Alias to avoid conflict
 
 // Internal imports from sibling modules
-use super::event_utils::{emit_backend_status, COMFYUI_PORT};
+use super::event_utils::emit_backend_status;
+use super::endpoint::{comfyui_url, verify_sidecar_identity};
 use crate::process_manager::ProcessManager;
 use super::process_handler::{
-    RESTART_ATTEMPTS, LAST_RESTART_TIME, MAX_RESTARTS_PER_HOUR,
+    RESTART_ATTEMPTS, LAST_RESTART_TIME, MAX_RESTARTS_PER_HOUR, IS_ATTEMPTING_SPAWN, IS_RESTARTING,
     spawn_comfyui_process,
 };
 
@@ -21,7 +22,7 @@ use super::process_handler::{
 // This function is intended for internal use by orchestration functions.
 pub(super) async fn perform_comfyui_health_check(app_handle: AppHandle<Wry>) -> Result<(), String> {
     info!("Performing initial ComfyUI health check...");
-    let health_check_url = format!("http://localhost:{}/queue", COMFYUI_PORT);
+    let health_check_url = comfyui_url(&app_handle, "/queue");
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(10))
         .build()
@@ -36,6 +37,11 @@ pub(super) async fn perform_comfyui_health_check(app_handle: AppHandle<Wry>) ->
             Ok(response) => {
                 if response.status().is_success() {
                     info!("ComfyUI initial health check successful.");
+                    if let Err(e) = verify_sidecar_identity(&app_handle).await {
+                        error!("{}", e);
+                        emit_backend_status(&app_handle, "backend_error", e.clone(), true);
+                        return Err(e);
+                    }
                     if let Err(e) = app_handle.emit("comfyui-fully-healthy", ()) {
                          error!("Failed to emit comfyui-fully-healthy event: {}", e);
                     }
@@ -83,6 +89,10 @@ pub async fn monitor_comfyui_health(app_handle: AppHandle<Wry>) {
         let is_running = process_manager.is_process_running("comfyui_sidecar");
 
         if !is_running {
+            if *IS_ATTEMPTING_SPAWN.lock().unwrap() || *IS_RESTARTING.lock().unwrap() {
+                // A deliberate (re)start is in progress, e.g. after a launch profile change.
+                continue;
+            }
             info!("ComfyUI process is not running, attempting to restart...");
             let mut attempts_lock = RESTART_ATTEMPTS.lock().unwrap();
             let mut last_restart_lock = LAST_RESTART_TIME.lock().unwrap();
@@ -122,7 +132,7 @@ pub async fn monitor_comfyui_health(app_handle: AppHandle<Wry>) {
             continue; 
         }
 
-        let health_url = format!("http://localhost:{}/queue", COMFYUI_PORT); 
+        let health_url = comfyui_url(&app_handle, "/queue");
         let client = reqwest::Client::new();
         match client.get(&health_url).send().await {
             Ok(response) => {
@@ -0,0 +1,363 @@
+// metamorphosis-app/src-tauri/src/sidecar_manager/lan_proxy.rs
+//
+// Opt-in LAN access to the ComfyUI sidecar. ComfyUI itself only listens on loopback; when
+// `lanAccessEnabled` is set, this proxy listens on all interfaces and forwards requests to it.
+// Every request must carry the per-session token (generated on each proxy start, never persisted),
+// either as an `X-Metamorphosis-Token` header, an `Authorization: Bearer` header or a `token`
+// query parameter, and only the endpoints the app itself uses are forwarded.
+//
+// The proxy works on raw TCP: it reads and checks the request head, rewrites it for the upstream,
+// then forwards the body. Only a WebSocket upgrade on `/ws` turns into a two-way pipe; every other
+// request is forwarded with `Connection: close` and exactly its `Content-Length` body, after which
+// nothing more is read from the client, so a second, unchecked request can't be pipelined through.
+// Clients get `HEAD_TIMEOUT` to send the request head, at most `MAX_CONNECTIONS` are served at once,
+// and stopping the proxy closes every open connection along with the listener.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Wry};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::endpoint::comfyui_port;
+use super::external_backend::is_external_mode;
+use crate::settings::{load_settings, save_settings};
+
+pub const DEFAULT_LAN_PROXY_PORT: u16 = 8190;
+const LAN_PROXY_HOST: &str = "0.0.0.0";
+const TOKEN_HEADER: &str = "x-metamorphosis-token";
+// Request heads larger than this are refused; ComfyUI requests are far smaller.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+// Connections with no traffic in either direction for this long are closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+// How long a client gets to send the complete request head.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+// Connections beyond this many are closed straight away.
+const MAX_CONNECTIONS: usize = 64;
+// The only path a WebSocket upgrade is honoured on.
+const WEBSOCKET_PATH: &str = "/ws";
+
+/// Paths forwarded to ComfyUI, matched exactly or as a `/`-separated prefix (`/history/<id>`).
+const ALLOWED_PATHS: &[&str] = &["/prompt", "/queue", "/history", "/view", "/upload/image", "/ws", "/interrupt"];
+
+struct RunningProxy {
+    port: u16,
+    token: String,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+static LAN_PROXY: Lazy<Mutex<Option<RunningProxy>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanAccessInfo {
+    pub enabled: bool, // The setting
+    pub running: bool,
+    pub port: u16,
+    pub token: Option<String>, // Only while running
+}
+
+fn path_allowed(path: &str) -> bool {
+    // None of the allowed endpoints need encoded characters or dot segments in the path, and
+    // either could be used to reach another route after ComfyUI normalises the path.
+    if path.contains('%') || path.split('/').any(|segment| segment == ".." || segment == ".") {
+        return false;
+    }
+    ALLOWED_PATHS.iter().any(|allowed| path == *allowed || path.strip_prefix(allowed).is_some_and(|rest| rest.starts_with('/')))
+}
+
+// Compares without returning early, so the token can't be guessed byte by byte from timings.
+fn token_matches(candidate: &str, token: &str) -> bool {
+    candidate.len() == token.len() && candidate.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Reads until the end of the request head. Returns the head and any body bytes read past it.
+async fn read_request_head(stream: &mut TcpStream) -> Result<(String, Vec<u8>), String> {
+    let mut buffer = Vec::with_capacity(2048);
+    let mut chunk = [0u8; 2048];
+    loop {
+        let read = stream.read(&mut chunk).await.map_err(|e| format!("Failed to read request: {}", e))?;
+        if read == 0 {
+            return Err("Connection closed before the request head was complete.".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buffer.split_off(end + 4);
+            let head = String::from_utf8(buffer).map_err(|_| "Request head is not valid UTF-8.".to_string())?;
+            return Ok((head, rest));
+        }
+        if buffer.len() > MAX_HEAD_BYTES {
+            return Err("Request head too large.".to_string());
+        }
+    }
+}
+
+async fn handle_connection(mut client: TcpStream, token: String, upstream_port: u16) {
+    let peer = client.peer_addr().map(|a| a.to_string()).unwrap_or_else(|_| "unknown".to_string());
+    let (head, body_start) = match tokio::time::timeout(HEAD_TIMEOUT, read_request_head(&mut client)).await {
+        Ok(Ok(parts)) => parts,
+        Ok(Err(e)) => {
+            warn!("[LAN_PROXY] {}: {}", peer, e);
+            return respond(&mut client, "400 Bad Request", "Bad request").await;
+        }
+        Err(_) => {
+            warn!("[LAN_PROXY] {}: request head not received within {:?}.", peer, HEAD_TIMEOUT);
+            return respond(&mut client, "408 Request Timeout", "Request timeout").await;
+        }
+    };
+
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next()) else {
+        return respond(&mut client, "400 Bad Request", "Bad request").await;
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    // Pull the token out of the query so it isn't forwarded; keep everything else as is.
+    let mut presented: Option<String> = None;
+    let kept_query: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| match pair.strip_prefix("token=") {
+            Some(value) => {
+                presented = Some(value.to_string());
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    let mut connection_upgrade = false;
+    let mut upgrade_websocket = false;
+    let mut content_length: Option<usize> = None;
+    let mut chunked = false;
+    let mut headers = Vec::new();
+    for line in lines.filter(|l| !l.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let (name_lower, value) = (name.trim().to_ascii_lowercase(), value.trim());
+        match name_lower.as_str() {
+            TOKEN_HEADER => presented = Some(value.to_string()),
+            "authorization" if value.starts_with("Bearer ") => presented = Some(value["Bearer ".len()..].to_string()),
+            // Rewritten below; Origin would trip ComfyUI's loopback origin check.
+            "host" | "origin" => {}
+            // Connection and Upgrade are set by the proxy, depending on whether the upgrade is allowed.
+            "connection" => connection_upgrade |= value.split(',').any(|token| token.trim().eq_ignore_ascii_case("upgrade")),
+            "upgrade" => upgrade_websocket = value.eq_ignore_ascii_case("websocket"),
+            "content-length" => match value.parse() {
+                Ok(length) if content_length.map_or(true, |existing| existing == length) => {
+                    content_length = Some(length);
+                    headers.push(line.to_string());
+                }
+                // Conflicting or malformed lengths make the body boundary ambiguous.
+                _ => return respond(&mut client, "400 Bad Request", "Bad request").await,
+            },
+            "transfer-encoding" => chunked = true,
+            _ => headers.push(line.to_string()),
+        }
+    }
+    let is_upgrade = path == WEBSOCKET_PATH && connection_upgrade && upgrade_websocket;
+
+    if !presented.as_deref().is_some_and(|p| token_matches(p, &token)) {
+        warn!("[LAN_PROXY] Rejected {} {} from {}: missing or wrong token.", method, path, peer);
+        return respond(&mut client, "401 Unauthorized", "Missing or invalid access token").await;
+    }
+    if !path_allowed(path) {
+        warn!("[LAN_PROXY] Rejected {} {} from {}: path not allowed.", method, path, peer);
+        return respond(&mut client, "403 Forbidden", "Endpoint not available over LAN").await;
+    }
+    // Without a Content-Length we can't tell where the body ends and a pipelined request starts.
+    if chunked {
+        warn!("[LAN_PROXY] Rejected {} {} from {}: chunked request bodies are not supported.", method, path, peer);
+        return respond(&mut client, "411 Length Required", "Content-Length required").await;
+    }
+
+    let mut upstream = match TcpStream::connect(("127.0.0.1", upstream_port)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            warn!("[LAN_PROXY] ComfyUI is not reachable on port {}: {}", upstream_port, e);
+            return respond(&mut client, "502 Bad Gateway", "ComfyUI is not running").await;
+        }
+    };
+
+    let target = if kept_query.is_empty() { path.to_string() } else { format!("{}?{}", path, kept_query.join("&")) };
+    let mut forwarded = format!("{} {} {}\r\nHost: 127.0.0.1:{}\r\n", method, target, version, upstream_port);
+    for header in &headers {
+        forwarded.push_str(header);
+        forwarded.push_str("\r\n");
+    }
+    forwarded.push_str(if is_upgrade { "Upgrade: websocket\r\nConnection: Upgrade\r\n\r\n" } else { "Connection: close\r\n\r\n" });
+
+    if let Err(e) = upstream.write_all(forwarded.as_bytes()).await {
+        warn!("[LAN_PROXY] Failed to forward request to ComfyUI: {}", e);
+        return respond(&mut client, "502 Bad Gateway", "Failed to reach ComfyUI").await;
+    }
+
+    if is_upgrade {
+        if !body_start.is_empty() && upstream.write_all(&body_start).await.is_err() {
+            return;
+        }
+        pipe_with_idle_timeout(client, upstream).await;
+        return;
+    }
+
+    // Forward exactly the declared body; anything the client sends beyond it is never read.
+    let body_length = content_length.unwrap_or(0);
+    let already_read = body_start.len().min(body_length);
+    if upstream.write_all(&body_start[..already_read]).await.is_err() {
+        return;
+    }
+    let mut client_body = (&mut client).take((body_length - already_read) as u64);
+    match tokio::time::timeout(IDLE_TIMEOUT, tokio::io::copy(&mut client_body, &mut upstream)).await {
+        Ok(Ok(_)) => {}
+        _ => return, // Client hung up or stalled mid-body
+    }
+    // Errors here are clients or ComfyUI hanging up mid-transfer; nothing to report.
+    let _ = tokio::time::timeout(IDLE_TIMEOUT, tokio::io::copy(&mut upstream, &mut client)).await;
+    let _ = client.shutdown().await;
+}
+
+/// Pipes an upgraded (WebSocket) connection both ways until either side closes it or neither
+/// side has sent anything for `IDLE_TIMEOUT`.
+async fn pipe_with_idle_timeout(mut client: TcpStream, mut upstream: TcpStream) {
+    let (mut client_read, mut client_write) = client.split();
+    let (mut upstream_read, mut upstream_write) = upstream.split();
+    let mut client_buffer = vec![0u8; 16 * 1024];
+    let mut upstream_buffer = vec![0u8; 16 * 1024];
+    loop {
+        let step = tokio::time::timeout(IDLE_TIMEOUT, async {
+            tokio::select! {
+                read = client_read.read(&mut client_buffer) => match read {
+                    Ok(0) | Err(_) => false,
+                    Ok(n) => upstream_write.write_all(&client_buffer[..n]).await.is_ok(),
+                },
+                read = upstream_read.read(&mut upstream_buffer) => match read {
+                    Ok(0) | Err(_) => false,
+                    Ok(n) => client_write.write_all(&upstream_buffer[..n]).await.is_ok(),
+                },
+            }
+        })
+        .await;
+        match step {
+            Ok(true) => continue,
+            Ok(false) => break,
+            Err(_) => {
+                info!("[LAN_PROXY] Closing WebSocket connection idle for {:?}.", IDLE_TIMEOUT);
+                break;
+            }
+        }
+    }
+}
+
+/// Starts the proxy if it isn't running. A new token is generated on every start.
+pub async fn start_lan_proxy(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    if LAN_PROXY.lock().unwrap().is_some() {
+        return Ok(());
+    }
+    if is_external_mode(app_handle) {
+        return Err("LAN access is only available for the ComfyUI run by the app, not in external mode.".to_string());
+    }
+    let port = load_settings(app_handle).lan_proxy_port;
+    let listener = TcpListener::bind((LAN_PROXY_HOST, port))
+        .await
+        .map_err(|e| format!("Failed to start the LAN proxy on port {}: {}", port, e))?;
+    let token = uuid::Uuid::new_v4().simple().to_string();
+
+    let task_app_handle = app_handle.clone();
+    let task_token = token.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        // Connections run in this set, so aborting this task (stop_lan_proxy) drops the set and
+        // aborts every connection still open, WebSocket pipes included.
+        let mut connections = JoinSet::new();
+        let permits = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let Ok(permit) = permits.clone().try_acquire_owned() else {
+                            warn!("[LAN_PROXY] Closing connection from {}: {} connections already open.", peer, MAX_CONNECTIONS);
+                            continue; // Dropping the stream closes it
+                        };
+                        // Resolved per connection: the sidecar's port can change between launches.
+                        let upstream_port = comfyui_port(&task_app_handle);
+                        let token = task_token.clone();
+                        connections.spawn(async move {
+                            handle_connection(stream, token, upstream_port).await;
+                            drop(permit);
+                        });
+                    }
+                    Err(e) => error!("[LAN_PROXY] Failed to accept connection: {}", e),
+                },
+                // Reap finished connections so the set doesn't grow.
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+    });
+    info!("[LAN_PROXY] Listening on {}:{}", LAN_PROXY_HOST, port);
+    *LAN_PROXY.lock().unwrap() = Some(RunningProxy { port, token, task });
+    Ok(())
+}
+
+/// Stops accepting connections and closes the ones already open.
+pub fn stop_lan_proxy() {
+    if let Some(proxy) = LAN_PROXY.lock().unwrap().take() {
+        proxy.task.abort();
+        info!("[LAN_PROXY] Stopped the proxy on port {}", proxy.port);
+    }
+}
+
+/// Called at startup: LAN access stays off unless the user turned it on.
+pub async fn start_lan_proxy_if_enabled(app_handle: &AppHandle<Wry>) {
+    if !load_settings(app_handle).lan_access_enabled {
+        return;
+    }
+    if let Err(e) = start_lan_proxy(app_handle).await {
+        error!("[LAN_PROXY] {}", e);
+    }
+}
+
+fn lan_access_info(app_handle: &AppHandle<Wry>) -> LanAccessInfo {
+    let settings = load_settings(app_handle);
+    let proxy = LAN_PROXY.lock().unwrap();
+    LanAccessInfo {
+        enabled: settings.lan_access_enabled,
+        running: proxy.is_some(),
+        port: proxy.as_ref().map(|p| p.port).unwrap_or(settings.lan_proxy_port),
+        token: proxy.as_ref().map(|p| p.token.clone()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_lan_access_info(app_handle: AppHandle<Wry>) -> Result<LanAccessInfo, String> {
+    Ok(lan_access_info(&app_handle))
+}
+
+/// Turns LAN access on or off, persisting the choice and starting or stopping the proxy.
+#[tauri::command]
+pub async fn set_lan_access_enabled(app_handle: AppHandle<Wry>, enabled: bool) -> Result<LanAccessInfo, String> {
+    let mut settings = load_settings(&app_handle);
+    settings.lan_access_enabled = enabled;
+    save_settings(&app_handle, &settings)?;
+    if enabled {
+        start_lan_proxy(&app_handle).await?;
+    } else {
+        stop_lan_proxy();
+    }
+    Ok(lan_access_info(&app_handle))
+}
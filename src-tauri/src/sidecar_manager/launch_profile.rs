@@ -0,0 +1,239 @@
+// metamorphosis-app/src-tauri/src/sidecar_manager/launch_profile.rs
+//
+// ComfyUI launch profiles: VRAM mode, precision, attention implementation, preview method, plus
+// free-form extra args and environment variables, turned into command-line flags by `to_args`.
+// The profile is persisted in settings. Until the user picks one, the preset matching the
+// detected VRAM is used, so a 4 GB card gets `--lowvram` without anyone having to know the flag.
+// `set_launch_profile` saves a profile and restarts the sidecar so it takes effect right away.
+
+use std::collections::BTreeMap;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use super::external_backend::is_external_mode;
+use super::orchestration::restart_comfyui_sidecar;
+use crate::gpu_detection::detect_vram_mb;
+use crate::process_manager::ProcessManager;
+use crate::settings::{load_settings, save_settings};
+
+// Flags the app sets itself; extra args may not override them (binding and CORS are security settings).
+const RESERVED_ARGS: &[&str] = &["--listen", "--port", "--enable-cors-header", "--front-end-version", "--front-end-root"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum VramMode {
+    #[default]
+    Auto, // ComfyUI decides from the free VRAM
+    Low,
+    Normal,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ForcePrecision {
+    #[default]
+    Auto,
+    Fp16,
+    Fp32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum UnetPrecision {
+    #[default]
+    Auto,
+    Fp16,
+    Bf16,
+    Fp8E4m3fn,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum VaePrecision {
+    #[default]
+    Auto,
+    Fp16,
+    Fp32,
+    Bf16,
+    Cpu, // Run the VAE on the CPU
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum AttentionImpl {
+    #[default]
+    Auto,
+    Pytorch,
+    Split,
+    Quad,
+    Sage,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PreviewMethod {
+    #[default]
+    None, // ComfyUI's default
+    Auto,
+    Latent2Rgb,
+    Taesd,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchProfile {
+    pub name: String,
+    pub vram_mode: VramMode,
+    pub force_precision: ForcePrecision,
+    pub unet_precision: UnetPrecision,
+    pub vae_precision: VaePrecision,
+    pub attention: AttentionImpl,
+    pub preview_method: PreviewMethod,
+    pub extra_args: Vec<String>,
+    pub env_vars: BTreeMap<String, String>,
+}
+
+impl LaunchProfile {
+    /// ComfyUI flags for this profile, appended to the ones the app always passes.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args: Vec<&str> = Vec::new();
+        args.extend(match self.vram_mode {
+            VramMode::Auto => None,
+            VramMode::Low => Some("--lowvram"),
+            VramMode::Normal => Some("--normalvram"),
+            VramMode::High => Some("--highvram"),
+        });
+        args.extend(match self.force_precision {
+            ForcePrecision::Auto => None,
+            ForcePrecision::Fp16 => Some("--force-fp16"),
+            ForcePrecision::Fp32 => Some("--force-fp32"),
+        });
+        args.extend(match self.unet_precision {
+            UnetPrecision::Auto => None,
+            UnetPrecision::Fp16 => Some("--fp16-unet"),
+            UnetPrecision::Bf16 => Some("--bf16-unet"),
+            UnetPrecision::Fp8E4m3fn => Some("--fp8_e4m3fn-unet"),
+        });
+        args.extend(match self.vae_precision {
+            VaePrecision::Auto => None,
+            VaePrecision::Fp16 => Some("--fp16-vae"),
+            VaePrecision::Fp32 => Some("--fp32-vae"),
+            VaePrecision::Bf16 => Some("--bf16-vae"),
+            VaePrecision::Cpu => Some("--cpu-vae"),
+        });
+        args.extend(match self.attention {
+            AttentionImpl::Auto => None,
+            AttentionImpl::Pytorch => Some("--use-pytorch-cross-attention"),
+            AttentionImpl::Split => Some("--use-split-cross-attention"),
+            AttentionImpl::Quad => Some("--use-quad-cross-attention"),
+            AttentionImpl::Sage => Some("--use-sage-attention"),
+        });
+        match self.preview_method {
+            PreviewMethod::None => {}
+            PreviewMethod::Auto => args.extend(["--preview-method", "auto"]),
+            PreviewMethod::Latent2Rgb => args.extend(["--preview-method", "latent2rgb"]),
+            PreviewMethod::Taesd => args.extend(["--preview-method", "taesd"]),
+        }
+        let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for arg in &self.extra_args {
+            let flag = arg.split('=').next().unwrap_or(arg);
+            if RESERVED_ARGS.contains(&flag) {
+                return Err(format!("{} is set by the app and cannot be used as an extra argument.", flag));
+            }
+        }
+        if let Some(name) = self.env_vars.keys().find(|name| name.is_empty() || name.contains('=')) {
+            return Err(format!("Invalid environment variable name: '{}'", name));
+        }
+        Ok(())
+    }
+}
+
+/// The shipped presets, from least to most VRAM.
+pub fn launch_profile_presets() -> Vec<LaunchProfile> {
+    vec![
+        LaunchProfile {
+            name: "lowVram".to_string(),
+            vram_mode: VramMode::Low,
+            vae_precision: VaePrecision::Fp16,
+            attention: AttentionImpl::Split,
+            ..Default::default()
+        },
+        LaunchProfile {
+            name: "balanced".to_string(),
+            ..Default::default()
+        },
+        LaunchProfile {
+            name: "highVram".to_string(),
+            vram_mode: VramMode::High,
+            preview_method: PreviewMethod::Auto,
+            ..Default::default()
+        },
+    ]
+}
+
+/// Preset for a card with `vram_mb` MiB: SDXL needs --lowvram below ~8 GB, and 16 GB+ can keep
+/// every model loaded. Unknown VRAM (non-NVIDIA, no nvidia-smi) gets the balanced preset.
+pub fn preset_for_vram(vram_mb: Option<u64>) -> LaunchProfile {
+    let name = match vram_mb {
+        Some(mb) if mb < 8 * 1024 => "lowVram",
+        Some(mb) if mb >= 16 * 1024 => "highVram",
+        _ => "balanced",
+    };
+    launch_profile_presets().into_iter().find(|p| p.name == name).unwrap_or_default()
+}
+
+/// The profile the next launch uses: the saved one, else the preset for the detected VRAM.
+pub fn effective_launch_profile(app_handle: &AppHandle<Wry>) -> LaunchProfile {
+    load_settings(app_handle).launch_profile.unwrap_or_else(|| preset_for_vram(detect_vram_mb()))
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchProfileOptions {
+    pub active: LaunchProfile,
+    pub is_automatic: bool, // No profile saved; `active` is the preset for the detected VRAM
+    pub detected_vram_mb: Option<u64>,
+    pub presets: Vec<LaunchProfile>,
+}
+
+fn launch_profile_options(app_handle: &AppHandle<Wry>) -> LaunchProfileOptions {
+    let saved = load_settings(app_handle).launch_profile;
+    let detected_vram_mb = detect_vram_mb();
+    LaunchProfileOptions {
+        is_automatic: saved.is_none(),
+        active: saved.unwrap_or_else(|| preset_for_vram(detected_vram_mb)),
+        detected_vram_mb,
+        presets: launch_profile_presets(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_launch_profiles(app_handle: AppHandle<Wry>) -> Result<LaunchProfileOptions, String> {
+    tokio::task::spawn_blocking(move || launch_profile_options(&app_handle)).await.map_err(|e| e.to_string())
+}
+
+/// Saves `profile` (None goes back to the VRAM-based preset) and restarts a running sidecar with it.
+#[tauri::command]
+pub async fn set_launch_profile(app_handle: AppHandle<Wry>, profile: Option<LaunchProfile>) -> Result<LaunchProfileOptions, String> {
+    if let Some(profile) = &profile {
+        profile.validate()?;
+    }
+    let mut settings = load_settings(&app_handle);
+    settings.launch_profile = profile;
+    save_settings(&app_handle, &settings)?;
+    info!("[LAUNCH_PROFILE] Saved launch profile: {:?}", settings.launch_profile.as_ref().map(|p| p.name.as_str()));
+
+    let sidecar_running = app_handle.state::<ProcessManager>().is_process_running("comfyui_sidecar");
+    if sidecar_running && !is_external_mode(&app_handle) {
+        restart_comfyui_sidecar(&app_handle).await?;
+    }
+    let handle = app_handle.clone();
+    tokio::task::spawn_blocking(move || launch_profile_options(&handle)).await.map_err(|e| e.to_string())
+}
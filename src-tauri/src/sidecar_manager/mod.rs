@@ -4,6 +4,11 @@ pub mod event_utils;
 pub mod process_handler;
 pub mod health_checker;
 pub mod orchestration;
+pub mod node_load_report;
+pub mod endpoint;
+pub mod lan_proxy;
+pub mod external_backend;
+pub mod launch_profile;
 
 // Re-export items that need to be public from the sidecar_manager module.
 // These will then be re-exported by the parent `comfyui_sidecar.rs` if needed
@@ -15,6 +20,5 @@ pub use orchestration::{
 };
 // process_handler functions are no longer public. Use ProcessManager instead.
 
-// Constants like COMFYUI_PORT might be pub from event_utils if needed externally,
-// or just used internally by the modules within sidecar_manager.
-pub use event_utils::COMFYUI_PORT;
\ No newline at end of file
+// The ComfyUI port is resolved at launch; build URLs with these instead of hard-coding 8188.
+pub use endpoint::{comfyui_base_url, comfyui_port, comfyui_url};
\ No newline at end of file
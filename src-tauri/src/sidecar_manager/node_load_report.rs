@@ -0,0 +1,164 @@
+// metamorphosis-app/src-tauri/src/sidecar_manager/node_load_report.rs
+//
+// Per-custom-node load status, parsed from ComfyUI's startup output. ComfyUI logs a traceback
+// and "Cannot import <path> module for custom nodes: <error>" for every node that fails to
+// import, then prints an "Import times for custom nodes:" block with one line per node:
+//
+//    0.0 seconds: /.../custom_nodes/websocket_image_save.py
+//    1.2 seconds (IMPORT FAILED): /.../custom_nodes/ComfyUI-Impact-Pack
+//
+// `NodeLoadParser` turns those lines into a `NodeLoadReport`, which is kept in
+// `NodeLoadReportState` and sent as a `custom-node-load-report` event once the block ends, so a
+// broken node pack shows up in the UI instead of as a workflow validation error later.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use log::{error, info, warn};
+
+// Traceback lines kept per failed node; the last ones carry the actual exception.
+const MAX_TRACEBACK_LINES: usize = 12;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomNodeLoadStatus {
+    pub name: String, // Directory or file name under custom_nodes
+    pub path: String,
+    pub success: bool,
+    pub load_time_secs: Option<f64>,
+    pub error: Option<String>,
+    pub traceback_excerpt: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeLoadReport {
+    pub nodes: Vec<CustomNodeLoadStatus>,
+    pub failed_count: usize,
+    pub generated_at: String,
+}
+
+/// Latest report from the running (or last) sidecar. None until the import times were printed.
+#[derive(Default)]
+pub struct NodeLoadReportState(pub Mutex<Option<NodeLoadReport>>);
+
+fn node_name_from_path(path: &str) -> String {
+    path.trim().trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+/// Parses `1.2 seconds (IMPORT FAILED): /path/to/node`.
+fn parse_import_time_line(line: &str) -> Option<(f64, bool, String)> {
+    let (timing, path) = line.trim().split_once(": ")?;
+    let mut parts = timing.split_whitespace();
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next()? != "seconds" {
+        return None;
+    }
+    Some((seconds, timing.contains("IMPORT FAILED"), path.trim().to_string()))
+}
+
+/// Feeds on sidecar output lines, one at a time, in order.
+#[derive(Default)]
+pub struct NodeLoadParser {
+    // Last lines of the current traceback; bounded, since a traceback has no reliable end marker.
+    traceback: VecDeque<String>,
+    in_traceback: bool,
+    // Node name -> (error, traceback excerpt) from "Cannot import" lines
+    failures: HashMap<String, (String, String)>,
+    in_import_times: bool,
+    nodes: Vec<CustomNodeLoadStatus>,
+}
+
+impl NodeLoadParser {
+    /// Returns the finished report when `line` ends the import-times block.
+    pub fn observe(&mut self, line: &str) -> Option<NodeLoadReport> {
+        let trimmed = line.trim_end();
+
+        if self.in_import_times {
+            if let Some((seconds, failed, path)) = parse_import_time_line(trimmed) {
+                let name = node_name_from_path(&path);
+                let failure = self.failures.remove(&name);
+                self.nodes.push(CustomNodeLoadStatus {
+                    success: !failed,
+                    load_time_secs: Some(seconds),
+                    error: failure.as_ref().map(|(e, _)| e.clone()).or_else(|| failed.then(|| "IMPORT FAILED".to_string())),
+                    traceback_excerpt: failure.map(|(_, t)| t),
+                    name,
+                    path,
+                });
+                return None;
+            }
+            // First line after the block (usually blank) ends it.
+            self.in_import_times = false;
+            return Some(self.finish());
+        }
+
+        if trimmed.starts_with("Traceback (most recent call last):") {
+            self.in_traceback = true;
+            self.traceback.clear();
+        }
+        if let Some(rest) = trimmed.trim_start().strip_prefix("Cannot import ") {
+            if let Some((path, error)) = rest.split_once(" module for custom nodes:") {
+                let excerpt = Vec::from(std::mem::take(&mut self.traceback)).join("\n").trim().to_string();
+                self.failures.insert(node_name_from_path(path), (error.trim().to_string(), excerpt));
+            }
+            self.in_traceback = false;
+            self.traceback.clear();
+            return None;
+        }
+        if self.in_traceback {
+            if self.traceback.len() == MAX_TRACEBACK_LINES {
+                self.traceback.pop_front();
+            }
+            self.traceback.push_back(trimmed.to_string());
+        }
+        if trimmed.trim_start().starts_with("Import times for custom nodes") {
+            self.in_import_times = true;
+            self.nodes.clear();
+        }
+        None
+    }
+
+    fn finish(&mut self) -> NodeLoadReport {
+        let mut nodes = std::mem::take(&mut self.nodes);
+        // Failures ComfyUI reported without a matching import-time line.
+        for (name, (error, excerpt)) in self.failures.drain() {
+            nodes.push(CustomNodeLoadStatus {
+                path: name.clone(),
+                name,
+                success: false,
+                load_time_secs: None,
+                error: Some(error),
+                traceback_excerpt: Some(excerpt),
+            });
+        }
+        NodeLoadReport {
+            failed_count: nodes.iter().filter(|n| !n.success).count(),
+            nodes,
+            generated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Forgets the previous sidecar's report; called before each spawn.
+pub fn clear_node_load_report(app_handle: &AppHandle<Wry>) {
+    *app_handle.state::<NodeLoadReportState>().0.lock().unwrap() = None;
+}
+
+/// Stores a finished report and sends it to the frontend.
+pub fn publish_node_load_report(app_handle: &AppHandle<Wry>, report: NodeLoadReport) {
+    for node in report.nodes.iter().filter(|n| !n.success) {
+        warn!("[NODE_LOAD_REPORT] {} failed to load: {}", node.name, node.error.as_deref().unwrap_or("unknown error"));
+    }
+    info!("[NODE_LOAD_REPORT] {} custom nodes, {} failed.", report.nodes.len(), report.failed_count);
+    *app_handle.state::<NodeLoadReportState>().0.lock().unwrap() = Some(report.clone());
+    if let Err(e) = app_handle.emit("custom-node-load-report", report) {
+        error!("Failed to emit custom-node-load-report event: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_custom_node_load_report(app_handle: AppHandle<Wry>) -> Result<Option<NodeLoadReport>, String> {
+    Ok(app_handle.state::<NodeLoadReportState>().0.lock().unwrap().clone())
+}
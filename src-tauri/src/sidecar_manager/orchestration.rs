@@ -2,14 +2,16 @@
 
 use tauri::{AppHandle, Wry, Emitter, Manager};
 use log::{info, error};
-use tokio::time::Duration; // For port check delay
-use tauri_plugin_http::reqwest; // For port check client
+use tokio::time::Duration; // For health check timeouts and delays
+use tauri_plugin_http::reqwest; // For health check client
 // use std::path::PathBuf; // For path construction during spawn_and_health_check_comfyui - PathBuf is used by internal_spawn_comfyui_process
 
 // Internal imports from sibling modules
-use super::event_utils::{emit_backend_status, COMFYUI_PORT};
+use super::event_utils::emit_backend_status;
+use super::endpoint::{comfyui_port, comfyui_url, port_is_free, verify_sidecar_identity};
+use super::external_backend::{connect_external_backend, is_external_mode};
 use crate::process_manager::ProcessManager;
-use super::process_handler::{spawn_comfyui_process as internal_spawn_comfyui_process, IS_ATTEMPTING_SPAWN};
+use super::process_handler::{spawn_comfyui_process as internal_spawn_comfyui_process, IS_ATTEMPTING_SPAWN, IS_RESTARTING};
 use super::health_checker::{perform_comfyui_health_check, monitor_comfyui_health}; // monitor_comfyui_health is started by perform_comfyui_health_check
 
 // Crate-level imports
@@ -21,6 +23,10 @@ use crate::setup; // For emit_setup_progress
 pub async fn ensure_backend_ready(app_handle: AppHandle<Wry>) -> Result<(), String> {
     log::error!("[EARLY_CALL_DEBUG] ensure_backend_ready INVOKED");
     info!("Ensuring backend is ready...");
+    if is_external_mode(&app_handle) {
+        // Nothing to install or spawn; the user's ComfyUI just has to be usable.
+        return connect_external_backend(&app_handle).await;
+    }
     emit_backend_status(&app_handle, "checking_dependencies", "Checking backend dependencies...".to_string(), false);
  
     match dependency_manager::install_python_dependencies_with_progress(&app_handle).await {
@@ -68,6 +74,10 @@ pub async fn ensure_backend_ready(app_handle: AppHandle<Wry>) -> Result<(), Stri
 /// Emits `setup-progress` events. This is typically called by `setup.rs`.
 pub async fn spawn_and_health_check_comfyui(app_handle: &AppHandle<Wry>) -> Result<(), String> {
     log::error!("[EARLY_SPAWN_DEBUG] Orchestration spawn_and_health_check_comfyui INVOKED");
+    if is_external_mode(app_handle) {
+        info!("[GUARD] spawn_and_health_check_comfyui: External backend mode, connecting instead of spawning.");
+        return connect_external_backend(app_handle).await;
+    }
     // Initial check for existing process or ongoing spawn attempt
     // Check 1: Is another spawn attempt already in progress?
     // This block ensures the MutexGuard for IS_ATTEMPTING_SPAWN is short-lived.
@@ -116,32 +126,8 @@ pub async fn spawn_and_health_check_comfyui(app_handle: &AppHandle<Wry>) -> Resu
     
     info!("[GUARD] Proceeding with port check and spawn logic as no existing process found and IS_ATTEMPTING_SPAWN is now true.");
 
-    // Port availability check
-    let max_port_check_retries = 3;
-    let port_check_delay = Duration::from_secs(3);
-    for attempt in 1..=max_port_check_retries {
-        match std::net::TcpListener::bind(format!("0.0.0.0:{}", COMFYUI_PORT)) {
-            Ok(listener) => {
-                drop(listener);
-                info!("[PORT CHECK] Attempt {}: Port {} is available.", attempt, COMFYUI_PORT);
-                break;
-            }
-            Err(e) => {
-                let port_busy_msg = format!("[PORT CHECK] Attempt {}: Port {} is already in use: {}.", attempt, COMFYUI_PORT, e);
-                error!("{}", port_busy_msg);
-                if attempt == max_port_check_retries {
-                    emit_backend_status(app_handle, "backend_error", port_busy_msg.clone(), true);
-                    setup::emit_setup_progress(app_handle, "error_port_conflict", "Port Conflict", 0, Some(port_busy_msg.clone()), Some(port_busy_msg.clone()));
-                    return Err(port_busy_msg);
-                }
-                let retry_detail_msg = format!("Port {} busy. Retrying in {}s... (Attempt {}/{})", COMFYUI_PORT, port_check_delay.as_secs(), attempt, max_port_check_retries);
-                info!("{}", retry_detail_msg);
-                emit_backend_status(app_handle, "waiting_for_port", retry_detail_msg.clone(), false);
-                setup::emit_setup_progress(app_handle, "port_check_retry", "Waiting for Port", 1, Some(retry_detail_msg), None);
-                tokio::time::sleep(port_check_delay).await;
-            }
-        }
-    }
+    // Port selection happens in internal_spawn_comfyui_process: a busy configured port is
+    // replaced with a free one rather than waited on.
 
     let phase_name = "starting_services";
     let mut current_phase_progress: u8 = 0;
@@ -168,7 +154,7 @@ pub async fn spawn_and_health_check_comfyui(app_handle: &AppHandle<Wry>) -> Resu
     setup::emit_setup_progress(app_handle, phase_name, "Performing ComfyUI health check...", current_phase_progress, None, None);
 
     // Use a simplified health check loop here for setup progress reporting
-    let health_check_url = format!("http://localhost:{}/queue", COMFYUI_PORT);
+    let health_check_url = comfyui_url(app_handle, "/queue");
     let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().map_err(|e| format!("Failed to build HTTP client: {}", e))?;
     let max_health_retries = 10;
     let health_retry_delay = Duration::from_secs(5);
@@ -189,6 +175,12 @@ pub async fn spawn_and_health_check_comfyui(app_handle: &AppHandle<Wry>) -> Resu
             Ok(response) => {
                 if response.status().is_success() {
                     info!("ComfyUI initial health check successful (setup flow).");
+                    if let Err(e) = verify_sidecar_identity(app_handle).await {
+                        error!("{}", e);
+                        emit_backend_status(app_handle, "backend_error", e.clone(), true);
+                        setup::emit_setup_progress(app_handle, "error_port_conflict", "Port Conflict", 0, Some(e.clone()), Some(e.clone()));
+                        return Err(e);
+                    }
                     setup::emit_setup_progress(app_handle, phase_name, "ComfyUI health check successful.", 100, None, None);
                     if let Err(e) = app_handle.emit("comfyui-fully-healthy", ()) {
                          error!("[SPAWN_AND_HEALTH_CHECK] Failed to emit comfyui-fully-healthy event: {}", e);
@@ -218,10 +210,32 @@ pub async fn spawn_and_health_check_comfyui(app_handle: &AppHandle<Wry>) -> Resu
     Err(err_msg)
 }
 
+/// Stops the running sidecar and starts it again, e.g. after the launch profile changed.
+pub async fn restart_comfyui_sidecar(app_handle: &AppHandle<Wry>) -> Result<(), String> {
+    info!("[COMFYUI LIFECYCLE] Restarting the ComfyUI sidecar.");
+    emit_backend_status(app_handle, "restarting", "Restarting ComfyUI backend...".to_string(), false);
+    *IS_RESTARTING.lock().unwrap() = true;
+    let _reset_restarting_flag_guard = scopeguard::guard((), |_| *IS_RESTARTING.lock().unwrap() = false);
+    let port = comfyui_port(app_handle);
+    app_handle.state::<ProcessManager>().stop_process_and_wait("comfyui_sidecar").await;
+
+    // Give the old process a moment to release its port, so the restart keeps the same one.
+    for _ in 0..20 {
+        if port_is_free(port) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    spawn_and_health_check_comfyui(app_handle).await
+}
+
 #[tauri::command]
 pub async fn ensure_comfyui_running_and_healthy(app_handle: AppHandle<Wry>) -> Result<(), String> {
     log::error!("[EARLY_CALL_DEBUG] ensure_comfyui_running_and_healthy INVOKED");
     info!("[COMFYUI LIFECYCLE] ensure_comfyui_running_and_healthy called.");
+    if is_external_mode(&app_handle) {
+        return connect_external_backend(&app_handle).await;
+    }
 
     let process_manager = app_handle.state::<ProcessManager>();
     if process_manager.is_process_running("comfyui_sidecar") {
@@ -6,20 +6,30 @@ use std::time::Instant;
 use tauri::{AppHandle, Wry};
 use tauri_plugin_shell::ShellExt;
 use once_cell::sync::Lazy;
-use log::{info, error};
+use log::{info, error, warn};
 use std::collections::HashMap;
 
 // Internal imports from sibling modules
-use super::event_utils::{emit_backend_status, COMFYUI_PORT};
+use super::event_utils::emit_backend_status;
+use super::endpoint::{resolve_launch_port, webview_origin, COMFYUI_LISTEN_HOST};
+use super::launch_profile::effective_launch_profile;
+use super::node_load_report::{clear_node_load_report, publish_node_load_report, NodeLoadParser};
 
 // Crate-level imports
-use crate::gpu_detection::{get_gpu_info, GpuType};
-use crate::setup_manager::python_utils::{get_conda_env_python_executable_path, get_conda_executable_path};
+use crate::gpu_detection::get_gpu_info;
+use crate::setup_manager::dependency_manager::torch_resolver::{resolve_torch_variant, TorchVariant};
+use crate::setup_manager::env_backend::{get_launch_env_vars, selected_backend, COMFYUI_ENV_NAME};
+use crate::setup_manager::python_utils::{
+    bundled_frontend_is_valid, get_bundled_frontend_path, get_conda_env_python_executable_path, BUNDLED_FRONTEND_VERSION,
+};
 use crate::process_manager::ProcessManager;
+use crate::process_records::settle_orphaned_sidecar;
 
 // Global static variables for process management
 // COMFYUI_CHILD_PROCESS is now deprecated and handled by the central ProcessManager.
 pub static IS_ATTEMPTING_SPAWN: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+// Set while the sidecar is deliberately stopped and started again, so the monitor doesn't race it.
+pub static IS_RESTARTING: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 pub static RESTART_ATTEMPTS: Lazy<Mutex<u32>> = Lazy::new(|| Mutex::new(0));
 pub static LAST_RESTART_TIME: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
 pub const MAX_RESTARTS_PER_HOUR: u32 = 5;
@@ -37,9 +47,17 @@ pub(super) async fn spawn_comfyui_process(app_handle: AppHandle<Wry>) -> Result<
 
     // The scopeguard for IS_ATTEMPTING_SPAWN is managed by the caller.
 
-    info!("Attempting to spawn ComfyUI process on port {}...", COMFYUI_PORT);
+    // A sidecar left running by a crashed earlier run is reused if it still answers, or killed
+    // so it doesn't hold the port (see process_records).
+    if settle_orphaned_sidecar(&app_handle).await {
+        return Ok(());
+    }
+
+    // Resolved on every spawn (restarts included): the configured port, or a free one if it is taken.
+    let port = resolve_launch_port(&app_handle)?;
+    info!("Attempting to spawn ComfyUI process on port {}...", port);
         // emit_backend_status is now in event_utils
-        emit_backend_status(&app_handle, "starting_sidecar", format!("Starting ComfyUI backend on port {}...", COMFYUI_PORT), false);
+        emit_backend_status(&app_handle, "starting_sidecar", format!("Starting ComfyUI backend on port {}...", port), false);
 
         let exe_path = std::env::current_exe().map_err(|e| format!("Failed to get current executable path: {}", e))?;
         let exe_dir = exe_path.parent().ok_or_else(|| format!("Failed to get parent directory of executable: {}", exe_path.display()))?.to_path_buf();
@@ -60,13 +78,6 @@ pub(super) async fn spawn_comfyui_process(app_handle: AppHandle<Wry>) -> Result<
             target_dir.join("vendor").join("comfyui")
         };
 
-        let conda_exe_path = get_conda_executable_path(&app_handle).await?;
-        if !conda_exe_path.exists() {
-            let err_msg = format!("Conda executable not found at expected path: {}", conda_exe_path.display());
-            error!("{}", err_msg);
-            emit_backend_status(&app_handle, "backend_error", err_msg.clone(), true);
-            return Err(err_msg);
-        }
          if !comfyui_dir.exists() {
              let err_msg = format!("ComfyUI directory not found at expected path: {}", comfyui_dir.display());
              error!("{}", err_msg);
@@ -83,7 +94,8 @@ pub(super) async fn spawn_comfyui_process(app_handle: AppHandle<Wry>) -> Result<
             return Err(err_msg);
         }
 
-        info!("Using Conda executable: {}", conda_exe_path.display());
+        let env_backend = selected_backend(&app_handle);
+        info!("Using Python environment backend: {:?}", env_backend);
         info!("Using ComfyUI script: {}", main_script.display());
         info!("Setting CWD to: {}", comfyui_dir.display());
 
@@ -93,64 +105,59 @@ pub(super) async fn spawn_comfyui_process(app_handle: AppHandle<Wry>) -> Result<
         let mut comfyui_args = vec![
             "main.py".to_string(), // Relative to the CWD, which is comfyui_dir
             "--listen".to_string(),
-            "--front-end-version".to_string(),
-            "Comfy-Org/ComfyUI_frontend@v1.18.2".to_string(),
+            COMFYUI_LISTEN_HOST.to_string(), // A bare --listen would bind every interface
             "--port".to_string(),
-            COMFYUI_PORT.to_string(),
+            port.to_string(),
             "--enable-cors-header".to_string(),
-            "*".to_string(),
+            webview_origin(&app_handle), // Only the app itself, not any page in a local browser
         ];
 
-        let use_cpu = match gpu_info.gpu_type {
-            GpuType::Nvidia => {
-                info!("NVIDIA GPU detected, launching in GPU mode.");
-                false
-            }
-            GpuType::Amd => {
-                info!("AMD GPU detected. Currently defaulting to CPU mode. ROCm support needs to be implemented.");
-                true 
-            }
-            GpuType::Intel => {
-                info!("Intel GPU detected. Currently defaulting to CPU mode. Intel GPU support needs to be implemented.");
-                true 
-            }
-            GpuType::Unknown => {
-                info!("Unknown GPU type detected, adding --cpu flag.");
-                true
-            }
-        };
+        // Serve the frontend bundled at build time. Without --front-end-root (or with
+        // --front-end-version) ComfyUI would fetch it from GitHub and fail to start offline.
+        let frontend_path = get_bundled_frontend_path(&app_handle)?;
+        if bundled_frontend_is_valid(&frontend_path) {
+            comfyui_args.push("--front-end-root".to_string());
+            comfyui_args.push(frontend_path.to_string_lossy().to_string());
+        } else {
+            // ComfyUI then falls back to the frontend package in its Python env.
+            warn!("Bundled ComfyUI frontend v{} not found at {}. Using ComfyUI's default frontend.", BUNDLED_FRONTEND_VERSION, frontend_path.display());
+        }
 
+        // Run on whatever the installed torch build targets (CUDA, ROCm, XPU or MPS); only a CPU
+        // build needs --cpu. This is the same resolution setup used to pick the build.
+        let torch_resolution = resolve_torch_variant(&gpu_info.gpu_type, gpu_info.cuda_version.as_deref(), std::env::consts::OS, std::env::consts::ARCH);
+        let use_cpu = torch_resolution.variant == TorchVariant::Cpu;
         if use_cpu {
-            comfyui_args.push("--cpu".to_string());
+            info!("Torch variant is CPU ({}), adding --cpu flag.", torch_resolution.reason);
+        } else {
+            info!("Torch variant is {} ({}), launching in GPU mode.", torch_resolution.variant.tag(), torch_resolution.reason);
         }
 
-        // 1. Capture the environment from Conda
-        info!("Capturing environment variables from conda env 'comfyui_env'...");
-        let env_capture_output = app_handle.shell()
-            .command(&conda_exe_path)
-            .args(&["run", "-n", "comfyui_env", "cmd", "/c", "set"])
-            .output()
-            .await
-            .map_err(|e| format!("Failed to capture conda environment: {}", e))?;
-
-        if !env_capture_output.status.success() {
-            return Err(format!("Failed to capture conda environment. Stderr: {}", String::from_utf8_lossy(&env_capture_output.stderr)));
+        if use_cpu {
+            comfyui_args.push("--cpu".to_string());
         }
 
-        let env_vars: HashMap<String, String> = String::from_utf8_lossy(&env_capture_output.stdout)
-            .lines()
-            .filter_map(|line| {
-                if let Some((key, value)) = line.split_once('=') {
-                    Some((key.to_string(), value.to_string()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        info!("Successfully captured {} environment variables.", env_vars.len());
+        let launch_profile = effective_launch_profile(&app_handle);
+        info!("Using launch profile '{}': {:?}", launch_profile.name, launch_profile.to_args());
+        comfyui_args.extend(launch_profile.to_args());
+
+        // 1. Capture the environment of the selected backend (conda env vars or venv activation vars)
+        let mut env_vars: HashMap<String, String> = get_launch_env_vars(&app_handle).await.map_err(|e| {
+            error!("{}", e);
+            emit_backend_status(&app_handle, "backend_error", e.clone(), true);
+            e
+        })?;
+        // The profile's variables go on top, so they can override the env's defaults.
+        env_vars.extend(launch_profile.env_vars.clone());
 
         // 2. Get python executable and spawn with the captured env
-        let python_exe_path = get_conda_env_python_executable_path(&app_handle, "comfyui_env").await?;
+        let python_exe_path = get_conda_env_python_executable_path(&app_handle, COMFYUI_ENV_NAME).await?;
+        if !python_exe_path.exists() {
+            let err_msg = format!("Python executable for the {:?} environment not found at: {}", env_backend, python_exe_path.display());
+            error!("{}", err_msg);
+            emit_backend_status(&app_handle, "backend_error", err_msg.clone(), true);
+            return Err(err_msg);
+        }
         info!("Direct Python executable path: {}", python_exe_path.display());
 
         let final_command = format!("{} {}", python_exe_path.to_string_lossy(), comfyui_args.join(" "));
@@ -163,10 +170,24 @@ pub(super) async fn spawn_comfyui_process(app_handle: AppHandle<Wry>) -> Result<
             .current_dir(&comfyui_dir)
             .envs(env_vars);
 
-        ProcessManager::spawn_managed_process(
+        // Parse the startup output into a per-node load report as it streams in.
+        clear_node_load_report(&app_handle);
+        // Dropped once the report is published; later output is runtime logging.
+        let mut node_load_parser = Some(NodeLoadParser::default());
+        let report_handle = app_handle.clone();
+        ProcessManager::spawn_managed_process_with_line_handler(
             &app_handle,
             "comfyui_sidecar".to_string(),
             command,
+            move |line, _is_stderr| {
+                for single_line in line.lines() {
+                    let Some(parser) = node_load_parser.as_mut() else { return };
+                    if let Some(report) = parser.observe(single_line) {
+                        publish_node_load_report(&report_handle, report);
+                        node_load_parser = None;
+                    }
+                }
+            },
         ).await?;
 
         info!("ComfyUI sidecar process has been handed off to the ProcessManager.");
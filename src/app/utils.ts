@@ -1,3 +1,5 @@
+import { getComfyUIEndpoint } from '@/services/comfyui/endpoint';
+
 export function generateComfyUIPromptData(promptData: {
   workflow_type: string;
   checkpoint_model: string;
@@ -103,8 +105,8 @@ export function generateComfyUIPromptData(promptData: {
 export async function verifyComfyUICommunication() {
   console.log("Attempting to verify ComfyUI communication...");
   try {
-    // Assuming ComfyUI is running on the default port 8188
-    const response = await fetch('http://127.0.0.1:8188/queue');
+    const { baseUrl } = await getComfyUIEndpoint();
+    const response = await fetch(`${baseUrl}/queue`);
     if (response.ok) {
       const data = await response.json();
       console.log("ComfyUI communication successful. /queue response:", data);
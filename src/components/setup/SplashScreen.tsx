@@ -258,6 +258,23 @@ const isStartingServicesRef = useRef(isStartingServices);
                 serviceStartupTimerId = null;
               }
               break;
+            case "incrementalSetupRequired":
+              const missing: { name: string }[] = inspectedPayload.data?.missing || [];
+              console.log(`[SplashScreen] setup_status: incrementalSetupRequired received. Missing components:`, missing);
+              setLoadingText(`Update required: ${missing.map(m => m.name).join(', ') || 'new components'}`);
+              setProgress(50);
+              setIsBackendReady(false);
+              setNavigateToSetup(true);
+              setIsStartingServices(false);
+              if (initialVerificationTimerId) {
+                clearTimeout(initialVerificationTimerId);
+                initialVerificationTimerId = null;
+              }
+              if (serviceStartupTimerId) {
+                clearTimeout(serviceStartupTimerId);
+                serviceStartupTimerId = null;
+              }
+              break;
             default:
               console.warn(`[SplashScreen] Received unhandled setup_status type: '${inspectedPayload.type}'. Full payload:`, inspectedPayload);
               setLoadingText(`Received unexpected status: ${inspectedPayload.type}`);
@@ -1,7 +1,7 @@
 import { fetch } from '@tauri-apps/plugin-http';
 import { ComfyUIWebSocketMessage, ImageOption } from './types'; // Assuming ImageOption might be used or adapted
 
-export const comfyuiApiUrl = 'http://127.0.0.1:8188';
+import { currentComfyUIEndpoint, getComfyUIEndpoint } from './endpoint';
 
 export interface PromptQueueResponse {
   prompt_id: string;
@@ -19,7 +19,7 @@ export async function queuePrompt(
   promptPayload: any, // Consider defining a more specific type for promptPayload
   clientId: string
 ): Promise<PromptQueueResponse> {
-  const httpResponse = await fetch(`${comfyuiApiUrl}/prompt`, {
+  const httpResponse = await fetch(`${(await getComfyUIEndpoint()).baseUrl}/prompt`, {
     method: 'POST',
     body: JSON.stringify({ prompt: promptPayload, client_id: clientId }),
     headers: { 'Content-Type': 'application/json' },
@@ -74,7 +74,7 @@ export async function uploadImageToComfyUI(
 
     console.log(`[ApiClient] Uploading image "${imageFile.name}" to ComfyUI. Subfolder: ${subfolder}, Type: ${type}, Overwrite: ${overwrite}`);
 
-    const response = await fetch(`${comfyuiApiUrl}/upload/image`, {
+    const response = await fetch(`${(await getComfyUIEndpoint()).baseUrl}/upload/image`, {
       method: 'POST',
       body: formData, // Pass FormData directly
       // Tauri's fetch with FormData usually sets the Content-Type header automatically.
@@ -104,7 +104,7 @@ export async function uploadImageToComfyUI(
  * @returns A Blob representing the image.
  */
 export async function getImageBlob(filename: string, subfolder: string, type: string): Promise<Blob> {
-    const imageUrl = `${comfyuiApiUrl}/view?filename=${encodeURIComponent(filename)}&subfolder=${encodeURIComponent(subfolder || '')}&type=${type}`;
+    const imageUrl = `${(await getComfyUIEndpoint()).baseUrl}/view?filename=${encodeURIComponent(filename)}&subfolder=${encodeURIComponent(subfolder || '')}&type=${type}`;
     // For binary data, fetch and then get ArrayBuffer
     const response = await fetch(imageUrl, { method: 'GET' });
 
@@ -123,5 +123,5 @@ export async function getImageBlob(filename: string, subfolder: string, type: st
  * @returns The full URL to view the image.
  */
 export function getImageUrl(filename: string, subfolder: string, type: 'output' | 'temp' | 'input'): string {
-    return `${comfyuiApiUrl}/view?filename=${encodeURIComponent(filename)}&subfolder=${encodeURIComponent(subfolder || '')}&type=${type}`;
+    return `${currentComfyUIEndpoint().baseUrl}/view?filename=${encodeURIComponent(filename)}&subfolder=${encodeURIComponent(subfolder || '')}&type=${type}`;
 }
\ No newline at end of file
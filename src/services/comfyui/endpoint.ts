@@ -0,0 +1,39 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { ComfyUiEndpoint } from '@/types/events';
+
+// The sidecar's port is configurable and may be reassigned at launch when it is taken,
+// so URLs are never hard-coded. The backend is the source of truth; this caches its answer
+// for synchronous callers (e.g. building <img> URLs) and follows the `comfyui-endpoint` event.
+const DEFAULT_PORT = 8188;
+
+let cachedEndpoint: ComfyUiEndpoint = {
+  port: DEFAULT_PORT,
+  baseUrl: `http://127.0.0.1:${DEFAULT_PORT}`,
+  wsUrl: `ws://127.0.0.1:${DEFAULT_PORT}/ws`,
+};
+
+if (typeof window !== 'undefined') {
+  listen<ComfyUiEndpoint>('comfyui-endpoint', (event) => {
+    cachedEndpoint = event.payload;
+  }).catch((err) => console.error('Failed to listen for comfyui-endpoint:', err));
+}
+
+/**
+ * Asks the backend where ComfyUI is listening and updates the cached endpoint.
+ */
+export async function getComfyUIEndpoint(): Promise<ComfyUiEndpoint> {
+  try {
+    cachedEndpoint = await invoke<ComfyUiEndpoint>('get_comfyui_endpoint');
+  } catch (err) {
+    console.error('Failed to get ComfyUI endpoint, using cached value:', err);
+  }
+  return cachedEndpoint;
+}
+
+/**
+ * The last known endpoint, for callers that cannot await.
+ */
+export function currentComfyUIEndpoint(): ComfyUiEndpoint {
+  return cachedEndpoint;
+}
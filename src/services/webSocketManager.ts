@@ -11,9 +11,7 @@ import {
 import useCharacterStore from '../store/characterStore';
 import { GenerationMode } from '../types/generation';
 import { invoke } from '@tauri-apps/api/core';
-
-export const comfyuiWsUrl = 'ws://127.0.0.1:8188/ws';
-export const comfyuiUrl = 'http://127.0.0.1:8188';
+import { currentComfyUIEndpoint } from './comfyui/endpoint';
 
 
 
@@ -36,7 +34,7 @@ export function createWebSocketManager(options: WebSocketManagerOptions) {
     onClose,
   } = options;
 
-  const socket = new WebSocket(`${comfyuiWsUrl}?clientId=${clientId}`);
+  const socket = new WebSocket(`${currentComfyUIEndpoint().wsUrl}?clientId=${clientId}`);
   // Binary data is no longer processed client-side.
   // socket.binaryType = 'arraybuffer';
 
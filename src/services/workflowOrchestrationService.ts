@@ -6,6 +6,7 @@ import { CharacterGenerationState } from '@/types/rust';
 import * as characterStateService from './characterStateService';
 import * as promptTemplateService from './promptTemplateService';
 import { createWebSocketManager } from './webSocketManager';
+import { getComfyUIEndpoint } from './comfyui/endpoint';
 
 interface GenerationResponse {
   prompt_id: string;
@@ -21,7 +22,9 @@ async function runGeneration(generationState: CharacterGenerationState) {
         // Log only the essential generation info (not the massive workflow JSON)
         console.log(`[GENERATION] Mode: ${generationState.generationMode}, Prompt: "${generationState.positivePrompt}", Seed: ${generationState.seed}`);
         const response = await invoke<GenerationResponse>('generate_character', { state: generationState });
-        
+        // Refresh the cached endpoint so the socket goes to the port the sidecar is actually on.
+        await getComfyUIEndpoint();
+
         createWebSocketManager({
             clientId: response.client_id,
             promptId: response.prompt_id,
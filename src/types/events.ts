@@ -123,6 +123,7 @@ export interface BackendStatusPayload {
 export type SetupStatusType =
   | 'InitialCheck'
   | 'FullSetupRequired'
+  | 'IncrementalSetupRequired'
   | 'QuickVerificationRequired'
   | 'BackendFullyVerifiedAndReady'
   | 'Error';
@@ -139,5 +140,245 @@ export interface SetupStatusPayload {
     gpuInfo?: BackendStatusPayload['gpuInfo']; // Re-use from BackendStatusPayload
     diskSpace?: BackendStatusPayload['diskSpace']; // Re-use from BackendStatusPayload
     isFirstRun?: boolean;
+    missing?: MissingComponent[]; // Present for IncrementalSetupRequired
   };
-}
\ No newline at end of file
+}
+
+export interface MissingComponent {
+  kind: 'model' | 'customNode' | 'pythonRequirements';
+  name: string;
+  reason: string;
+}
+// Payload of the `deep_verification_report` event and the `run_deep_verification` command
+export interface VerificationCheck {
+  category: 'model' | 'customNode' | 'pythonEnv' | 'pythonPackage' | 'torchDevice';
+  name: string;
+  status: 'passed' | 'warning' | 'failed';
+  details?: string;
+}
+
+export interface DeepVerificationReport {
+  passed: boolean;
+  checks: VerificationCheck[];
+  durationMs: number;
+}
+
+// Result of the post-install torch probe (`run_torch_device_probe`, `reinstall_torch_variant`)
+export interface TorchProbeResult {
+  version: string;
+  cudaAvailable: boolean;
+  cudaBuild?: string | null;
+  hipBuild?: string | null;
+  mpsAvailable: boolean;
+  xpuAvailable: boolean;
+  deviceName?: string | null;
+  vramBytes?: number | null;
+}
+
+// Payload of the `torch_device_mismatch` event
+export interface TorchDeviceMismatch {
+  gpu: { gpuType: 'Nvidia' | 'Amd' | 'Intel' | 'Unknown'; cudaVersion?: string | null };
+  expected: { variant: string; reason: string };
+  probe: TorchProbeResult;
+  message: string;
+  canReinstall: boolean;
+}
+
+export interface TorchDeviceReport {
+  probe: TorchProbeResult;
+  mismatch?: TorchDeviceMismatch | null;
+}
+
+// Payload of the `install-package-progress` event (package-level pip/uv/conda progress)
+export interface InstallPackageProgressPayload {
+  phase: string;
+  step: string;
+  stage: 'resolving' | 'downloading' | 'installing' | 'done';
+  currentPackage?: string | null;
+  packagesDone: number;
+  packagesTotal: number;
+  packagesTotalKnown: boolean; // false while pip is still resolving; packagesTotal only grows until then
+  bytesDownloaded: number;
+}
+
+// Returned by `list_env_snapshots` and `rollback_env_snapshot`
+export interface EnvSnapshot {
+  id: string;
+  createdAt: string;
+  step: string;
+  reason: string;
+  backend: 'conda' | 'venv';
+  knownGood: boolean;
+  hasCondaList: boolean;
+}
+
+// Returned by `check_custom_node_updates`
+export interface CustomNodeUpdateStatus {
+  name: string;
+  currentCommit?: string | null;
+  latestCommit?: string | null;
+  pinnedRevision?: string | null;
+  updateAvailable: boolean;
+  error?: string | null;
+}
+
+// Returned by `update_custom_node`, `update_all_custom_nodes` and `rollback_custom_node`
+export interface CustomNodeUpdateResult {
+  name: string;
+  previousCommit?: string | null;
+  newCommit?: string | null;
+  loads?: boolean | null;
+  loadError?: string | null;
+  error?: string | null;
+}
+
+export interface CustomNodeRevisionRecord {
+  commit: string;
+  replacedAt: string;
+  replacedBy: string;
+}
+
+export interface MissingNodePack {
+  name: string;
+  repoUrl?: string | null;
+  classTypes: string[];
+  installed: boolean; // On disk but ComfyUI did not load it
+}
+
+// Returned by `check_workflow_requirements`
+export interface WorkflowRequirements {
+  requiredPacks: string[];
+  missingPacks: MissingNodePack[];
+  unknownClassTypes: string[];
+  checkedAgainstComfyui: boolean;
+}
+
+// Returned by `list_available_custom_node_packs`
+export interface AvailableCustomNodePack {
+  id: string;
+  title: string;
+  author: string;
+  description: string;
+  repoUrl: string;
+  directoryName: string;
+  installType: string;
+  supported: boolean;
+  isCore: boolean;
+  installed: boolean;
+}
+
+// Returned by `list_user_custom_nodes` and `install_user_custom_node`
+export interface UserCustomNode {
+  id: string;
+  name: string;
+  title: string;
+  repoUrl: string;
+  installedAt: string;
+}
+
+export interface ParsedRequirement {
+  name: string;
+  requirement: string;
+  source: string; // "ComfyUI", a custom node directory name, or "constraints"
+}
+
+export interface RequirementConflict {
+  kind: 'incompatibleVersions' | 'mutuallyExclusivePackages' | 'constraintViolation';
+  package: string;
+  requirements: ParsedRequirement[];
+  message: string;
+}
+
+// Returned by `check_requirement_conflicts`, also the `requirement-conflicts` event payload
+export interface RequirementConflictReport {
+  sources: string[];
+  constraintsFile?: string | null;
+  conflicts: RequirementConflict[];
+}
+
+export interface CustomNodeLoadStatus {
+  name: string;
+  path: string;
+  success: boolean;
+  loadTimeSecs?: number | null;
+  error?: string | null;
+  tracebackExcerpt?: string | null;
+}
+
+// Returned by `get_custom_node_load_report`, also the `custom-node-load-report` event payload
+export interface NodeLoadReport {
+  nodes: CustomNodeLoadStatus[];
+  failedCount: number;
+  generatedAt: string;
+}
+
+// Returned by `get_comfyui_endpoint`, also the `comfyui-endpoint` event payload
+export interface ComfyUiEndpoint {
+  port: number;
+  baseUrl: string;
+  wsUrl: string;
+}
+
+// Returned by `get_lan_access_info` and `set_lan_access_enabled`
+export interface LanAccessInfo {
+  enabled: boolean;
+  running: boolean;
+  port: number;
+  token?: string | null;
+}
+
+export type BackendMode = 'managed' | 'external';
+
+export interface MissingModelFile {
+  classType: string;
+  input: string;
+  file: string;
+}
+
+// Returned by `check_external_comfyui`
+export interface ExternalCapabilityReport {
+  baseUrl: string;
+  nodePacks: WorkflowRequirements;
+  missingModels: MissingModelFile[];
+  ready: boolean;
+}
+
+export type VramMode = 'auto' | 'low' | 'normal' | 'high';
+export type ForcePrecision = 'auto' | 'fp16' | 'fp32';
+export type UnetPrecision = 'auto' | 'fp16' | 'bf16' | 'fp8E4m3fn';
+export type VaePrecision = 'auto' | 'fp16' | 'fp32' | 'bf16' | 'cpu';
+export type AttentionImpl = 'auto' | 'pytorch' | 'split' | 'quad' | 'sage';
+export type PreviewMethod = 'none' | 'auto' | 'latent2Rgb' | 'taesd';
+
+export interface LaunchProfile {
+  name: string;
+  vramMode: VramMode;
+  forcePrecision: ForcePrecision;
+  unetPrecision: UnetPrecision;
+  vaePrecision: VaePrecision;
+  attention: AttentionImpl;
+  previewMethod: PreviewMethod;
+  extraArgs: string[];
+  envVars: Record<string, string>;
+}
+
+// Returned by `get_launch_profiles` and `set_launch_profile`
+export interface LaunchProfileOptions {
+  active: LaunchProfile;
+  isAutomatic: boolean;
+  detectedVramMb?: number | null;
+  presets: LaunchProfile[];
+}
+
+// Payload of `orphaned-processes-detected`; returned by `get_orphaned_processes`
+export interface ProcessRecord {
+  name: string;
+  pid: number;
+  startTime: string;
+  commandLine: string;
+  recordedAt: number;
+  appPid: number;
+}
+
+// Passed to `resolve_orphaned_process`
+export type OrphanAction = 'adopt' | 'kill';